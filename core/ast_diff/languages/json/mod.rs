@@ -0,0 +1,242 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::ast_diff::{assign_qualified_names, AstNode, AstParser};
+use crate::ShadowError;
+
+#[derive(Clone, Default)]
+pub struct JsonParser {
+    // Character scanner over objects and arrays; `//` and `/* */` comments and trailing
+    // commas are accepted so tsconfig-style files parse too
+}
+
+impl JsonParser {
+    pub fn new() -> Self {
+        JsonParser {}
+    }
+}
+
+impl AstParser for JsonParser {
+    fn parse(&self, content: &str) -> Result<AstNode, ShadowError> {
+        let mut scanner = Scanner { chars: content.chars().collect(), pos: 0, line: 1 };
+
+        scanner.skip_whitespace();
+        let value = if scanner.peek().is_some() {
+            let value = scanner.parse_value()?;
+            scanner.skip_whitespace();
+            if scanner.peek().is_some() {
+                return Err(scanner.error("Unexpected content after the top-level value"));
+            }
+            value
+        } else {
+            Value { text: String::new(), children: Vec::new() }
+        };
+
+        let mut root = AstNode {
+            node_type: "Program".to_string(),
+            name: None,
+            start_line: 1,
+            end_line: content.lines().count() as u32,
+            children: value.children,
+            body_hash: Some(value_hash(&value.text)),
+            modifiers: Vec::new(),
+            qualified_name: None,
+        };
+        assign_qualified_names(&mut root);
+        Ok(root)
+    }
+
+    fn supported_extensions(&self) -> Vec<&'static str> {
+        vec!["json"]
+    }
+}
+
+// A node's hash covers its own scalar value only; objects and arrays leave their
+// entries to their children, so a changed key doesn't also mark every parent as modified
+pub(crate) fn value_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for token in text.split_whitespace() {
+        token.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+pub(crate) fn property(node_type: &str, name: String, start_line: u32) -> AstNode {
+    AstNode {
+        node_type: node_type.to_string(),
+        name: Some(name),
+        start_line,
+        end_line: start_line,
+        children: Vec::new(),
+        body_hash: None,
+        modifiers: Vec::new(),
+        qualified_name: None,
+    }
+}
+
+// A parsed value: scalars keep their source text, containers their entries
+struct Value {
+    text: String,
+    children: Vec<AstNode>,
+}
+
+struct Scanner {
+    chars: Vec<char>,
+    pos: usize,
+    line: u32,
+}
+
+impl Scanner {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, message: &str) -> ShadowError {
+        ShadowError::Parse(format!("{} on line {}", message, self.line))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.bump();
+            } else if c == '/' && self.chars.get(self.pos + 1) == Some(&'/') {
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.bump();
+                }
+            } else if c == '/' && self.chars.get(self.pos + 1) == Some(&'*') {
+                self.pos += 2;
+                while self.peek().is_some() && !(self.peek() == Some('*') && self.chars.get(self.pos + 1) == Some(&'/')) {
+                    self.bump();
+                }
+                self.pos = (self.pos + 2).min(self.chars.len());
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value, ShadowError> {
+        match self.peek() {
+            Some('{') => self.parse_object(),
+            Some('[') => self.parse_array(),
+            Some('"') => Ok(Value { text: self.parse_string()?, children: Vec::new() }),
+            Some(_) => {
+                let mut text = String::new();
+                while let Some(c) = self.peek().filter(|c| !c.is_whitespace() && !matches!(c, ',' | '}' | ']')) {
+                    text.push(c);
+                    self.bump();
+                }
+                if text.is_empty() {
+                    return Err(self.error("Expected a value"));
+                }
+                Ok(Value { text, children: Vec::new() })
+            }
+            None => Err(self.error("Unexpected end of input")),
+        }
+    }
+
+    // Each key becomes a `Property` node spanning the key through the end of its value
+    fn parse_object(&mut self) -> Result<Value, ShadowError> {
+        self.bump();
+        let mut children = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('}') => {
+                    self.bump();
+                    break;
+                }
+                Some('"') => {}
+                _ => return Err(self.error("Expected a key or '}'")),
+            }
+
+            let start_line = self.line;
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            if self.bump() != Some(':') {
+                return Err(self.error("Expected ':'"));
+            }
+            self.skip_whitespace();
+
+            let name = key[1..key.len() - 1].to_string();
+            children.push(self.parse_entry("Property", name, start_line)?);
+            self.skip_separator('}')?;
+        }
+
+        Ok(Value { text: "{}".to_string(), children })
+    }
+
+    // Array items become `Element` nodes named by their index
+    fn parse_array(&mut self) -> Result<Value, ShadowError> {
+        self.bump();
+        let mut children = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(']') {
+                self.bump();
+                break;
+            }
+
+            let start_line = self.line;
+            let index = children.len();
+            children.push(self.parse_entry("Element", index.to_string(), start_line)?);
+            self.skip_separator(']')?;
+        }
+
+        Ok(Value { text: "[]".to_string(), children })
+    }
+
+    fn parse_entry(&mut self, node_type: &str, name: String, start_line: u32) -> Result<AstNode, ShadowError> {
+        let value = self.parse_value()?;
+        let mut node = property(node_type, name, start_line);
+        node.end_line = self.line;
+        node.children = value.children;
+        node.body_hash = Some(value_hash(&value.text));
+        Ok(node)
+    }
+
+    // Consumes the `,` after an entry, or leaves the closing bracket for the caller
+    fn skip_separator(&mut self, closing: char) -> Result<(), ShadowError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(',') => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) if c == closing => Ok(()),
+            _ => Err(self.error(&format!("Expected ',' or '{}'", closing))),
+        }
+    }
+
+    // Returns the string with its quotes and escapes as written
+    fn parse_string(&mut self) -> Result<String, ShadowError> {
+        let mut text = String::new();
+        text.extend(self.bump());
+
+        loop {
+            match self.bump() {
+                Some('\\') => {
+                    text.push('\\');
+                    text.extend(self.bump());
+                }
+                Some('"') => {
+                    text.push('"');
+                    return Ok(text);
+                }
+                Some('\n') | None => return Err(self.error("Unterminated string")),
+                Some(c) => text.push(c),
+            }
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod json;
+pub mod python;
+pub mod rust;
+pub mod toml;
+pub mod ts;
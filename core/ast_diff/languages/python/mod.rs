@@ -0,0 +1,162 @@
+use crate::ast_diff::{assign_body_hashes, assign_qualified_names, AstNode, AstParser};
+use crate::ShadowError;
+
+#[derive(Clone, Default)]
+pub struct PythonParser {
+    // Indentation-based line scanner, no full grammar
+}
+
+impl PythonParser {
+    pub fn new() -> Self {
+        PythonParser {}
+    }
+
+    fn parse_simple(&self, content: &str) -> AstNode {
+        let mut root = AstNode {
+            node_type: "Program".to_string(),
+            name: None,
+            start_line: 1,
+            end_line: content.lines().count() as u32,
+            children: Vec::new(),
+            body_hash: None,
+            modifiers: Vec::new(),
+            qualified_name: None,
+        };
+
+        // Open defs/classes with the column they were declared at
+        let mut open: Vec<(usize, AstNode)> = Vec::new();
+        let mut last_code_line = 0;
+        let mut bracket_depth: i32 = 0;
+
+        for (index, line) in content.lines().enumerate() {
+            let current_line = index as u32 + 1;
+            let code = line.split('#').next().unwrap_or("");
+            let trimmed = code.trim();
+
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            // Continuation lines of a bracketed expression never close a block
+            let continuation = bracket_depth > 0;
+            bracket_depth += Self::bracket_delta(code);
+            if continuation {
+                last_code_line = current_line;
+                continue;
+            }
+
+            // Dedenting back to (or past) a def's column closes it
+            let indent = line.len() - line.trim_start().len();
+            while open.last().is_some_and(|(column, _)| *column >= indent) {
+                Self::close(&mut open, &mut root, last_code_line);
+            }
+            last_code_line = current_line;
+
+            if let Some(func_name) = self.extract_function_name(trimmed) {
+                open.push((indent, Self::node("FunctionDeclaration", func_name, current_line)));
+            } else if let Some(class_name) = self.extract_class_name(trimmed) {
+                open.push((indent, Self::node("ClassDeclaration", class_name, current_line)));
+            } else {
+                for module_name in self.extract_import_names(trimmed) {
+                    let import = Self::node("ImportDeclaration", module_name, current_line);
+                    match open.last_mut() {
+                        Some((_, parent)) => parent.children.push(import),
+                        None => root.children.push(import),
+                    }
+                }
+            }
+        }
+
+        while !open.is_empty() {
+            Self::close(&mut open, &mut root, last_code_line);
+        }
+
+        let lines: Vec<&str> = content.lines().collect();
+        assign_body_hashes(&mut root, &lines);
+        assign_qualified_names(&mut root);
+
+        root
+    }
+
+    fn node(node_type: &str, name: String, line: u32) -> AstNode {
+        AstNode {
+            node_type: node_type.to_string(),
+            name: Some(name),
+            start_line: line,
+            end_line: line,
+            children: Vec::new(),
+            body_hash: None,
+            modifiers: Vec::new(),
+            qualified_name: None,
+        }
+    }
+
+    fn close(open: &mut Vec<(usize, AstNode)>, root: &mut AstNode, end_line: u32) {
+        if let Some((_, mut node)) = open.pop() {
+            node.end_line = end_line.max(node.start_line);
+            match open.last_mut() {
+                Some((_, parent)) => parent.children.push(node),
+                None => root.children.push(node),
+            }
+        }
+    }
+
+    fn bracket_delta(code: &str) -> i32 {
+        code.chars()
+            .map(|c| match c {
+                '(' | '[' | '{' => 1,
+                ')' | ']' | '}' => -1,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    fn extract_function_name(&self, line: &str) -> Option<String> {
+        let rest = line.strip_prefix("async ").unwrap_or(line);
+        let rest = rest.strip_prefix("def ")?;
+        let name = rest.split('(').next()?.trim();
+        if name.is_empty() {
+            return None;
+        }
+        Some(name.to_string())
+    }
+
+    fn extract_class_name(&self, line: &str) -> Option<String> {
+        let rest = line.strip_prefix("class ")?;
+        let name = rest.split(['(', ':']).next()?.trim();
+        if name.is_empty() {
+            return None;
+        }
+        Some(name.to_string())
+    }
+
+    fn extract_import_names(&self, line: &str) -> Vec<String> {
+        // `from pkg.mod import a, b` is one import of `pkg.mod`
+        if let Some(rest) = line.strip_prefix("from ") {
+            if let Some((module_name, _)) = rest.split_once(" import") {
+                return vec![module_name.trim().to_string()];
+            }
+            return Vec::new();
+        }
+
+        // `import a.b as c, d` imports each listed module
+        if let Some(rest) = line.strip_prefix("import ") {
+            return rest.split(',')
+                .filter_map(|entry| entry.split_whitespace().next())
+                .map(|module_name| module_name.to_string())
+                .collect();
+        }
+
+        Vec::new()
+    }
+}
+
+impl AstParser for PythonParser {
+    fn parse(&self, content: &str) -> Result<AstNode, ShadowError> {
+        Ok(self.parse_simple(content))
+    }
+
+    fn supported_extensions(&self) -> Vec<&'static str> {
+        vec!["py", "pyi"]
+    }
+}
@@ -0,0 +1,313 @@
+use crate::ast_diff::{assign_body_hashes, assign_qualified_names, AstNode, AstParser};
+use crate::ShadowError;
+
+#[derive(Clone, Default)]
+pub struct RustParser {
+    // Brace-counting item scanner, no full grammar
+}
+
+impl RustParser {
+    pub fn new() -> Self {
+        RustParser {}
+    }
+
+    fn parse_simple(&self, content: &str) -> AstNode {
+        let mut root = AstNode {
+            node_type: "Program".to_string(),
+            name: None,
+            start_line: 1,
+            end_line: content.lines().count() as u32,
+            children: Vec::new(),
+            body_hash: None,
+            modifiers: Vec::new(),
+            qualified_name: None,
+        };
+
+        // Items whose closing brace hasn't been seen yet, with the depth inside their body
+        let mut open: Vec<(AstNode, Option<usize>)> = Vec::new();
+        let mut depth = 0;
+
+        // Items are matched on code only, so comments and string contents never produce nodes
+        let code = strip_comments_and_strings(content);
+
+        for (index, code_line) in code.lines().enumerate() {
+            let current_line = index as u32 + 1;
+            let trimmed = code_line.trim();
+
+            // Functions directly inside an impl or trait body are its methods
+            let in_impl_body = matches!(open.last(), Some((node, Some(body_depth)))
+                if matches!(node.node_type.as_str(), "ImplBlock" | "TraitDeclaration") && *body_depth == depth);
+
+            if let Some((node_type, name)) = self.extract_item(trimmed) {
+                let node_type = if in_impl_body && node_type == "FunctionDeclaration" {
+                    "MethodDefinition"
+                } else {
+                    node_type
+                };
+                open.push((Self::node(node_type, name, current_line), None));
+            }
+
+            for c in code_line.chars() {
+                if c == '{' {
+                    depth += 1;
+                    // The first brace after an item opens its body
+                    if let Some(pending) = open.iter_mut().rev().find(|(_, body_depth)| body_depth.is_none()) {
+                        pending.1 = Some(depth);
+                    }
+                } else if c == '}' {
+                    if matches!(open.last(), Some((_, Some(body_depth))) if *body_depth == depth) {
+                        Self::close(&mut open, &mut root, current_line);
+                    }
+                    depth = depth.saturating_sub(1);
+                }
+            }
+
+            // Bodiless items (`mod a;`, `struct Unit;`, trait method signatures) end on their own line
+            if trimmed.ends_with(';') {
+                while matches!(open.last(), Some((_, None))) {
+                    Self::close(&mut open, &mut root, current_line);
+                }
+            }
+        }
+
+        // Unterminated bodies run to the end of the file
+        while let Some((node, body_depth)) = open.last() {
+            let end_line = match body_depth {
+                Some(_) => root.end_line,
+                None => node.start_line,
+            };
+            Self::close(&mut open, &mut root, end_line);
+        }
+
+        let lines: Vec<&str> = content.lines().collect();
+        assign_body_hashes(&mut root, &lines);
+        assign_qualified_names(&mut root);
+
+        root
+    }
+
+    fn node(node_type: &str, name: String, line: u32) -> AstNode {
+        AstNode {
+            node_type: node_type.to_string(),
+            name: Some(name),
+            start_line: line,
+            end_line: line,
+            children: Vec::new(),
+            body_hash: None,
+            modifiers: Vec::new(),
+            qualified_name: None,
+        }
+    }
+
+    // Finishes the innermost open item and attaches it to its enclosing one
+    fn close(open: &mut Vec<(AstNode, Option<usize>)>, root: &mut AstNode, end_line: u32) {
+        if let Some((mut node, _)) = open.pop() {
+            node.end_line = end_line.max(node.start_line);
+            match open.last_mut() {
+                Some((parent, _)) => parent.children.push(node),
+                None => root.children.push(node),
+            }
+        }
+    }
+
+    // The node type and name of an item declared on this line. `impl` blocks are named
+    // after what they implement: `Display for Point`, or just `Point`.
+    fn extract_item(&self, line: &str) -> Option<(&'static str, String)> {
+        let rest = strip_visibility(line);
+
+        // Qualifiers that may precede `fn`; string contents are already blanked, so
+        // `extern "C"` shows up with an empty ABI string
+        let mut rest = rest;
+        loop {
+            let stripped = ["const ", "async ", "unsafe ", "default ", "extern \"\" ", "extern \" \" ", "extern "]
+                .iter()
+                .find_map(|qualifier| rest.strip_prefix(qualifier));
+            match stripped {
+                Some(stripped) if !stripped.starts_with(['{', '"']) => rest = stripped.trim_start(),
+                _ => break,
+            }
+        }
+
+        for (keyword, node_type) in [
+            ("fn ", "FunctionDeclaration"),
+            ("struct ", "StructDeclaration"),
+            ("enum ", "EnumDeclaration"),
+            ("trait ", "TraitDeclaration"),
+            ("mod ", "ModuleDeclaration"),
+        ] {
+            if let Some(after) = rest.strip_prefix(keyword) {
+                let name: String = after.trim_start().chars().take_while(|c| is_identifier_char(*c)).collect();
+                return (!name.is_empty()).then_some((node_type, name));
+            }
+        }
+
+        let after = rest.strip_prefix("impl")?;
+        if !after.starts_with([' ', '<']) {
+            return None;
+        }
+        let target = skip_generics(after.trim_start());
+        let target = target.split(" where").next().unwrap_or(target);
+        let target = target.split('{').next().unwrap_or(target).trim();
+        (!target.is_empty()).then(|| ("ImplBlock", target.split_whitespace().collect::<Vec<_>>().join(" ")))
+    }
+}
+
+impl AstParser for RustParser {
+    fn parse(&self, content: &str) -> Result<AstNode, ShadowError> {
+        Ok(self.parse_simple(content))
+    }
+
+    fn supported_extensions(&self) -> Vec<&'static str> {
+        vec!["rs"]
+    }
+}
+
+// Drops `pub`, `pub(crate)`, `pub(super)` and `pub(in path)`
+fn strip_visibility(line: &str) -> &str {
+    let Some(rest) = line.strip_prefix("pub") else {
+        return line;
+    };
+    if let Some(rest) = rest.strip_prefix(' ') {
+        return rest.trim_start();
+    }
+    match rest.trim_start().strip_prefix('(') {
+        Some(scoped) => scoped.split_once(')').map_or(line, |(_, rest)| rest.trim_start()),
+        None => line,
+    }
+}
+
+// Skips a leading `<...>` generic parameter list
+fn skip_generics(text: &str) -> &str {
+    if !text.starts_with('<') {
+        return text;
+    }
+
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return text[i + 1..].trim_start();
+                }
+            }
+            _ => {}
+        }
+    }
+    ""
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// Blanks comments (nested block comments included) and the contents of string, raw
+// string and char literals with spaces, keeping quotes and newlines so line numbers
+// stay the same. Lifetimes like `'a` are left alone.
+fn strip_comments_and_strings(content: &str) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let mut code = String::with_capacity(content.len());
+    let blank = |c: char| if c == '\n' { '\n' } else { ' ' };
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let previous_is_ident = i > 0 && is_identifier_char(chars[i - 1]);
+
+        if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                code.push(' ');
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            let mut nesting = 0;
+            while i < chars.len() {
+                if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+                    nesting += 1;
+                    code.push_str("  ");
+                    i += 2;
+                } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                    nesting -= 1;
+                    code.push_str("  ");
+                    i += 2;
+                    if nesting == 0 {
+                        break;
+                    }
+                } else {
+                    code.push(blank(chars[i]));
+                    i += 1;
+                }
+            }
+        } else if c == 'r' && !previous_is_ident && raw_string_hashes(&chars[i + 1..]).is_some() {
+            // r"..." or r#"..."#: no escapes, ends at a quote followed by as many hashes
+            let hashes = raw_string_hashes(&chars[i + 1..]).unwrap_or(0);
+            let opening = 2 + hashes;
+            code.extend(&chars[i..i + opening]);
+            i += opening;
+            while i < chars.len() {
+                let closes = chars[i] == '"' && (0..hashes).all(|h| chars.get(i + 1 + h) == Some(&'#'));
+                if closes {
+                    code.extend(&chars[i..i + 1 + hashes]);
+                    i += 1 + hashes;
+                    break;
+                }
+                code.push(blank(chars[i]));
+                i += 1;
+            }
+        } else if c == '"' {
+            code.push('"');
+            i += 1;
+            while i < chars.len() {
+                match chars[i] {
+                    '\\' => {
+                        code.push(' ');
+                        if let Some(&escaped) = chars.get(i + 1) {
+                            code.push(blank(escaped));
+                        }
+                        i += 2;
+                    }
+                    '"' => {
+                        code.push('"');
+                        i += 1;
+                        break;
+                    }
+                    other => {
+                        code.push(blank(other));
+                        i += 1;
+                    }
+                }
+            }
+        } else if c == '\'' && char_literal_len(&chars[i..]).is_some() {
+            let len = char_literal_len(&chars[i..]).unwrap_or(1);
+            code.push('\'');
+            code.extend(std::iter::repeat_n(' ', len - 2));
+            code.push('\'');
+            i += len;
+        } else {
+            code.push(c);
+            i += 1;
+        }
+    }
+
+    code
+}
+
+// Number of `#`s in a raw string opening (`"`, `#"`, `##"`, ...) right after the `r`
+fn raw_string_hashes(rest: &[char]) -> Option<usize> {
+    let hashes = rest.iter().take_while(|c| **c == '#').count();
+    (rest.get(hashes) == Some(&'"')).then_some(hashes)
+}
+
+// Length of a char literal starting at `'`, or None for a lifetime
+fn char_literal_len(rest: &[char]) -> Option<usize> {
+    match rest.get(1)? {
+        '\\' => {
+            let end = rest.iter().skip(2).position(|c| *c == '\'')? + 2;
+            (end <= 10).then_some(end + 1)
+        }
+        '\n' => None,
+        _ => (rest.get(2) == Some(&'\'')).then_some(3),
+    }
+}
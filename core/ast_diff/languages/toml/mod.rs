@@ -0,0 +1,219 @@
+use crate::ast_diff::languages::json::{property, value_hash};
+use crate::ast_diff::{assign_qualified_names, AstNode, AstParser};
+use crate::ShadowError;
+
+#[derive(Clone, Default)]
+pub struct TomlParser {
+    // Line scanner over tables and key/value pairs, no full grammar. Inline arrays and
+    // tables are compared as a single value.
+}
+
+impl TomlParser {
+    pub fn new() -> Self {
+        TomlParser {}
+    }
+
+    fn parse_simple(&self, content: &str) -> AstNode {
+        let mut root = AstNode {
+            node_type: "Program".to_string(),
+            name: None,
+            start_line: 1,
+            end_line: content.lines().count() as u32,
+            children: Vec::new(),
+            body_hash: Some(value_hash("")),
+            modifiers: Vec::new(),
+            qualified_name: None,
+        };
+
+        // Keys of the table the following pairs belong to
+        let mut table: Vec<String> = Vec::new();
+        // A value still open across lines: its node path, start line and text so far
+        let mut pending: Option<(Vec<String>, u32, String)> = None;
+
+        for (index, line) in content.lines().enumerate() {
+            let current_line = index as u32 + 1;
+
+            if let Some((path, start_line, mut text)) = pending.take() {
+                text.push('\n');
+                text.push_str(strip_comment(line));
+                if value_complete(&text) {
+                    insert_pair(&mut root, &path, start_line, current_line, &text);
+                } else {
+                    pending = Some((path, start_line, text));
+                }
+                continue;
+            }
+
+            let trimmed = strip_comment(line).trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            if let Some(header) = trimmed.strip_prefix("[[").and_then(|rest| rest.strip_suffix("]]")) {
+                // Each `[[name]]` appends an `Element` to the array of tables
+                table = split_key(header);
+                let array = entry(&mut root, &table, current_line);
+                let element = property("Element", array.children.len().to_string(), current_line);
+                array.children.push(element);
+            } else if let Some(header) = trimmed.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+                table = split_key(header);
+                entry(&mut root, &table, current_line);
+            } else if let Some((key, value)) = split_pair(trimmed) {
+                let mut path = table.clone();
+                path.extend(split_key(key));
+                let value = value.trim();
+                if value_complete(value) {
+                    insert_pair(&mut root, &path, current_line, current_line, value);
+                } else {
+                    pending = Some((path, current_line, value.to_string()));
+                }
+            }
+        }
+
+        // An unterminated value runs to the end of the file
+        if let Some((path, start_line, text)) = pending {
+            let end_line = root.end_line;
+            insert_pair(&mut root, &path, start_line, end_line, &text);
+        }
+
+        extend_end_lines(&mut root);
+        assign_qualified_names(&mut root);
+        root
+    }
+}
+
+impl AstParser for TomlParser {
+    fn parse(&self, content: &str) -> Result<AstNode, ShadowError> {
+        Ok(self.parse_simple(content))
+    }
+
+    fn supported_extensions(&self) -> Vec<&'static str> {
+        vec!["toml"]
+    }
+}
+
+// Walks `path` from `root`, creating tables as needed. Arrays of tables continue into
+// their most recent element.
+fn entry<'a>(root: &'a mut AstNode, path: &[String], line: u32) -> &'a mut AstNode {
+    let mut node = root;
+    for key in path {
+        if node.children.last().is_some_and(|child| child.node_type == "Element") {
+            node = node.children.last_mut().unwrap();
+        }
+
+        let position = node.children.iter().position(|child| child.name.as_deref() == Some(key.as_str()));
+        let position = position.unwrap_or_else(|| {
+            let mut table = property("Property", key.clone(), line);
+            table.body_hash = Some(value_hash("{}"));
+            node.children.push(table);
+            node.children.len() - 1
+        });
+        node = &mut node.children[position];
+    }
+    node
+}
+
+fn insert_pair(root: &mut AstNode, path: &[String], start_line: u32, end_line: u32, value: &str) {
+    let Some((key, parents)) = path.split_last() else {
+        return;
+    };
+
+    let mut pair = property("Property", key.clone(), start_line);
+    pair.end_line = end_line.max(start_line);
+    pair.body_hash = Some(value_hash(value));
+
+    let parent = entry(root, parents, start_line);
+    if parent.children.last().is_some_and(|child| child.node_type == "Element") {
+        parent.children.last_mut().unwrap().children.push(pair);
+    } else {
+        parent.children.push(pair);
+    }
+}
+
+// Tables end where their last pair does
+fn extend_end_lines(node: &mut AstNode) {
+    for child in node.children.iter_mut() {
+        extend_end_lines(child);
+        node.end_line = node.end_line.max(child.end_line);
+    }
+}
+
+// Splits `key = value` at the first `=` outside quotes
+fn split_pair(line: &str) -> Option<(&str, &str)> {
+    let mut quote: Option<char> = None;
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '=' => return Some((&line[..i], &line[i + 1..])),
+            None => {}
+        }
+    }
+    None
+}
+
+// `a."b.c".d` is the three keys `a`, `b.c` and `d`
+fn split_key(key: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+
+    for c in key.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '.' => keys.push(std::mem::take(&mut current).trim().to_string()),
+            None => current.push(c),
+        }
+    }
+    keys.push(current.trim().to_string());
+    keys
+}
+
+// Drops a `#` comment that isn't inside a string
+fn strip_comment(line: &str) -> &str {
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' => return &line[..i],
+            None => {}
+        }
+    }
+    line
+}
+
+// Whether a value's brackets and multi-line strings are all closed
+fn value_complete(value: &str) -> bool {
+    for delimiter in ["\"\"\"", "'''"] {
+        if value.trim_start().starts_with(delimiter) {
+            return value.trim_start()[3..].contains(delimiter);
+        }
+    }
+
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for c in value.chars() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth -= 1,
+                _ => {}
+            },
+        }
+    }
+    depth <= 0
+}
@@ -1,15 +1,23 @@
-use crate::ast_diff::{AstNode, AstParser};
+use crate::ast_diff::{assign_body_hashes, assign_qualified_names, AstNode, AstParser};
+use crate::ShadowError;
 use serde_json::Value;
 use std::collections::HashMap;
 
 #[derive(Clone)]
 pub struct TypeScriptParser {
     // Simple regex-based parser for prototype
+    // Reject input with unbalanced brackets instead of parsing what it can
+    strict: bool,
 }
 
 impl TypeScriptParser {
     pub fn new() -> Self {
-        TypeScriptParser {}
+        TypeScriptParser { strict: false }
+    }
+
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
     }
 
     fn parse_simple(&self, content: &str) -> AstNode {
@@ -19,82 +27,319 @@ impl TypeScriptParser {
             start_line: 1,
             end_line: content.lines().count() as u32,
             children: Vec::new(),
+            body_hash: None,
+            modifiers: Vec::new(),
+            qualified_name: None,
         };
 
+        // Declarations whose closing brace hasn't been seen yet, with the depth inside their body
+        let mut open: Vec<(AstNode, Option<usize>)> = Vec::new();
+        let mut scan = BraceScanner::default();
+        let mut depth = 0;
         let mut current_line = 1;
+        let mut last_code_line = 0;
+        let mut previous = "";
+        // Start line of an import whose module specifier hasn't been seen yet
+        let mut pending_import: Option<u32> = None;
+
+        // Declarations are matched on code only, so commented-out code and strings
+        // that merely look like declarations don't produce nodes
+        let code = strip_comments_and_strings(content);
         
-        for line in content.lines() {
-            let trimmed = line.trim();
-            
-            // Parse function declarations
-            if let Some(func_name) = self.extract_function_name(trimmed) {
-                root.children.push(AstNode {
-                    node_type: "FunctionDeclaration".to_string(),
-                    name: Some(func_name),
-                    start_line: current_line,
-                    end_line: current_line,
-                    children: Vec::new(),
-                });
+        for (line, code_line) in content.lines().zip(code.lines()) {
+            let trimmed = code_line.trim();
+
+            // A type alias without a body or `;` ends once the next line stops continuing it
+            if !trimmed.is_empty() {
+                let continues = trimmed.starts_with(['|', '&', '{'])
+                    || previous.ends_with(['=', '|', '&', '<', ',', '(']);
+                if matches!(open.last(), Some((node, None)) if node.node_type == "TypeAlias") && !continues {
+                    Self::close(&mut open, &mut root, last_code_line);
+                }
             }
+
+            // Members declared directly in a class body become its methods
+            let in_class_body = matches!(open.last(), Some((node, Some(body_depth)))
+                if node.node_type == "ClassDeclaration" && *body_depth == depth);
             
-            // Parse class declarations
-            if let Some(class_name) = self.extract_class_name(trimmed) {
-                root.children.push(AstNode {
-                    node_type: "ClassDeclaration".to_string(),
-                    name: Some(class_name),
-                    start_line: current_line,
-                    end_line: current_line,
-                    children: Vec::new(),
-                });
+            if in_class_body {
+                if let Some(method_name) = self.extract_method_name(trimmed) {
+                    let mut method = Self::node("MethodDefinition", method_name, current_line);
+                    method.modifiers = self.extract_modifiers(trimmed);
+                    open.push((method, None));
+                }
+            } else {
+                // Parse function declarations
+                if let Some(func_name) = self.extract_function_name(trimmed) {
+                    let mut function = Self::node("FunctionDeclaration", func_name, current_line);
+                    function.modifiers = self.extract_modifiers(trimmed);
+                    open.push((function, None));
+                }
+                
+                // `export default function () {}` and `export default class {}` have no name
+                if let Some(node_type) = self.extract_anonymous_default(trimmed) {
+                    let node = AstNode {
+                        name: None,
+                        modifiers: self.extract_modifiers(trimmed),
+                        ..Self::node(node_type, String::new(), current_line)
+                    };
+                    open.push((node, None));
+                }
+
+                // Parse class declarations
+                if let Some(class_name) = self.extract_class_name(trimmed) {
+                    open.push((Self::node("ClassDeclaration", class_name, current_line), None));
+                }
+
+                // Parse interface declarations
+                if let Some(interface_name) = self.extract_interface_name(trimmed) {
+                    open.push((Self::node("InterfaceDeclaration", interface_name, current_line), None));
+                }
+
+                // Parse type aliases
+                if let Some(alias_name) = self.extract_type_alias_name(trimmed) {
+                    open.push((Self::node("TypeAlias", alias_name, current_line), None));
+                }
+                
+                // Parse import statements, which may span several lines. The node is named
+                // after the module, which is a string, so it is read from the original line.
+                if pending_import.is_none() && trimmed.starts_with("import ") {
+                    pending_import = Some(current_line);
+                }
+                if let Some(start_line) = pending_import {
+                    if let Some(import_name) = self.extract_import_name(line.trim()) {
+                        let mut import = Self::node("ImportDeclaration", import_name, start_line);
+                        import.end_line = current_line;
+                        match open.last_mut() {
+                            Some((parent, _)) => parent.children.push(import),
+                            None => root.children.push(import),
+                        }
+                        pending_import = None;
+                    } else if trimmed.ends_with(';') {
+                        pending_import = None;
+                    }
+                }
             }
-            
-            // Parse import statements
-            if let Some(import_name) = self.extract_import_name(trimmed) {
-                root.children.push(AstNode {
-                    node_type: "ImportDeclaration".to_string(),
-                    name: Some(import_name),
-                    start_line: current_line,
-                    end_line: current_line,
-                    children: Vec::new(),
-                });
+
+            for brace in scan.braces(line) {
+                if brace == '{' {
+                    depth += 1;
+                    // The first brace after a declaration opens its body
+                    if let Some(pending) = open.iter_mut().rev().find(|(_, body_depth)| body_depth.is_none()) {
+                        pending.1 = Some(depth);
+                    }
+                } else {
+                    if matches!(open.last(), Some((_, Some(body_depth))) if *body_depth == depth) {
+                        Self::close(&mut open, &mut root, current_line);
+                    }
+                    depth = depth.saturating_sub(1);
+                }
+            }
+
+            // Bodiless declarations (`const f = (x) => x`, overloads) end on their own line
+            let expression_arrow = trimmed.contains("=>") && !trimmed.ends_with("=>");
+            if trimmed.ends_with(';') || expression_arrow {
+                while matches!(open.last(), Some((_, None))) {
+                    Self::close(&mut open, &mut root, current_line);
+                }
+            }
+
+            if !trimmed.is_empty() {
+                last_code_line = current_line;
+                previous = trimmed;
             }
             
             current_line += 1;
         }
 
+        // Unterminated bodies run to the end of the file
+        while let Some((node, body_depth)) = open.last() {
+            let end_line = match body_depth {
+                Some(_) => root.end_line,
+                None if node.node_type == "TypeAlias" => last_code_line,
+                None => node.start_line,
+            };
+            Self::close(&mut open, &mut root, end_line);
+        }
+
+        let lines: Vec<&str> = content.lines().collect();
+        assign_body_hashes(&mut root, &lines);
+        assign_qualified_names(&mut root);
+
         root
     }
 
-    fn extract_function_name(&self, line: &str) -> Option<String> {
-        // Simple regex patterns for function detection
-        if line.starts_with("function ") {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                let name = parts[1].split('(').next()?;
-                return Some(name.to_string());
+    fn node(node_type: &str, name: String, line: u32) -> AstNode {
+        AstNode {
+            node_type: node_type.to_string(),
+            name: Some(name),
+            start_line: line,
+            end_line: line,
+            children: Vec::new(),
+            body_hash: None,
+            modifiers: Vec::new(),
+            qualified_name: None,
+        }
+    }
+
+    // Finishes the innermost open declaration and attaches it to its enclosing one
+    fn close(open: &mut Vec<(AstNode, Option<usize>)>, root: &mut AstNode, end_line: u32) {
+        if let Some((mut node, _)) = open.pop() {
+            node.end_line = end_line.max(node.start_line);
+            match open.last_mut() {
+                Some((parent, _)) => parent.children.push(node),
+                None => root.children.push(node),
             }
         }
-        
-        if line.starts_with("export function ") {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 3 {
-                let name = parts[2].split('(').next()?;
-                return Some(name.to_string());
+    }
+
+    // `function f`, `export async function f`, `function* f` or `async function *f`
+    fn extract_function_name(&self, line: &str) -> Option<String> {
+        let mut rest = line;
+        while let Some(stripped) = rest.strip_prefix("export ").or_else(|| rest.strip_prefix("async ")) {
+            rest = stripped.trim_start();
+        }
+
+        if let Some(after) = rest.strip_prefix("function") {
+            if !after.starts_with([' ', '*']) {
+                return None;
             }
+            let after = after.trim_start().trim_start_matches('*').trim_start();
+            let name: String = after.chars().take_while(|c| is_identifier_char(*c)).collect();
+            return (!name.is_empty()).then_some(name);
         }
         
         // Arrow functions
-        if line.contains(" = ") && line.contains(" => ") {
-            let parts: Vec<&str> = line.split(" = ").collect();
-            if parts.len() >= 2 {
-                let name = parts[0].trim().split_whitespace().last()?;
-                return Some(name.to_string());
-            }
+        if line.contains("=>") {
+            return self.extract_arrow_function_name(line);
         }
         
         None
     }
 
+    // `const f = () => ...`, `export const f: () => void = () => ...` or a class property
+    // `handler = async (e) => ...`. Destructuring and non-function values yield None.
+    fn extract_arrow_function_name(&self, line: &str) -> Option<String> {
+        let mut rest = line;
+        loop {
+            let stripped = ["export ", "default ", "declare ", "const ", "let ", "var ", "public ", "private ", "protected ", "static ", "readonly ", "override "]
+                .iter()
+                .find_map(|keyword| rest.strip_prefix(keyword));
+            match stripped {
+                Some(stripped) => rest = stripped.trim_start(),
+                None => break,
+            }
+        }
+
+        let name_len = rest.find(|c: char| !is_identifier_char(c)).unwrap_or(rest.len());
+        if name_len == 0 {
+            return None;
+        }
+        let (name, after_name) = rest.split_at(name_len);
+        let after_name = after_name.trim_start();
+
+        // Skip a type annotation, which may itself contain `=>`, up to the assignment
+        let value = if let Some(annotation) = after_name.strip_prefix(':') {
+            let mut depth = 0i32;
+            let mut chars = annotation.char_indices().peekable();
+            let mut value = None;
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '(' | '<' | '{' | '[' => depth += 1,
+                    ')' | ']' | '}' => depth -= 1,
+                    '>' if depth > 0 => depth -= 1,
+                    '=' if chars.peek().is_some_and(|(_, next)| *next == '>') => {
+                        chars.next();
+                    }
+                    '=' if depth == 0 => {
+                        value = Some(&annotation[i + 1..]);
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            value?
+        } else {
+            after_name.strip_prefix('=').filter(|value| !value.starts_with(['=', '>']))?
+        };
+
+        let value = value.trim_start();
+        let value = value.strip_prefix("async ").map(str::trim_start).unwrap_or(value);
+        let is_arrow = if value.starts_with(['(', '<']) {
+            true
+        } else {
+            // A single bare parameter: `x => x * 2`
+            let param_len = value.find(|c: char| !is_identifier_char(c)).unwrap_or(value.len());
+            param_len > 0 && value[param_len..].trim_start().starts_with("=>")
+        };
+
+        if is_arrow {
+            Some(name.to_string())
+        } else {
+            None
+        }
+    }
+
+    fn extract_method_name(&self, line: &str) -> Option<String> {
+        // Arrow function properties: `handler = () => {`
+        if let Some(name) = self.extract_function_name(line) {
+            return Some(name);
+        }
+
+        let mut rest = line;
+        loop {
+            let stripped = ["public ", "private ", "protected ", "static ", "async ", "readonly ", "override ", "abstract ", "get ", "set "]
+                .iter()
+                .find_map(|modifier| rest.strip_prefix(modifier));
+            match stripped {
+                Some(stripped) => rest = stripped.trim_start(),
+                None => break,
+            }
+        }
+        let rest = rest.trim_start_matches('*');
+
+        let paren = rest.find('(')?;
+        let name = rest[..paren].split('<').next()?.trim_end_matches('?').trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$' || c == '#') {
+            return None;
+        }
+        if matches!(name, "if" | "for" | "while" | "switch" | "catch" | "return" | "function" | "new") {
+            return None;
+        }
+        Some(name.to_string())
+    }
+
+    // `async` and `generator`, read from the declaration up to its parameter list, so an
+    // `async` call inside an arrow's body doesn't count
+    fn extract_modifiers(&self, line: &str) -> Vec<String> {
+        let head = line.split('(').next().unwrap_or(line);
+        let head = head.split("=>").next().unwrap_or(head);
+
+        let mut modifiers = Vec::new();
+        if head.split_whitespace().any(|token| token == "async") {
+            modifiers.push("async".to_string());
+        }
+        if head.contains("function*") || head.split_whitespace().any(|token| token.starts_with('*')) {
+            modifiers.push("generator".to_string());
+        }
+        modifiers
+    }
+
+    fn extract_anonymous_default(&self, line: &str) -> Option<&'static str> {
+        let rest = line.strip_prefix("export default ")?;
+        let rest = rest.strip_prefix("async ").unwrap_or(rest);
+
+        if let Some(after) = rest.strip_prefix("function") {
+            let after = after.trim_start().trim_start_matches('*').trim_start();
+            return after.starts_with('(').then_some("FunctionDeclaration");
+        }
+        if let Some(after) = rest.strip_prefix("class") {
+            let after = after.trim_start();
+            return (after.starts_with('{') || after.starts_with("extends ")).then_some("ClassDeclaration");
+        }
+        None
+    }
+
     fn extract_class_name(&self, line: &str) -> Option<String> {
         if line.starts_with("class ") || line.starts_with("export class ") {
             let parts: Vec<&str> = line.split_whitespace().collect();
@@ -108,25 +353,302 @@ impl TypeScriptParser {
         None
     }
 
+    fn extract_interface_name(&self, line: &str) -> Option<String> {
+        let rest = line.strip_prefix("export ").unwrap_or(line);
+        let rest = rest.strip_prefix("declare ").unwrap_or(rest);
+        let rest = rest.strip_prefix("interface ")?;
+        let name = rest.split(['{', '<', ' ']).next()?;
+        if name.is_empty() {
+            return None;
+        }
+        Some(name.to_string())
+    }
+
+    fn extract_type_alias_name(&self, line: &str) -> Option<String> {
+        let rest = line.strip_prefix("export ").unwrap_or(line);
+        let rest = rest.strip_prefix("declare ").unwrap_or(rest);
+        let rest = rest.strip_prefix("type ")?;
+        // `export type { A } from './a'` is a type-only re-export, not an alias
+        let name = rest.split(['=', '<', ' ']).next()?;
+        if name.is_empty() || !rest.contains('=') {
+            return None;
+        }
+        Some(name.to_string())
+    }
+
+    // The module specifier of an import line, or of the `} from '...'` line that ends
+    // a multi-line import
     fn extract_import_name(&self, line: &str) -> Option<String> {
-        if line.starts_with("import ") {
-            // Extract module name from import statement
-            if let Some(from_pos) = line.find(" from ") {
-                let module_part = &line[from_pos + 6..];
-                let module_name = module_part.trim().trim_matches('\'').trim_matches('"');
+        let module_part = match line.strip_prefix("import ") {
+            // `import './polyfills'` has no `from`
+            Some(rest) if rest.trim_start().starts_with(['\'', '"']) => rest.trim_start(),
+            _ => {
+                let from_pos = line.rfind("from ")?;
+                let preceded_by_space = line[..from_pos].ends_with([' ', '}']) || from_pos == 0;
+                if !preceded_by_space {
+                    return None;
+                }
+                line[from_pos + 5..].trim()
+            }
+        };
+
+        if let Some(quote) = module_part.chars().next().filter(|c| matches!(c, '\'' | '"')) {
+            if let Some(module_name) = module_part[1..].split(quote).next() {
                 return Some(module_name.to_string());
             }
         }
-        None
+        let module_name = module_part.trim_end_matches(';').trim_matches('\'').trim_matches('"');
+        (!module_name.is_empty()).then(|| module_name.to_string())
     }
 }
 
 impl AstParser for TypeScriptParser {
-    fn parse(&self, content: &str) -> Result<AstNode, Box<dyn std::error::Error>> {
+    fn parse(&self, content: &str) -> Result<AstNode, ShadowError> {
+        if self.strict {
+            check_balanced(content)?;
+        }
         Ok(self.parse_simple(content))
     }
 
     fn supported_extensions(&self) -> Vec<&'static str> {
         vec!["ts", "js", "tsx", "jsx"]
     }
-}
\ No newline at end of file
+}
+
+// Vue and Svelte single-file components. Only their <script> blocks are parsed, as
+// TypeScript, with line numbers relative to the whole file.
+#[derive(Clone)]
+pub struct ComponentParser {
+    script: TypeScriptParser,
+}
+
+impl ComponentParser {
+    pub fn new() -> Self {
+        ComponentParser { script: TypeScriptParser::new() }
+    }
+}
+
+impl Default for ComponentParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AstParser for ComponentParser {
+    fn parse(&self, content: &str) -> Result<AstNode, ShadowError> {
+        let mut root = self.script.parse_simple(&extract_script_blocks(content));
+        root.end_line = content.lines().count() as u32;
+        Ok(root)
+    }
+
+    fn supported_extensions(&self) -> Vec<&'static str> {
+        vec!["vue", "svelte"]
+    }
+}
+
+// Keeps the contents of every <script> block (including `<script setup>` and
+// `<script context="module">`) and blanks everything else, one output line per input line
+pub fn extract_script_blocks(content: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_script = false;
+
+    for line in content.lines() {
+        let mut kept = String::new();
+        let mut rest = line;
+
+        loop {
+            if in_script {
+                match rest.find("</script>") {
+                    Some(end) => {
+                        kept.push_str(&rest[..end]);
+                        rest = &rest[end + "</script>".len()..];
+                        in_script = false;
+                    }
+                    None => {
+                        kept.push_str(rest);
+                        break;
+                    }
+                }
+            } else {
+                let Some(start) = find_script_tag(rest) else {
+                    break;
+                };
+                // An opening tag split across lines is treated as ending on its first line
+                match rest[start..].find('>') {
+                    Some(close) => rest = &rest[start + close + 1..],
+                    None => rest = "",
+                }
+                in_script = true;
+            }
+        }
+
+        lines.push(kept);
+    }
+
+    lines.join("\n")
+}
+
+// Start of a `<script>` or `<script ...>` tag, but not e.g. `<scripts>`
+fn find_script_tag(line: &str) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(pos) = line[offset..].find("<script") {
+        let start = offset + pos;
+        let next = line[start + "<script".len()..].chars().next();
+        if next.is_none_or(|c| c == '>' || c.is_whitespace()) {
+            return Some(start);
+        }
+        offset = start + "<script".len();
+    }
+    None
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+// Blanks comments and the contents of string and template literals with spaces,
+// keeping quotes and newlines so line numbers stay the same
+fn strip_comments_and_strings(content: &str) -> String {
+    let mut code = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut quote: Option<char> = None;
+    let mut in_line_comment = false;
+    let mut in_block_comment = false;
+
+    while let Some(c) = chars.next() {
+        if c == '\n' {
+            in_line_comment = false;
+            // Plain strings can't span lines; an unterminated one ends here
+            if quote != Some('`') {
+                quote = None;
+            }
+            code.push('\n');
+            continue;
+        }
+
+        if in_line_comment {
+            code.push(' ');
+        } else if in_block_comment {
+            if c == '*' && chars.peek() == Some(&'/') {
+                chars.next();
+                in_block_comment = false;
+                code.push(' ');
+            }
+            code.push(' ');
+        } else if let Some(q) = quote {
+            if c == q {
+                quote = None;
+                code.push(c);
+            } else {
+                code.push(' ');
+                if c == '\\' && chars.peek().is_some_and(|next| *next != '\n') {
+                    chars.next();
+                    code.push(' ');
+                }
+            }
+        } else {
+            match c {
+                '\'' | '"' | '`' => {
+                    quote = Some(c);
+                    code.push(c);
+                }
+                '/' if chars.peek() == Some(&'/') => {
+                    in_line_comment = true;
+                    code.push(' ');
+                }
+                '/' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    in_block_comment = true;
+                    code.push_str("  ");
+                }
+                _ => code.push(c),
+            }
+        }
+    }
+
+    code
+}
+
+// Errors on the first bracket that is closed by the wrong kind, never closed, or
+// closed without being opened. Comments and strings are ignored.
+fn check_balanced(content: &str) -> Result<(), ShadowError> {
+    let mut open: Vec<(char, usize)> = Vec::new();
+
+    for (index, line) in strip_comments_and_strings(content).lines().enumerate() {
+        let line_number = index + 1;
+        for c in line.chars() {
+            match c {
+                '(' | '[' | '{' => open.push((c, line_number)),
+                ')' | ']' | '}' => {
+                    let expected = match c {
+                        ')' => '(',
+                        ']' => '[',
+                        _ => '{',
+                    };
+                    match open.pop() {
+                        Some((opener, _)) if opener == expected => {}
+                        Some((opener, opened_on)) => {
+                            return Err(ShadowError::Parse(format!("Mismatched '{}' on line {} closes '{}' from line {}", c, line_number, opener, opened_on)));
+                        }
+                        None => return Err(ShadowError::Parse(format!("Unexpected '{}' on line {}", c, line_number))),
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    match open.last() {
+        Some((opener, opened_on)) => Err(ShadowError::Parse(format!("Unclosed '{}' from line {}", opener, opened_on))),
+        None => Ok(()),
+    }
+}
+
+// Tracks string and comment state across lines so only structural braces are reported
+#[derive(Default)]
+struct BraceScanner {
+    in_block_comment: bool,
+    in_template: bool,
+}
+
+impl BraceScanner {
+    fn braces(&mut self, line: &str) -> Vec<char> {
+        let mut braces = Vec::new();
+        let mut quote: Option<char> = if self.in_template { Some('`') } else { None };
+        let mut chars = line.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if self.in_block_comment {
+                if c == '*' && chars.peek() == Some(&'/') {
+                    chars.next();
+                    self.in_block_comment = false;
+                }
+                continue;
+            }
+
+            if let Some(q) = quote {
+                if c == '\\' {
+                    chars.next();
+                } else if c == q {
+                    quote = None;
+                }
+                continue;
+            }
+
+            match c {
+                '\'' | '"' | '`' => quote = Some(c),
+                '/' if chars.peek() == Some(&'/') => break,
+                '/' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    self.in_block_comment = true;
+                }
+                '{' | '}' => braces.push(c),
+                _ => {}
+            }
+        }
+
+        // Only template literals may span lines
+        self.in_template = quote == Some('`');
+        braces
+    }
+}
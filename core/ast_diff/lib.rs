@@ -1,5 +1,10 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+
+use crate::diff::{diff_lines, LineOp};
+use crate::ShadowError;
 
 pub mod languages;
 
@@ -13,6 +18,43 @@ pub struct AstNode {
     pub start_line: u32,
     pub end_line: u32,
     pub children: Vec<AstNode>,
+    #[serde(default)]
+    pub body_hash: Option<u64>,
+    // Declaration modifiers such as `async` and `generator`
+    #[serde(default)]
+    pub modifiers: Vec<String>,
+    // The name prefixed with its enclosing named scopes, like `ClassA.get`
+    #[serde(default)]
+    pub qualified_name: Option<String>,
+}
+
+impl AstNode {
+    // Structural similarity in [0, 1], symmetric. Leaves compare node type and name; nodes
+    // with children weigh those equally with how much of the two child sets overlap.
+    // Children are paired by node type and name, in order, and scored recursively.
+    pub fn similarity(&self, other: &AstNode) -> f64 {
+        let type_score = if self.node_type == other.node_type { 1.0 } else { 0.0 };
+        let name_score = if self.name == other.name { 1.0 } else { 0.0 };
+
+        if self.children.is_empty() && other.children.is_empty() {
+            return (type_score + name_score) / 2.0;
+        }
+
+        let mut unmatched: HashMap<(&str, Option<&str>), Vec<&AstNode>> = HashMap::new();
+        for child in other.children.iter().rev() {
+            unmatched.entry((child.node_type.as_str(), child.name.as_deref())).or_default().push(child);
+        }
+
+        let matched: f64 = self.children.iter()
+            .filter_map(|child| {
+                let candidates = unmatched.get_mut(&(child.node_type.as_str(), child.name.as_deref()))?;
+                candidates.pop().map(|candidate| child.similarity(candidate))
+            })
+            .sum();
+        let overlap = 2.0 * matched / (self.children.len() + other.children.len()) as f64;
+
+        (type_score + name_score + 2.0 * overlap) / 4.0
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -26,6 +68,9 @@ pub struct AstChange {
     pub change_type: ChangeType,
     pub node_type: String,
     pub name: Option<String>,
+    // The node's qualified name, telling apart same-named members of different scopes
+    #[serde(default)]
+    pub qualified_name: Option<String>,
     pub line_range: (u32, u32),
     pub old_content: Option<String>,
     pub new_content: Option<String>,
@@ -36,45 +81,285 @@ pub enum ChangeType {
     Added,
     Modified,
     Removed,
+    Renamed { old_name: String, new_name: String },
+    // Same body, but relocated rather than just shifted by edits around it
+    Moved { old_line_range: (u32, u32) },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ChangeCounts {
+    pub added: usize,
+    pub modified: usize,
+    pub removed: usize,
+    pub renamed: usize,
+    pub moved: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct DiffStats {
+    // Files with at least one structural change
+    pub files_changed: usize,
+    pub by_node_type: BTreeMap<String, ChangeCounts>,
+}
+
+impl DiffStats {
+    pub fn from_ast_diffs(diffs: &[AstDiff]) -> Self {
+        let mut stats = DiffStats::default();
+
+        for diff in diffs {
+            if !diff.changes.is_empty() {
+                stats.files_changed += 1;
+            }
+
+            for change in &diff.changes {
+                let counts = stats.by_node_type.entry(change.node_type.clone()).or_default();
+                match change.change_type {
+                    ChangeType::Added => counts.added += 1,
+                    ChangeType::Modified => counts.modified += 1,
+                    ChangeType::Removed => counts.removed += 1,
+                    ChangeType::Renamed { .. } => counts.renamed += 1,
+                    ChangeType::Moved { .. } => counts.moved += 1,
+                }
+            }
+        }
+
+        stats
+    }
+}
+
+impl ChangeType {
+    // Orders variants by kind, then by their payload
+    fn sort_key(&self) -> (u8, &str, &str, (u32, u32)) {
+        match self {
+            ChangeType::Added => (0, "", "", (0, 0)),
+            ChangeType::Modified => (1, "", "", (0, 0)),
+            ChangeType::Removed => (2, "", "", (0, 0)),
+            ChangeType::Renamed { old_name, new_name } => (3, old_name, new_name, (0, 0)),
+            ChangeType::Moved { old_line_range } => (4, "", "", *old_line_range),
+        }
+    }
+}
+
+// Sets `qualified_name` on every named node below `root` to its name joined with those of
+// its named ancestors by `.`. Unnamed nodes add nothing to the scope of their children.
+pub fn assign_qualified_names(root: &mut AstNode) {
+    fn assign(node: &mut AstNode, scope: Option<&str>) {
+        for child in node.children.iter_mut() {
+            child.qualified_name = child.name.as_ref().map(|name| match scope {
+                Some(scope) => format!("{}.{}", scope, name),
+                None => name.clone(),
+            });
+            let child_scope = child.qualified_name.clone();
+            assign(child, child_scope.as_deref().or(scope));
+        }
+    }
+    assign(root, None);
+}
+
+// Hashes each node's own lines, skipping lines that belong to its children so a
+// changed method doesn't also mark its class as modified. Whitespace is collapsed so
+// reindenting code doesn't change the hash.
+pub fn assign_body_hashes(node: &mut AstNode, lines: &[&str]) {
+    for child in node.children.iter_mut() {
+        assign_body_hashes(child, lines);
+    }
+
+    let mut hasher = DefaultHasher::new();
+    let start = node.start_line.max(1);
+    for line_number in start..=node.end_line {
+        let owned_by_child = node.children.iter()
+            .any(|child| child.start_line <= line_number && line_number <= child.end_line);
+        if owned_by_child {
+            continue;
+        }
+
+        if let Some(line) = lines.get(line_number as usize - 1) {
+            for token in line.split_whitespace() {
+                token.hash(&mut hasher);
+            }
+        }
+    }
+    node.body_hash = Some(hasher.finish());
+}
+
+// Removed/Added pairs at least this similar are reported as a single rename
+const RENAME_SIMILARITY_THRESHOLD: f64 = 0.9;
+
+// Identifier and punctuation tokens of a changed node's lines, with its own name blanked out
+fn body_tokens(lines: &[&str], change: &AstChange) -> Vec<String> {
+    let name = change.name.as_deref().unwrap_or("");
+    let start = change.line_range.0.max(1) as usize - 1;
+    let end = (change.line_range.1 as usize).min(lines.len());
+
+    let mut tokens = Vec::new();
+    for line in lines.get(start..end).unwrap_or(&[]) {
+        let mut word = String::new();
+        for c in line.chars() {
+            if c.is_alphanumeric() || c == '_' || c == '$' {
+                word.push(c);
+                continue;
+            }
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            if !c.is_whitespace() {
+                tokens.push(c.to_string());
+            }
+        }
+        if !word.is_empty() {
+            tokens.push(word);
+        }
+    }
+
+    tokens.into_iter().filter(|token| token != name).collect()
+}
+
+// Dice coefficient over token multisets
+fn body_similarity(a: &[String], b: &[String]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for token in a {
+        *counts.entry(token).or_insert(0) += 1;
+    }
+
+    let mut common = 0;
+    for token in b {
+        if let Some(count) = counts.get_mut(token.as_str()) {
+            if *count > 0 {
+                *count -= 1;
+                common += 1;
+            }
+        }
+    }
+
+    2.0 * common as f64 / (a.len() + b.len()) as f64
 }
 
+// Unchanged nodes displaced by no more than this many lines are never reported as moved
+const MOVE_LINE_THRESHOLD: u32 = 1;
+
+// Deeper trees are rejected rather than risking a stack overflow on hostile input
+const MAX_DIFF_DEPTH: usize = 256;
+
 pub struct AstDiffEngine {
-    parsers: HashMap<String, Box<dyn AstParser>>,
+    parsers: HashMap<String, std::sync::Arc<dyn AstParser>>,
+    context_lines: usize,
+    generic_fallback: bool,
 }
 
 pub trait AstParser: Send + Sync {
-    fn parse(&self, content: &str) -> Result<AstNode, Box<dyn std::error::Error>>;
+    fn parse(&self, content: &str) -> Result<AstNode, ShadowError>;
     fn supported_extensions(&self) -> Vec<&'static str>;
 }
 
 impl AstDiffEngine {
     pub fn new() -> Self {
-        let mut engine = AstDiffEngine {
-            parsers: HashMap::new(),
-        };
+        let mut engine = Self::new_empty();
         
         // Register TypeScript/JavaScript parser
-        let ts_parser = languages::ts::TypeScriptParser::new();
-        for ext in ts_parser.supported_extensions() {
-            engine.parsers.insert(ext.to_string(), Box::new(ts_parser.clone()));
-        }
+        engine.register_parser(Box::new(languages::ts::TypeScriptParser::new()));
+        engine.register_parser(Box::new(languages::ts::ComponentParser::new()));
+
+        // Register Python parser
+        engine.register_parser(Box::new(languages::python::PythonParser::new()));
+
+        // Register Rust parser
+        engine.register_parser(Box::new(languages::rust::RustParser::new()));
+
+        // Register JSON and TOML config parsers
+        engine.register_parser(Box::new(languages::json::JsonParser::new()));
+        engine.register_parser(Box::new(languages::toml::TomlParser::new()));
         
         engine
     }
 
-    pub fn compute_diff(&self, file_path: &str, old_content: &str, new_content: &str) -> Result<AstDiff, Box<dyn std::error::Error>> {
-        let extension = std::path::Path::new(file_path)
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .unwrap_or("");
+    // An engine with no parsers, for callers that want to pick their own set
+    pub fn new_empty() -> Self {
+        AstDiffEngine {
+            parsers: HashMap::new(),
+            context_lines: 0,
+            generic_fallback: false,
+        }
+    }
+
+    // Registers the parser for each of its extensions. A later registration replaces an
+    // earlier one for the same extension, so the built-in parsers can be overridden.
+    pub fn register_parser(&mut self, parser: Box<dyn AstParser>) {
+        let parser: std::sync::Arc<dyn AstParser> = parser.into();
+        for ext in parser.supported_extensions() {
+            self.parsers.insert(ext.to_string(), parser.clone());
+        }
+    }
+
+    pub fn set_context_lines(&mut self, context_lines: usize) {
+        self.context_lines = context_lines;
+    }
+
+    pub fn context_lines(&self) -> usize {
+        self.context_lines
+    }
+
+    // Diff files without a registered parser line by line instead of failing
+    pub fn with_generic_fallback(mut self, enabled: bool) -> Self {
+        self.generic_fallback = enabled;
+        self
+    }
+
+    pub fn set_generic_fallback(&mut self, enabled: bool) {
+        self.generic_fallback = enabled;
+    }
+
+    pub fn has_parser(&self, file_path: &str) -> bool {
+        self.parsers.contains_key(Self::extension_of(file_path))
+    }
+
+    pub fn compute_diff(&self, file_path: &str, old_content: &str, new_content: &str) -> Result<AstDiff, ShadowError> {
+        if self.generic_fallback && !self.has_parser(file_path) {
+            return Ok(self.line_diff(file_path, old_content, new_content));
+        }
+
+        let old_ast = self.parse(file_path, old_content)?;
+        let new_ast = self.parse(file_path, new_content)?;
+
+        self.diff_parsed(file_path, &old_ast, &new_ast, old_content, new_content)
+    }
+
+    pub fn parse(&self, file_path: &str, content: &str) -> Result<AstNode, ShadowError> {
+        let extension = Self::extension_of(file_path);
 
         let parser = self.parsers.get(extension)
-            .ok_or_else(|| format!("No parser available for extension: {}", extension))?;
+            .ok_or_else(|| ShadowError::NoParser(extension.to_string()))?;
+
+        parser.parse(content)
+    }
 
-        let old_ast = parser.parse(old_content)?;
-        let new_ast = parser.parse(new_content)?;
+    pub fn diff_parsed(&self, file_path: &str, old_ast: &AstNode, new_ast: &AstNode, old_content: &str, new_content: &str) -> Result<AstDiff, ShadowError> {
+        let old_lines: Vec<&str> = old_content.lines().collect();
+        let new_lines: Vec<&str> = new_content.lines().collect();
+        // Lines the LCS keeps in place; nodes anchored by them only shifted with nearby edits
+        let line_map: HashMap<usize, usize> = diff_lines(&old_lines, &new_lines).into_iter()
+            .filter_map(|op| match op {
+                LineOp::Equal(i, j) => Some((i, j)),
+                _ => None,
+            })
+            .collect();
+        let changes = self.diff_nodes(old_ast, new_ast, &old_lines, &new_lines, &line_map, 0)?;
+        let mut changes = Self::pair_renames(changes, &old_lines, &new_lines);
 
-        let changes = self.diff_nodes(&old_ast, &new_ast);
+        // diff_nodes walks HashMaps, so fix the order here. Every field takes part in the
+        // comparison, so identical inputs always serialize identically.
+        changes.sort_by(|a, b| {
+            a.line_range.0.cmp(&b.line_range.0)
+                .then_with(|| a.change_type.sort_key().cmp(&b.change_type.sort_key()))
+                .then_with(|| a.name.cmp(&b.name))
+                .then_with(|| a.line_range.1.cmp(&b.line_range.1))
+                .then_with(|| a.node_type.cmp(&b.node_type))
+                .then_with(|| a.old_content.cmp(&b.old_content))
+                .then_with(|| a.new_content.cmp(&b.new_content))
+        });
 
         Ok(AstDiff {
             file_path: file_path.to_string(),
@@ -82,27 +367,68 @@ impl AstDiffEngine {
         })
     }
 
-    fn diff_nodes(&self, old_node: &AstNode, new_node: &AstNode) -> Vec<AstChange> {
-        let mut changes = Vec::new();
+    fn extension_of(file_path: &str) -> &str {
+        std::path::Path::new(file_path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("")
+    }
 
-        // Simple diff algorithm - compare by name and type
-        let old_children: HashMap<String, &AstNode> = old_node.children.iter()
-            .filter_map(|child| child.name.as_ref().map(|name| (name.clone(), child)))
-            .collect();
+    fn line_diff(&self, file_path: &str, old_content: &str, new_content: &str) -> AstDiff {
+        let old_lines: Vec<&str> = old_content.lines().collect();
+        let new_lines: Vec<&str> = new_content.lines().collect();
 
-        let new_children: HashMap<String, &AstNode> = new_node.children.iter()
-            .filter_map(|child| child.name.as_ref().map(|name| (name.clone(), child)))
+        let changes = diff_lines(&old_lines, &new_lines).into_iter()
+            .filter_map(|op| match op {
+                LineOp::Delete(i) => Some(AstChange {
+                    change_type: ChangeType::Removed,
+                    node_type: "Line".to_string(),
+                    name: None,
+                    qualified_name: None,
+                    line_range: (i as u32 + 1, i as u32 + 1),
+                    old_content: Some(old_lines[i].to_string()),
+                    new_content: None,
+                }),
+                LineOp::Insert(j) => Some(AstChange {
+                    change_type: ChangeType::Added,
+                    node_type: "Line".to_string(),
+                    name: None,
+                    qualified_name: None,
+                    line_range: (j as u32 + 1, j as u32 + 1),
+                    old_content: None,
+                    new_content: Some(new_lines[j].to_string()),
+                }),
+                LineOp::Equal(_, _) => None,
+            })
             .collect();
 
+        AstDiff {
+            file_path: file_path.to_string(),
+            changes,
+        }
+    }
+
+    fn diff_nodes(&self, old_node: &AstNode, new_node: &AstNode, old_lines: &[&str], new_lines: &[&str], line_map: &HashMap<usize, usize>, depth: usize) -> Result<Vec<AstChange>, ShadowError> {
+        if depth > MAX_DIFF_DEPTH {
+            return Err(ShadowError::Parse(format!("AST nesting exceeds maximum diff depth of {}", MAX_DIFF_DEPTH)));
+        }
+
+        let mut changes = Vec::new();
+
+        // Simple diff algorithm - compare by name and type
+        let old_children = Self::keyed_children(old_node);
+        let new_children = Self::keyed_children(new_node);
+
         // Find removed nodes
         for (name, old_child) in &old_children {
             if !new_children.contains_key(name) {
                 changes.push(AstChange {
                     change_type: ChangeType::Removed,
                     node_type: old_child.node_type.clone(),
-                    name: Some(name.clone()),
+                    name: old_child.name.clone(),
+                    qualified_name: old_child.qualified_name.clone(),
                     line_range: (old_child.start_line, old_child.end_line),
-                    old_content: Some(format!("{} {}", old_child.node_type, name)),
+                    old_content: Some(self.node_content(old_child, old_lines)),
                     new_content: None,
                 });
             }
@@ -115,10 +441,11 @@ impl AstDiffEngine {
                     changes.push(AstChange {
                         change_type: ChangeType::Added,
                         node_type: new_child.node_type.clone(),
-                        name: Some(name.clone()),
+                        name: new_child.name.clone(),
+                        qualified_name: new_child.qualified_name.clone(),
                         line_range: (new_child.start_line, new_child.end_line),
                         old_content: None,
-                        new_content: Some(format!("{} {}", new_child.node_type, name)),
+                        new_content: Some(self.node_content(new_child, new_lines)),
                     });
                 }
                 Some(old_child) => {
@@ -126,24 +453,159 @@ impl AstDiffEngine {
                         changes.push(AstChange {
                             change_type: ChangeType::Modified,
                             node_type: new_child.node_type.clone(),
-                            name: Some(name.clone()),
+                            name: new_child.name.clone(),
+                            qualified_name: new_child.qualified_name.clone(),
+                            line_range: (new_child.start_line, new_child.end_line),
+                            old_content: Some(self.node_content(old_child, old_lines)),
+                            new_content: Some(self.node_content(new_child, new_lines)),
+                        });
+                    } else if Self::node_moved(old_child, new_child, line_map) {
+                        changes.push(AstChange {
+                            change_type: ChangeType::Moved { old_line_range: (old_child.start_line, old_child.end_line) },
+                            node_type: new_child.node_type.clone(),
+                            name: new_child.name.clone(),
+                            qualified_name: new_child.qualified_name.clone(),
                             line_range: (new_child.start_line, new_child.end_line),
-                            old_content: Some(format!("{} {}", old_child.node_type, name)),
-                            new_content: Some(format!("{} {}", new_child.node_type, name)),
+                            old_content: None,
+                            new_content: None,
                         });
                     }
                     
                     // Recursively diff children
-                    changes.extend(self.diff_nodes(old_child, new_child));
+                    changes.extend(self.diff_nodes(old_child, new_child, old_lines, new_lines, line_map, depth + 1)?);
                 }
             }
         }
 
-        changes
+        Ok(changes)
+    }
+
+    // Children keyed by qualified name where the parser gave one, otherwise by name.
+    // Unnamed ones (anonymous default exports, IIFEs) get a positional key per node
+    // type, like `<anon:FunctionDeclaration:0>`, which no identifier can clash with
+    // and which keeps two anonymous siblings apart.
+    fn keyed_children(node: &AstNode) -> HashMap<String, &AstNode> {
+        let mut anonymous: HashMap<&str, usize> = HashMap::new();
+        node.children.iter()
+            .map(|child| match child.qualified_name.as_ref().or(child.name.as_ref()) {
+                Some(name) => (name.clone(), child),
+                None => {
+                    let index = anonymous.entry(child.node_type.as_str()).or_insert(0);
+                    *index += 1;
+                    (format!("<anon:{}:{}>", child.node_type, *index - 1), child)
+                }
+            })
+            .collect()
+    }
+
+    // Collapses a Removed and an Added node of the same type with near-identical bodies into a
+    // Renamed change. Candidates are taken most similar first, then by lowest line, so the
+    // pairing doesn't depend on HashMap iteration order.
+    fn pair_renames(changes: Vec<AstChange>, old_lines: &[&str], new_lines: &[&str]) -> Vec<AstChange> {
+        let mut candidates = Vec::new();
+        for (i, removed) in changes.iter().enumerate() {
+            if !matches!(removed.change_type, ChangeType::Removed) || removed.name.is_none() {
+                continue;
+            }
+            for (j, added) in changes.iter().enumerate() {
+                if !matches!(added.change_type, ChangeType::Added) || added.name.is_none() || added.node_type != removed.node_type {
+                    continue;
+                }
+                let similarity = body_similarity(
+                    &body_tokens(old_lines, removed),
+                    &body_tokens(new_lines, added),
+                );
+                if similarity > RENAME_SIMILARITY_THRESHOLD {
+                    candidates.push((similarity, removed.line_range.0, added.line_range.0, i, j));
+                }
+            }
+        }
+
+        candidates.sort_by(|a, b| {
+            b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.1.cmp(&b.1))
+                .then(a.2.cmp(&b.2))
+        });
+
+        let mut paired: HashMap<usize, usize> = HashMap::new();
+        let mut used_added = std::collections::HashSet::new();
+        for (_, _, _, i, j) in candidates {
+            if !paired.contains_key(&i) && !used_added.contains(&j) {
+                paired.insert(i, j);
+                used_added.insert(j);
+            }
+        }
+
+        let mut renames: HashMap<usize, AstChange> = HashMap::new();
+        for (&i, &j) in &paired {
+            let removed = &changes[i];
+            let added = &changes[j];
+            let old_name = removed.name.clone().unwrap_or_default();
+            let new_name = added.name.clone().unwrap_or_default();
+            renames.insert(j, AstChange {
+                change_type: ChangeType::Renamed { old_name, new_name: new_name.clone() },
+                node_type: added.node_type.clone(),
+                name: Some(new_name),
+                qualified_name: added.qualified_name.clone(),
+                line_range: added.line_range,
+                old_content: removed.old_content.clone(),
+                new_content: added.new_content.clone(),
+            });
+        }
+
+        changes.into_iter()
+            .enumerate()
+            .filter(|(index, _)| !paired.contains_key(index))
+            .map(|(index, change)| renames.remove(&index).unwrap_or(change))
+            .collect()
+    }
+
+    // Moved when none of the node's old lines line up with its new lines in the line diff,
+    // i.e. its text was deleted in one place and inserted in another
+    fn node_moved(old_node: &AstNode, new_node: &AstNode, line_map: &HashMap<usize, usize>) -> bool {
+        if old_node.start_line.abs_diff(new_node.start_line) <= MOVE_LINE_THRESHOLD {
+            return false;
+        }
+
+        let new_range = new_node.start_line.max(1) as usize - 1..new_node.end_line as usize;
+        let anchored = (old_node.start_line.max(1) as usize - 1..old_node.end_line as usize)
+            .any(|i| line_map.get(&i).is_some_and(|j| new_range.contains(j)));
+        !anchored
     }
 
     fn nodes_differ(&self, old_node: &AstNode, new_node: &AstNode) -> bool {
+        if let (Some(old_hash), Some(new_hash)) = (old_node.body_hash, new_node.body_hash) {
+            if old_hash != new_hash {
+                return true;
+            }
+        }
+
         old_node.node_type != new_node.node_type ||
+        old_node.modifiers != new_node.modifiers ||
         old_node.children.len() != new_node.children.len()
     }
-}
\ No newline at end of file
+
+    fn node_content(&self, node: &AstNode, lines: &[&str]) -> String {
+        let start = node.start_line.saturating_sub(1) as usize;
+        let end = (node.end_line as usize).min(lines.len());
+
+        if start >= end {
+            return format!("{} {}", node.node_type, node.name.as_deref().unwrap_or(""));
+        }
+
+        if self.context_lines == 0 {
+            return lines[start..end].join("\n");
+        }
+
+        // Mark the node's own lines so they stand out from the surrounding context
+        let from = start.saturating_sub(self.context_lines);
+        let to = (end + self.context_lines).min(lines.len());
+        (from..to)
+            .map(|i| {
+                let marker = if i >= start && i < end { '>' } else { ' ' };
+                format!("{} {}", marker, lines[i])
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
@@ -2,6 +2,18 @@
 mod tests {
     use super::super::*;
     use crate::ast_diff::languages::ts::TypeScriptParser;
+    use crate::{DependencyGraphBuilder, RiskLevel};
+    use std::path::PathBuf;
+
+    fn write_workspace(files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("shadow-test-{}", uuid::Uuid::new_v4()));
+        for (path, content) in files {
+            let file_path = root.join(path);
+            std::fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+            std::fs::write(file_path, content).unwrap();
+        }
+        root
+    }
 
     #[test]
     fn test_typescript_parser_functions() {
@@ -91,4 +103,2813 @@ function oldFunction() {
         let high_risk = builder.analyze_impact(&many_files);
         assert!(matches!(high_risk.risk_level, RiskLevel::High));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_analyze_impact_globs() {
+        let root = write_workspace(&[
+            ("src/auth/login.ts", "export function login() {}"),
+            ("src/auth/session.ts", "export function session() {}"),
+            ("src/app.ts", "export function app() {}"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        let result = builder.analyze_impact_globs(&[
+            "src/auth/**".to_string(),
+            "src/missing/*.ts".to_string(),
+        ]);
+
+        assert_eq!(result.analysis.changed_files, vec!["src/auth/login.ts", "src/auth/session.ts"]);
+        assert_eq!(result.unmatched_patterns, vec!["src/missing/*.ts"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_directory_coupling() {
+        let root = write_workspace(&[
+            ("ui/page.ts", "import { x } from '../core/x';\nimport { y } from '../core/y';\n"),
+            ("ui2/other.ts", "import { x } from '../core/x';\n"),
+            ("core/x.ts", "export const x = 1;\n"),
+            ("core/y.ts", "import { page } from '../ui/page';\nexport const y = 1;\n"),
+        ]);
+
+        let mut engine = crate::Engine::new();
+        assert!(engine.coupling("ui", "core").is_err());
+        engine.build_dependency_graph(&root.to_string_lossy()).unwrap();
+
+        // `ui2/` is not under `ui`, and a trailing slash makes no difference
+        let coupling = engine.coupling("ui", "core/").unwrap();
+        assert_eq!((coupling.a_to_b, coupling.b_to_a), (2, 1));
+        let coupling = engine.coupling("core", "ui").unwrap();
+        assert_eq!((coupling.a_to_b, coupling.b_to_a), (1, 2));
+        let coupling = engine.coupling("ui2", "ui").unwrap();
+        assert_eq!((coupling.a_to_b, coupling.b_to_a), (0, 0));
+
+        // A re-export counts like an import, once even when the file also imports it
+        std::fs::write(root.join("ui/index.ts"), "export * from '../core/x';\nimport { x } from '../core/x';\n").unwrap();
+        engine.build_dependency_graph(&root.to_string_lossy()).unwrap();
+        let coupling = engine.coupling("./ui", "core\\").unwrap();
+        assert_eq!((coupling.a_to_b, coupling.b_to_a), (3, 1));
+
+        // A re-export counts like an import, once even when the file also imports it
+        std::fs::write(root.join("ui/index.ts"), "export * from '../core/x';\nimport { x } from '../core/x';\n").unwrap();
+        engine.build_dependency_graph(&root.to_string_lossy()).unwrap();
+        let coupling = engine.coupling("ui", "core").unwrap();
+        assert_eq!((coupling.a_to_b, coupling.b_to_a), (3, 1));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_ast_diff_context_lines() {
+        let mut engine = AstDiffEngine::new();
+        engine.set_context_lines(1);
+
+        let old_content = "const a = 1;\nconst b = 2;\n";
+        let new_content = "const a = 1;\nfunction added() {}\nconst b = 2;\n";
+
+        let diff = engine.compute_diff("test.ts", old_content, new_content).unwrap();
+        let added = diff.changes.iter()
+            .find(|change| change.name.as_deref() == Some("added"))
+            .unwrap();
+
+        assert_eq!(
+            added.new_content.as_deref(),
+            Some("  const a = 1;\n> function added() {}\n  const b = 2;")
+        );
+    }
+
+    #[test]
+    fn test_stylesheet_imports() {
+        let root = write_workspace(&[
+            ("styles/main.scss", "@import 'variables';\n@use '../theme/colors' as c;\n@use 'sass:math';\n"),
+            ("styles/_variables.scss", "$gap: 4px;\n"),
+            ("theme/colors.scss", "$primary: blue;\n"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        let mut edges = builder.get_graph().edges["styles/main.scss"].clone();
+        edges.sort();
+        assert_eq!(edges, vec!["styles/_variables.scss", "theme/colors.scss"]);
+
+        let impact = builder.analyze_impact(&["styles/_variables.scss".to_string()]);
+        assert_eq!(impact.impacted_files, vec!["styles/main.scss"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_diff_rejects_deeply_nested_ast() {
+        let mut node = AstNode {
+            node_type: "Block".to_string(),
+            name: Some("leaf".to_string()),
+            start_line: 1,
+            end_line: 1,
+            children: Vec::new(),
+            body_hash: None,
+            modifiers: Vec::new(),
+            qualified_name: None,
+        };
+        for _ in 0..1000 {
+            node = AstNode {
+                node_type: "Block".to_string(),
+                name: Some("block".to_string()),
+                start_line: 1,
+                end_line: 1,
+                children: vec![node],
+                body_hash: None,
+                modifiers: Vec::new(),
+                qualified_name: None,
+            };
+        }
+
+        let engine = AstDiffEngine::new();
+        let result = engine.diff_nodes(&node, &node, &[], &[], &std::collections::HashMap::new(), 0);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_session_elapsed_seconds() {
+        let store = std::sync::Arc::new(crate::Store::in_memory());
+        let mut engine = crate::Engine::with_store(store.clone());
+        assert_eq!(engine.session_elapsed_seconds().unwrap(), None);
+
+        let mut session = crate::Session::new();
+        session.start_time = chrono::Utc::now() - chrono::Duration::seconds(90);
+        engine.start_new_session(session, false).unwrap();
+        let elapsed = engine.session_elapsed_seconds().unwrap().unwrap();
+        assert!((90..100).contains(&elapsed), "elapsed {}", elapsed);
+
+        // Without a session of its own, an engine reads the persisted one
+        let observer = crate::Engine::with_store(store);
+        assert!(observer.session_elapsed_seconds().unwrap().is_some_and(|seconds| seconds >= 90));
+
+        engine.stop_session().unwrap();
+        assert_eq!(engine.session_elapsed_seconds().unwrap(), None);
+        assert_eq!(observer.session_elapsed_seconds().unwrap(), None);
+    }
+
+    #[test]
+    fn test_file_diff_line_stats() {
+        let diff = crate::FileDiff {
+            path: "src/a.ts".to_string(),
+            original_content: "a\nb\nc\nd\n".to_string(),
+            modified_content: "a\nc\nd\ne\nf\n".to_string(),
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+            kind: crate::FileChangeKind::Modified,
+            renamed_from: None,
+        };
+
+        assert_eq!(diff.line_stats(), (2, 1));
+    }
+
+    #[test]
+    fn test_csv_quoting() {
+        assert_eq!(crate::diff::csv_field("src/plain.ts"), "src/plain.ts");
+        assert_eq!(crate::diff::csv_field("a,b.ts"), "\"a,b.ts\"");
+        assert_eq!(crate::diff::csv_field("say \"hi\".ts"), "\"say \"\"hi\"\".ts\"");
+        assert_eq!(crate::diff::csv_field("two\nlines.ts"), "\"two\nlines.ts\"");
+        assert_eq!(crate::diff::csv_field("carriage\r.ts"), "\"carriage\r.ts\"");
+    }
+
+    #[test]
+    fn test_find_cycles() {
+        let edges: std::collections::HashMap<String, Vec<String>> = [
+            ("b.ts", vec!["a.ts"]),
+            ("a.ts", vec!["b.ts", "c.ts"]),
+            ("c.ts", vec![]),
+            ("self.ts", vec!["self.ts"]),
+        ]
+        .into_iter()
+        .map(|(file, targets)| (file.to_string(), targets.into_iter().map(String::from).collect()))
+        .collect();
+
+        let cycles = crate::dep_graph::find_cycles_in(&edges);
+
+        assert_eq!(cycles, vec![vec!["a.ts", "b.ts"], vec!["self.ts"]]);
+    }
+
+    #[test]
+    fn test_reexport_cycles() {
+        let root = write_workspace(&[
+            ("a/index.ts", "export * from '../b';\nexport const a = 1;\n"),
+            ("b/index.ts", "export { a as fromA } from '../a';\nexport const b = 1;\n"),
+            ("app.ts", "import { a, b } from './a';\n"),
+            ("x.ts", "import { y } from './y';\nexport const x = 1;\n"),
+            ("y.ts", "import { x } from './x';\nexport const y = 1;\n"),
+        ]);
+
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        let graph = builder.get_graph();
+        assert_eq!(graph.reexport_edges["a/index.ts"], ["b/index.ts"]);
+        assert_eq!(graph.reexport_edges["b/index.ts"], ["a/index.ts"]);
+        assert!(graph.nodes["b/index.ts"].exports.contains(&"fromA".to_string()));
+
+        // The plain import cycle between x and y isn't a re-export cycle
+        assert_eq!(builder.reexport_cycles(), [["a/index.ts", "b/index.ts"]]);
+        assert!(builder.find_cycles().contains(&vec!["x.ts".to_string(), "y.ts".to_string()]));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_suggest_semver_bump() {
+        let change = |old: &str, new: &str| vec![("lib.ts".to_string(), old.to_string(), new.to_string())];
+        let base = "export function parse(input: string): Ast {\n  return build(input);\n}\n";
+
+        let patch = change(base, "export function parse(input: string): Ast {\n  return build(input.trim());\n}\n");
+        assert_eq!(crate::suggest_semver_bump(&patch), crate::SemverBump::Patch);
+
+        let minor = change(base, &format!("{}export const VERSION = '2';\n", base));
+        assert_eq!(crate::suggest_semver_bump(&minor), crate::SemverBump::Minor);
+
+        let major = change(base, "export function parse(input: string, strict: boolean): Ast {\n}\n");
+        assert_eq!(crate::suggest_semver_bump(&major), crate::SemverBump::Major);
+
+        let removed = change(base, "function parse(input: string): Ast {\n}\n");
+        assert_eq!(crate::suggest_semver_bump(&removed), crate::SemverBump::Major);
+
+        let mut mixed = patch.clone();
+        mixed.extend(minor);
+        assert_eq!(crate::suggest_semver_bump(&mixed), crate::SemverBump::Minor);
+    }
+
+    #[test]
+    fn test_parse_cache_evicts_least_recently_used() {
+        let mut engine = crate::Engine::new();
+        engine.set_parse_cache_capacity(2);
+        let diff = |engine: &crate::Engine, path: &str| {
+            let change = (path.to_string(), "let a = 1;\n".to_string(), "let a = 2;\n".to_string());
+            engine.compute_ast_diffs(&[change]).unwrap();
+        };
+
+        diff(&engine, "a.ts");
+        diff(&engine, "b.ts");
+        // A hit on a.ts makes b.ts the one to go
+        diff(&engine, "a.ts");
+        diff(&engine, "c.ts");
+        assert_eq!(engine.parse_cache_paths(), ["a.ts", "c.ts"]);
+
+        engine.set_parse_cache_capacity(1);
+        assert_eq!(engine.parse_cache_paths(), ["c.ts"]);
+        engine.clear_parse_cache();
+        assert!(engine.parse_cache_paths().is_empty());
+    }
+
+    #[test]
+    fn test_risk_contributors() {
+        let root = write_workspace(&[
+            ("core.scss", "$core: 1;\n"),
+            ("util.scss", "$util: 1;\n"),
+            ("leaf.scss", "$leaf: 1;\n"),
+            ("a.scss", "@import 'core';\n@import 'util';\n"),
+            ("b.scss", "@import 'core';\n"),
+            ("c.scss", "@import 'core';\n"),
+        ]);
+        let changed: Vec<String> = ["leaf.scss", "util.scss", "core.scss"].iter().map(|file| file.to_string()).collect();
+
+        let mut engine = crate::Engine::new();
+        assert!(engine.risk_contributors(&changed).is_err());
+        engine.build_dependency_graph(&root.to_string_lossy()).unwrap();
+
+        let contributors: Vec<(String, usize, f64)> = engine.risk_contributors(&changed).unwrap().into_iter()
+            .map(|contribution| (contribution.file, contribution.impacted_count, contribution.share))
+            .collect();
+        assert_eq!(contributors, [
+            ("core.scss".to_string(), 3, 1.0),
+            ("util.scss".to_string(), 1, 1.0 / 3.0),
+            ("leaf.scss".to_string(), 0, 0.0),
+        ]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_tests_as_sinks() {
+        let root = write_workspace(&[
+            ("src/a.scss", "$a: 1;\n"),
+            ("src/a.test.scss", "@import 'a';\n"),
+            ("src/a.check.scss", "@import 'a';\n"),
+            ("tools/run.scss", "@import '../src/a.test.scss';\n"),
+            ("tools/verify.scss", "@import '../src/a.check.scss';\n"),
+        ]);
+        let changed = vec!["src/a.scss".to_string()];
+        let impacted = |builder: &DependencyGraphBuilder, changed: &[String]| {
+            let mut files = builder.analyze_impact(changed).impacted_files;
+            files.sort();
+            files
+        };
+
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+        assert_eq!(impacted(&builder, &changed), ["src/a.check.scss", "src/a.test.scss", "tools/run.scss", "tools/verify.scss"]);
+
+        // Tests are still impacted, but what imports them is not reached through them
+        builder.set_tests_as_sinks(true);
+        assert_eq!(impacted(&builder, &changed), ["src/a.check.scss", "src/a.test.scss", "tools/verify.scss"]);
+        assert!(impacted(&builder, &["src/a.test.scss".to_string()]).is_empty());
+
+        builder.set_test_patterns(vec!["**/*.check.*".to_string()]);
+        assert!(builder.is_test_file("src/a.check.scss"));
+        assert!(!builder.is_test_file("src/a.test.scss"));
+        assert_eq!(impacted(&builder, &changed), ["src/a.check.scss", "src/a.test.scss", "tools/run.scss"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_graph_metrics() {
+        let root = write_workspace(&[
+            ("a.scss", "@import 'b';\n$a: 1;\n"),
+            ("b.scss", "@import 'a';\n$b: 1;\n"),
+            ("c.scss", "@import 'b';\n"),
+            ("d.scss", "$d: 1;\n"),
+        ]);
+
+        let mut engine = crate::Engine::new();
+        assert!(engine.graph_metrics().is_err());
+        engine.build_dependency_graph(&root.to_string_lossy()).unwrap();
+
+        // 3 edges among 4 files: a <-> b and c -> b, with d on its own
+        let metrics = engine.graph_metrics().unwrap();
+        assert_eq!(metrics.density, 3.0 / 12.0);
+        assert_eq!(metrics.avg_out_degree, 0.75);
+        assert_eq!(metrics.max_out_degree, 1);
+        assert_eq!(metrics.num_components, 2);
+        assert_eq!(metrics.num_cycles, 1);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_ambient_module_declarations() {
+        let root = write_workspace(&[
+            ("types/some-lib.d.ts", "declare module 'some-lib' {\n  export function helper(): void;\n}\n"),
+            ("src/consumer.ts", "import type { helper } from 'some-lib';\nimport React from 'react';\n"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        let graph = builder.get_graph();
+        assert_eq!(graph.edges["src/consumer.ts"], vec!["types/some-lib.d.ts#some-lib"]);
+        assert!(builder.has_edge("src/consumer.ts", "types/some-lib.d.ts#some-lib"));
+        assert!(!builder.has_edge("types/some-lib.d.ts#some-lib", "src/consumer.ts"));
+        assert!(!builder.has_edge("src/unknown.ts", "src/consumer.ts"));
+        assert_eq!(graph.nodes["src/consumer.ts"].external_imports, vec!["some-lib", "react"]);
+
+        let mut impacted = builder.analyze_impact(&["types/some-lib.d.ts".to_string()]).impacted_files;
+        impacted.sort();
+        assert_eq!(impacted, vec!["src/consumer.ts"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_generic_fallback_for_unsupported_files() {
+        let old_content = "alpha\nbeta\ngamma\n";
+        let new_content = "alpha\ngamma\ndelta\n";
+
+        assert!(AstDiffEngine::new().compute_diff("notes.txt", old_content, new_content).is_err());
+
+        let engine = AstDiffEngine::new().with_generic_fallback(true);
+        let diff = engine.compute_diff("notes.txt", old_content, new_content).unwrap();
+
+        assert_eq!(diff.changes.len(), 2);
+        assert!(diff.changes.iter().all(|change| change.node_type == "Line"));
+        assert!(matches!(diff.changes[0].change_type, ChangeType::Removed));
+        assert_eq!(diff.changes[0].line_range, (2, 2));
+        assert_eq!(diff.changes[0].old_content.as_deref(), Some("beta"));
+        assert!(matches!(diff.changes[1].change_type, ChangeType::Added));
+        assert_eq!(diff.changes[1].line_range, (3, 3));
+        assert_eq!(diff.changes[1].new_content.as_deref(), Some("delta"));
+    }
+
+    #[test]
+    fn test_unified_hunks() {
+        // What the session patch emits under the headers of an added, deleted and modified file
+        assert_eq!(crate::diff::unified_hunks("", "export const add = 1;\n", 3), "@@ -0,0 +1,1 @@\n+export const add = 1;\n");
+        assert_eq!(crate::diff::unified_hunks("export const drop = 1;\n", "", 3), "@@ -1,1 +0,0 @@\n-export const drop = 1;\n");
+        assert_eq!(
+            crate::diff::unified_hunks("export const keep = 1;\nexport const edit = 1;\n", "export const keep = 1;\nexport const edit = 2;\n", 3),
+            "@@ -1,2 +1,2 @@\n export const keep = 1;\n-export const edit = 1;\n+export const edit = 2;\n",
+        );
+        assert_eq!(crate::diff::unified_hunks("a\n", "a", 3), "@@ -1,1 +1,1 @@\n-a\n+a\n\\ No newline at end of file\n");
+        assert_eq!(crate::diff::unified_hunks("same\n", "same\n", 3), "");
+    }
+
+    #[test]
+    fn test_checkpoints() {
+        let root = write_workspace(&[
+            ("a.ts", "let a = 1;\n"),
+            ("b.ts", "let b = 1;\n"),
+        ]);
+        let path = |file: &str| root.join(file).to_string_lossy().to_string();
+
+        assert!(crate::Engine::new().checkpoint("first").is_err());
+
+        let mut tracker = crate::DiffTracker::new_lazy(&path(".shadow"));
+        tracker.start_tracking(&root).unwrap();
+        for file in ["a.ts", "b.ts", "c.ts"] {
+            tracker.record_change(&path(file), None).unwrap();
+        }
+
+        std::fs::write(root.join("a.ts"), "let a = 2;\n").unwrap();
+        tracker.checkpoint("first").unwrap();
+        assert!(tracker.checkpoint("first").is_err());
+
+        std::fs::write(root.join("a.ts"), "let a = 3;\n").unwrap();
+        std::fs::write(root.join("c.ts"), "let c = 1;\n").unwrap();
+
+        // Only what changed since the checkpoint, compared to its contents
+        let diffs = tracker.diff_against("first").unwrap();
+        let mut changes: Vec<(&str, &str, &str)> = diffs.iter()
+            .map(|diff| (diff.path.as_str(), diff.original_content.as_str(), diff.modified_content.as_str()))
+            .collect();
+        changes.sort();
+        assert_eq!(changes, [
+            (path("a.ts").as_str(), "let a = 2;\n", "let a = 3;\n"),
+            (path("c.ts").as_str(), "", "let c = 1;\n"),
+        ]);
+
+        assert!(tracker.diff_against("missing").is_err());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_import_mismatches() {
+        let root = write_workspace(&[
+            ("lib.ts", "export function used() {}\nexport default class Main {}\nconst local = 1;\nexport { local as aliased };\n"),
+            ("app.ts", "import Main, { used, missing } from './lib';\nimport { aliased, local } from './lib';\n"),
+        ]);
+
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        let mut exports = builder.get_graph().nodes["lib.ts"].exports.clone();
+        exports.sort();
+        assert_eq!(exports, ["aliased", "default", "used"]);
+
+        // The default import matches `export default`; `local` is only exported under its alias
+        let mismatches = builder.import_mismatches();
+        let names: Vec<(&str, &str)> = mismatches.iter()
+            .map(|(file, _, name)| (file.as_str(), name.as_str()))
+            .collect();
+        assert_eq!(names, [("app.ts", "local"), ("app.ts", "missing")]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_pluggable_risk_scorer() {
+        let mut builder = DependencyGraphBuilder::new(".");
+        let changed = vec!["a.ts".to_string()];
+
+        builder.set_risk_scorer(Box::new(|context: &crate::ImpactContext| {
+            if context.changed_count >= 1 { RiskLevel::High } else { RiskLevel::Low }
+        }));
+        assert!(matches!(builder.analyze_impact(&changed).risk_level, RiskLevel::High));
+
+        builder.set_risk_preset(crate::RiskPreset::Default);
+        assert!(matches!(builder.analyze_impact(&changed).risk_level, RiskLevel::Low));
+
+        let many: Vec<String> = (0..6).map(|i| format!("src/file{}.ts", i)).collect();
+        builder.set_risk_preset(crate::RiskPreset::Lenient);
+        assert!(matches!(builder.analyze_impact(&many).risk_level, RiskLevel::Medium));
+
+        let root_files = vec!["a.ts".to_string(), "b.ts".to_string()];
+        assert!(!builder.impact_context(&root_files, &[]).cross_package);
+        assert!(!builder.impact_context(&many, &["src/lib/util.ts".to_string()]).cross_package);
+        assert!(builder.impact_context(&root_files, &["src/c.ts".to_string()]).cross_package);
+    }
+
+    #[test]
+    fn test_python_parser_nesting() {
+        let engine = AstDiffEngine::new();
+        let content = "import os, sys\nfrom pkg.util import helper\n\nclass Greeter(Base):\n    def greet(self,\n    name):\n        return name\n\n    async def wait(self):\n        pass\n\ndef main():\n    # comment\n    return Greeter()\n";
+        let ast = engine.parse("app.py", content).unwrap();
+
+        let names: Vec<_> = ast.children.iter()
+            .map(|node| (node.node_type.as_str(), node.name.as_deref().unwrap(), node.start_line, node.end_line))
+            .collect();
+        assert_eq!(names, vec![
+            ("ImportDeclaration", "os", 1, 1),
+            ("ImportDeclaration", "sys", 1, 1),
+            ("ImportDeclaration", "pkg.util", 2, 2),
+            ("ClassDeclaration", "Greeter", 4, 10),
+            ("FunctionDeclaration", "main", 12, 14),
+        ]);
+
+        let methods: Vec<_> = ast.children[3].children.iter()
+            .map(|node| (node.name.as_deref().unwrap(), node.start_line, node.end_line))
+            .collect();
+        assert_eq!(methods, vec![("greet", 5, 7), ("wait", 9, 10)]);
+        assert!(engine.has_parser("stubs.pyi"));
+    }
+
+    #[test]
+    fn test_typescript_end_lines() {
+        let parser = TypeScriptParser::new();
+        let content = "function one() { return 1 }\nfunction braces() {\n  const s = \"}\";\n  const t = `${s} }`;\n  // }\n  if (s) {\n    return t;\n  }\n}\nconst inc = (x) => x + 1\nclass Box {\n  /* } */\n  value = 1;\n}\n";
+        let ast = parser.parse(content).unwrap();
+
+        let ranges: Vec<_> = ast.children.iter()
+            .map(|node| (node.name.as_deref().unwrap(), node.start_line, node.end_line))
+            .collect();
+        assert_eq!(ranges, vec![("one", 1, 1), ("braces", 2, 9), ("inc", 10, 10), ("Box", 11, 14)]);
+    }
+
+    #[test]
+    fn test_typescript_interfaces_and_type_aliases() {
+        let parser = TypeScriptParser::new();
+        let content = "export interface Shape<T> {\n  area(): number;\n  meta: { id: T };\n}\ntype Id = string;\nexport type Status =\n  | 'open'\n  | 'closed'\n\nexport type { Shape as S } from './shape';\ninterface Empty {}\n";
+        let ast = parser.parse(content).unwrap();
+
+        let nodes: Vec<_> = ast.children.iter()
+            .map(|node| (node.node_type.as_str(), node.name.as_deref().unwrap(), node.start_line, node.end_line))
+            .collect();
+        assert_eq!(nodes, vec![
+            ("InterfaceDeclaration", "Shape", 1, 4),
+            ("TypeAlias", "Id", 5, 5),
+            ("TypeAlias", "Status", 6, 8),
+            ("InterfaceDeclaration", "Empty", 11, 11),
+        ]);
+    }
+
+    #[test]
+    fn test_typescript_methods_nest_under_classes() {
+        let engine = AstDiffEngine::new();
+        let old = "function render() {\n  return 1;\n}\nclass View {\n  render() {\n    return 2;\n  }\n  private async load(id: string): Promise<void> {\n  }\n}\n";
+        let new = "function render() {\n  return 1;\n}\nclass View {\n  render() {\n    return 2;\n  }\n  private async fetch(id: string): Promise<void> {\n    await this.cache.refresh(id, { force: true });\n  }\n}\n";
+
+        let ast = engine.parse("view.ts", old).unwrap();
+        assert_eq!(ast.children.len(), 2);
+        let methods: Vec<_> = ast.children[1].children.iter()
+            .map(|node| (node.node_type.as_str(), node.name.as_deref().unwrap(), node.start_line, node.end_line))
+            .collect();
+        assert_eq!(methods, vec![("MethodDefinition", "render", 5, 7), ("MethodDefinition", "load", 8, 9)]);
+
+        let diff = engine.compute_diff("view.ts", old, new).unwrap();
+        let mut changes: Vec<_> = diff.changes.iter()
+            .map(|change| (format!("{:?}", change.change_type), change.node_type.as_str(), change.name.as_deref().unwrap()))
+            .collect();
+        changes.sort();
+        assert_eq!(changes, vec![
+            ("Added".to_string(), "MethodDefinition", "fetch"),
+            ("Removed".to_string(), "MethodDefinition", "load"),
+        ]);
+    }
+
+    #[test]
+    fn test_body_hash_detects_edits_but_ignores_reindent() {
+        let engine = AstDiffEngine::new();
+        let old = "function total(a, b) {\n  return a + b;\n}\n";
+        let edited = "function total(a, b) {\n  return a - b;\n}\n";
+        let reindented = "function total(a, b) {\n        return a   +   b;\n}\n";
+
+        let diff = engine.compute_diff("math.ts", old, edited).unwrap();
+        assert_eq!(diff.changes.len(), 1);
+        assert!(matches!(diff.changes[0].change_type, crate::ast_diff::ChangeType::Modified));
+
+        let diff = engine.compute_diff("math.ts", old, reindented).unwrap();
+        assert!(diff.changes.is_empty());
+
+        let diff = engine.compute_diff("math.py", "def total(a, b):\n    return a + b\n", "def total(a, b):\n    return a * b\n").unwrap();
+        assert_eq!(diff.changes.len(), 1);
+    }
+
+    #[test]
+    fn test_rename_detection() {
+        let engine = AstDiffEngine::new();
+        let old = "function oldName(a, b) {\n  const sum = a + b;\n  return sum * 2;\n}\nfunction other() {\n  return 'x';\n}\n";
+        let new = "function newName(a, b) {\n  const sum = a + b;\n  return sum * 2;\n}\nfunction fresh() {\n  return [1, 2, 3].map((n) => n * n);\n}\n";
+
+        let diff = engine.compute_diff("names.ts", old, new).unwrap();
+        let renamed: Vec<_> = diff.changes.iter()
+            .filter_map(|change| match &change.change_type {
+                ChangeType::Renamed { old_name, new_name } => Some((old_name.as_str(), new_name.as_str())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(renamed, vec![("oldName", "newName")]);
+
+        // `other` -> `fresh` differs too much to count as a rename
+        assert_eq!(diff.changes.len(), 3);
+        assert!(diff.changes.iter().any(|change| matches!(change.change_type, ChangeType::Removed) && change.name.as_deref() == Some("other")));
+    }
+
+    #[test]
+    fn test_register_parser_overrides_builtin() {
+        struct StubParser;
+
+        impl AstParser for StubParser {
+            fn parse(&self, _content: &str) -> Result<AstNode, crate::ShadowError> {
+                Ok(AstNode {
+                    node_type: "Program".to_string(),
+                    name: Some("stub".to_string()),
+                    start_line: 1,
+                    end_line: 1,
+                    children: Vec::new(),
+                    body_hash: None,
+                    modifiers: Vec::new(),
+                    qualified_name: None,
+                })
+            }
+
+            fn supported_extensions(&self) -> Vec<&'static str> {
+                vec!["ts", "vue"]
+            }
+        }
+
+        let mut empty = AstDiffEngine::new_empty();
+        assert!(!empty.has_parser("app.ts"));
+        empty.register_parser(Box::new(StubParser));
+        assert!(empty.has_parser("App.vue"));
+
+        let mut engine = AstDiffEngine::new();
+        engine.register_parser(Box::new(StubParser));
+        assert_eq!(engine.parse("app.ts", "function f() {}").unwrap().name.as_deref(), Some("stub"));
+        assert!(engine.has_parser("app.js"));
+    }
+
+    #[test]
+    fn test_compute_unified_diff() {
+        let root = write_workspace(&[("notes.ts", "a\nb\nc\nd\ne\nf\ng\nh\n")]);
+        let path = root.join("notes.ts").to_string_lossy().to_string();
+
+        let mut tracker = crate::DiffTracker::new_lazy(".shadow");
+        tracker.record_change(&path, Some("a\nb\nc\nd\nE\nf\ng\nh\n")).unwrap();
+
+        let diff = tracker.compute_unified_diff_with_context(&path, 1).unwrap();
+        assert!(diff.starts_with("--- a/"));
+        assert!(diff.ends_with("@@ -4,3 +4,3 @@\n d\n-E\n+e\n f\n"));
+        assert_eq!(tracker.compute_unified_diff(&path).unwrap().lines().filter(|line| line.starts_with(' ')).count(), 6);
+        assert!(tracker.compute_unified_diff(&root.join("other.ts").to_string_lossy()).is_none());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_load_persisted_diffs() {
+        let root = write_workspace(&[]);
+        let diff = |path: &str| crate::FileDiff {
+            path: path.to_string(),
+            original_content: "before".to_string(),
+            modified_content: "after".to_string(),
+            timestamp: "2024-01-01T00:00:00+00:00".to_string(),
+            kind: crate::FileChangeKind::Modified,
+            renamed_from: None,
+        };
+
+        let db = crate::Store::open(&root.join("session.db")).unwrap();
+        db.put("diff/s1/a.ts", serde_json::to_vec(&diff("a.ts")).unwrap()).unwrap();
+        db.put("diff/s10/b.ts", serde_json::to_vec(&diff("b.ts")).unwrap()).unwrap();
+
+        // The diffs outlive the process that wrote them
+        drop(db);
+        let db = crate::Store::open(&root.join("session.db")).unwrap();
+        let restored: Vec<String> = crate::DiffTracker::load_diffs(&db, "s1").unwrap()
+            .into_iter()
+            .map(|diff| diff.path)
+            .collect();
+        assert_eq!(restored, vec!["a.ts"]);
+        assert!(crate::DiffTracker::load_diffs(&db, "s2").unwrap().is_empty());
+
+        drop(db);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_capture_changes_and_persist() {
+        let root = write_workspace(&[("kept.ts", "same"), ("edited.ts", "after"), ("created.ts", "new")]);
+        let path = |name: &str| root.join(name).to_string_lossy().to_string();
+
+        let mut tracker = crate::DiffTracker::new_lazy(".shadow");
+        tracker.record_change(&path("kept.ts"), None).unwrap();
+        tracker.record_change(&path("edited.ts"), Some("before")).unwrap();
+        tracker.record_change(&path("created.ts"), Some("")).unwrap();
+        tracker.record_change(&path("removed.ts"), Some("gone")).unwrap();
+
+        tracker.capture_changes().unwrap();
+        assert_eq!(tracker.get_diff_count().unwrap(), 3);
+        assert_eq!(tracker.to_csv().lines().filter(|line| line.ends_with(",deleted")).count(), 1);
+
+        let db = crate::Store::open(&root.join("session.db")).unwrap();
+        tracker.persist_diffs(&db, "s1").unwrap();
+
+        let mut restored: Vec<String> = crate::DiffTracker::load_diffs(&db, "s1").unwrap()
+            .into_iter()
+            .map(|diff| diff.modified_content)
+            .collect();
+        restored.sort();
+        assert_eq!(restored, vec!["", "after", "new"]);
+        assert!(crate::DiffTracker::load_diffs(&db, "s2").unwrap().is_empty());
+
+        // The diffs outlive the process that wrote them
+        drop(db);
+        let db = crate::Store::open(&root.join("session.db")).unwrap();
+        assert_eq!(crate::DiffTracker::load_diffs(&db, "s1").unwrap().len(), 3);
+
+        drop(db);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_lazy_capture_read_errors() {
+        let root = write_workspace(&[("edited.ts", "after"), ("removed.ts", "gone")]);
+        let path = |name: &str| root.join(name).to_string_lossy().to_string();
+
+        let mut tracker = crate::DiffTracker::new_lazy(".shadow");
+        tracker.record_change(&path("edited.ts"), Some("before")).unwrap();
+        tracker.record_change(&path("removed.ts"), None).unwrap();
+
+        std::fs::remove_file(root.join("removed.ts")).unwrap();
+        tracker.capture_changes().unwrap();
+        assert_eq!(tracker.get_diff_count().unwrap(), 2);
+        assert_eq!(tracker.to_csv().lines().filter(|line| line.ends_with(",deleted")).count(), 1);
+
+        // A file that exists but can't be read fails the capture instead of passing as deleted
+        std::fs::remove_file(root.join("edited.ts")).unwrap();
+        std::fs::create_dir(root.join("edited.ts")).unwrap();
+        assert!(tracker.capture_changes().is_err());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_activity_timeline() {
+        let root = write_workspace(&[
+            ("a.ts", "let a = 1;\n"),
+            ("b.ts", "let b = 1;\n"),
+        ]);
+        let path = |name: &str| root.join(name).to_string_lossy().to_string();
+
+        let mut tracker = crate::DiffTracker::new_lazy(".shadow");
+        tracker.record_change(&path("a.ts"), None).unwrap();
+        tracker.record_change(&path("b.ts"), None).unwrap();
+        assert!(tracker.activity_timeline().is_empty());
+
+        std::fs::write(root.join("b.ts"), "let b = 2;\n").unwrap();
+        tracker.capture_changes().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        std::fs::write(root.join("a.ts"), "let a = 2;\n").unwrap();
+        tracker.capture_changes().unwrap();
+
+        // b.ts changed first and keeps its time through the second capture
+        let timeline = tracker.activity_timeline();
+        let paths: Vec<&str> = timeline.iter().map(|(_, path)| path.as_str()).collect();
+        assert_eq!(paths, [path("b.ts"), path("a.ts")]);
+        assert!(timeline[0].0 < timeline[1].0);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_scan_respects_gitignore() {
+        let root = write_workspace(&[
+            (".gitignore", "dist/\n*.gen.ts\n!keep.gen.ts\n"),
+            ("src/app.ts", ""),
+            ("src/app.gen.ts", ""),
+            ("src/keep.gen.ts", ""),
+            ("dist/app.js", ""),
+            ("pkg/.gitignore", "/local.ts\n"),
+            ("pkg/local.ts", ""),
+            ("pkg/nested/local.ts", ""),
+        ]);
+
+        let scan = |respect: bool| {
+            let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+            builder.set_respect_gitignore(respect);
+            let mut files = Vec::new();
+            builder.scan_streaming(|node| files.push(node.file_path.clone())).unwrap();
+            files.sort();
+            files
+        };
+
+        assert_eq!(scan(true), vec!["pkg/nested/local.ts", "src/app.ts", "src/keep.gen.ts"]);
+        assert_eq!(scan(false).len(), 6);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_tracked_extensions() {
+        let mut tracker = crate::DiffTracker::new_lazy(".shadow");
+        assert!(tracker.set_tracked_extensions(Vec::new()).is_err());
+        assert!(tracker.set_tracked_extensions(vec![".".to_string()]).is_err());
+        assert_eq!(tracker.tracked_extensions().len(), 5);
+
+        tracker.set_tracked_extensions(vec![".Svelte".to_string(), "mjs".to_string()]).unwrap();
+        assert_eq!(tracker.tracked_extensions(), ["svelte", "mjs"]);
+    }
+
+    #[test]
+    fn test_binary_and_oversized_files_are_skipped() {
+        let root = write_workspace(&[("data.json", "{}"), ("big.ts", "0123456789"), ("ok.ts", "let a")]);
+        std::fs::write(root.join("data.json"), [b'{', 0, b'}']).unwrap();
+        let path = |name: &str| root.join(name).to_string_lossy().to_string();
+
+        let mut tracker = crate::DiffTracker::new_lazy(".shadow");
+        tracker.set_max_file_bytes(8);
+        for name in ["data.json", "big.ts", "ok.ts"] {
+            tracker.record_change(&path(name), None).unwrap();
+        }
+
+        let skipped = tracker.skipped_files();
+        assert_eq!(skipped.len(), 2);
+        assert!(skipped.iter().all(|file| file.ends_with("data.json") || file.ends_with("big.ts")));
+
+        std::fs::write(root.join("ok.ts"), "let a = 1; // now too long").unwrap();
+        tracker.capture_changes().unwrap();
+        assert_eq!(tracker.get_diff_count().unwrap(), 0);
+        assert_eq!(tracker.skipped_files().len(), 3);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_find_cycles_over_import_edges() {
+        let root = write_workspace(&[
+            ("styles/c.scss", "@import 'a';\n"),
+            ("styles/a.scss", "@import 'b';\n"),
+            ("styles/b.scss", "@import 'c';\n"),
+            ("styles/self.scss", "@import 'self';\n"),
+            ("styles/leaf.scss", "@import 'a';\n"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        assert_eq!(builder.find_cycles(), vec![
+            vec!["styles/a.scss", "styles/b.scss", "styles/c.scss"],
+            vec!["styles/self.scss"],
+        ]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let root = write_workspace(&[
+            ("app.scss", "@import 'layout';\n@import 'tokens';\n"),
+            ("layout.scss", "@import 'tokens';\n"),
+            ("tokens.scss", "$gap: 4px;\n"),
+            ("reset.scss", ""),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+        assert_eq!(builder.topological_order().unwrap(), vec!["reset.scss", "tokens.scss", "layout.scss", "app.scss"]);
+
+        std::fs::write(root.join("tokens.scss"), "@import 'app';\n").unwrap();
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+        let error = builder.topological_order().unwrap_err();
+        assert_eq!(error.cycles, vec![vec!["app.scss", "layout.scss", "tokens.scss"]]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_tsconfig_path_aliases() {
+        let root = write_workspace(&[
+            ("tsconfig.json", "{\n  // aliases\n  \"compilerOptions\": {\n    \"baseUrl\": \".\",\n    \"paths\": { \"@app/*\": [\"missing/*\", \"src/*\"], \"@config\": [\"config/index.ts\"] }\n  }\n}\n"),
+            ("src/utils.ts", "export const x = 1;\n"),
+            ("config/index.ts", "export default {};\n"),
+            ("main.ts", "import { x } from '@app/utils';\nimport config from '@config';\nimport { y } from '@app/nowhere';\nimport React from 'react';\n"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        let mut edges = builder.get_graph().edges["main.ts"].clone();
+        edges.sort();
+        assert_eq!(edges, vec!["config/index.ts", "src/utils.ts"]);
+        assert_eq!(builder.get_graph().nodes["main.ts"].external_imports, vec!["@app/nowhere", "react"]);
+
+        std::fs::rename(root.join("tsconfig.json"), root.join("tsconfig.base.json")).unwrap();
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+        assert!(builder.get_graph().edges["main.ts"].is_empty());
+
+        builder.set_tsconfig_path("tsconfig.base.json");
+        builder.build_graph().unwrap();
+        assert_eq!(builder.get_graph().edges["main.ts"].len(), 2);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_directory_imports_resolve_to_index_files() {
+        let root = write_workspace(&[
+            ("src/components/index.ts", "export * from './Button';\n"),
+            ("src/components/Button.tsx", "export const Button = 1;\n"),
+            ("src/widgets/index.jsx", "export default 1;\n"),
+            ("src/legacy.js", "module.exports = {};\n"),
+            ("src/app.ts", "import { Button } from './components';\nimport widgets from './widgets/';\nconst legacy = require('./legacy');\n"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        let mut edges = builder.get_graph().edges["src/app.ts"].clone();
+        edges.sort();
+        assert_eq!(edges, vec!["src/components/index.ts", "src/legacy.js", "src/widgets/index.jsx"]);
+        assert_eq!(builder.get_graph().reexport_edges["src/components/index.ts"], vec!["src/components/Button.tsx"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_dynamic_imports() {
+        let root = write_workspace(&[
+            ("src/lazy.ts", "export default 1;\n"),
+            ("src/tpl.ts", "export default 2;\n"),
+            ("src/main.ts", "const m = await import('./lazy');\nconst t = require(`./tpl`);\nconst d = await import(`./pages/${name}`);\nconst c = await import( \"chart.js\" );\nconst r = reimport('./nope');\n"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        let mut edges = builder.get_graph().edges["src/main.ts"].clone();
+        edges.sort();
+        assert_eq!(edges, vec!["src/lazy.ts", "src/tpl.ts"]);
+        assert_eq!(builder.get_graph().nodes["src/main.ts"].external_imports, vec!["chart.js"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_graph_to_dot() {
+        let root = write_workspace(&[
+            ("b.scss", "@import 'a';\n"),
+            ("a.scss", "$x: 1;\n"),
+            ("say \"hi\".scss", "@import 'b';\n"),
+            ("far.scss", "@import 'say \"hi\"';\n"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+        let graph = builder.get_graph();
+
+        assert_eq!(graph.to_dot(), concat!(
+            "digraph dependencies {\n",
+            "  \"a.scss\";\n",
+            "  \"b.scss\";\n",
+            "  \"far.scss\";\n",
+            "  \"say \\\"hi\\\".scss\";\n",
+            "  \"b.scss\" -> \"a.scss\";\n",
+            "  \"far.scss\" -> \"say \\\"hi\\\".scss\";\n",
+            "  \"say \\\"hi\\\".scss\" -> \"b.scss\";\n",
+            "}\n",
+        ));
+
+        let filtered = graph.to_dot_filtered(&["b.scss".to_string()], 1);
+        assert!(filtered.contains("\"a.scss\";") && filtered.contains("-> \"b.scss\""));
+        assert!(!filtered.contains("far.scss"));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_dependents_of() {
+        let root = write_workspace(&[
+            ("src/date.ts", "export const now = 1;\n"),
+            ("src/format.ts", "import { now } from './date';\n"),
+            ("src/clock.ts", "import { now } from './date';\n"),
+            ("src/app.ts", "import { f } from './format';\n"),
+            ("src/cycle.ts", "import { app } from './app';\nimport { now } from './date';\n"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        assert_eq!(builder.dependents_of("src/date.ts"), vec!["src/clock.ts", "src/cycle.ts", "src/format.ts"]);
+        assert_eq!(
+            builder.transitive_dependents_of("src/date.ts"),
+            vec!["src/app.ts", "src/clock.ts", "src/cycle.ts", "src/format.ts"],
+        );
+        assert!(builder.dependents_of("src/cycle.ts").is_empty());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_depth_limited_impact() {
+        let root = write_workspace(&[
+            ("a.scss", "$x: 1;\n"),
+            ("b.scss", "@import 'a';\n"),
+            ("c.scss", "@import 'b';\n@import 'a';\n"),
+            ("d.scss", "@import 'c';\n"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+        let changed = vec!["a.scss".to_string()];
+
+        let limited = builder.analyze_impact_with_depth(&changed, 1);
+        let mut impacted = limited.impacted_files.clone();
+        impacted.sort();
+        assert_eq!(impacted, vec!["b.scss", "c.scss"]);
+
+        let full = builder.analyze_impact(&changed);
+        assert_eq!(full.impacted_files.len(), 3);
+        assert_eq!(full.depths["b.scss"], 1);
+        assert_eq!(full.depths["c.scss"], 1);
+        assert_eq!(full.depths["d.scss"], 2);
+        assert!(!full.depths.contains_key("a.scss"));
+
+        assert!(builder.analyze_impact_with_depth(&changed, 0).impacted_files.is_empty());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_risk_thresholds() {
+        assert!(crate::RiskThresholds::new(5, 4).is_err());
+
+        let many: Vec<String> = (0..9).map(|i| format!("src/file{}.ts", i)).collect();
+        let builder = DependencyGraphBuilder::new(".");
+        assert!(matches!(builder.analyze_impact(&many).risk_level, RiskLevel::High));
+
+        let builder = DependencyGraphBuilder::new(".")
+            .with_risk_thresholds(crate::RiskThresholds::new(10, 30).unwrap());
+        assert!(matches!(builder.analyze_impact(&many).risk_level, RiskLevel::Low));
+
+        let builder = DependencyGraphBuilder::new(".")
+            .with_risk_thresholds(crate::RiskThresholds::new(3, 3).unwrap());
+        assert!(matches!(builder.analyze_impact(&many[..3]).risk_level, RiskLevel::Low));
+        assert!(matches!(builder.analyze_impact(&many[..4]).risk_level, RiskLevel::High));
+    }
+
+    #[test]
+    fn test_risk_reasons() {
+        let root = write_workspace(&[
+            ("src/date.ts", "export const now = 1;\n"),
+            ("src/format.ts", "import { now } from './date';\n"),
+            ("src/clock.ts", "import { now } from './date';\n"),
+            ("src/index.ts", "import { f } from './format';\n"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        let analysis = builder.analyze_impact(&["src/date.ts".to_string()]);
+        assert_eq!(analysis.risk_reasons, vec![
+            "change reaches entrypoint src/index.ts",
+            "touches file imported by 2 others",
+            "3 files impacted",
+        ]);
+
+        let analysis = builder.analyze_impact(&["src/clock.ts".to_string()]);
+        assert!(analysis.risk_reasons.is_empty());
+
+        // Files at the workspace root share one package
+        let analysis = builder.analyze_impact(&["a.ts".to_string(), "b.ts".to_string()]);
+        assert!(analysis.risk_reasons.is_empty());
+        let analysis = builder.analyze_impact(&["a.ts".to_string(), "src/clock.ts".to_string()]);
+        assert_eq!(analysis.risk_reasons, vec!["change spans multiple top-level directories"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_orphan_files() {
+        let root = write_workspace(&[
+            ("src/date.ts", "export const now = 1;\n"),
+            ("src/format.ts", "import { now } from './date';\n"),
+            ("src/legacy.ts", "export const old = 1;\n"),
+            ("src/main.ts", "export const start = 1;\n"),
+            ("src/index.ts", "export * from './barrel';\n"),
+            ("src/barrel.ts", "export const b = 1;\n"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        assert_eq!(
+            builder.orphan_files(&[]),
+            vec!["src/legacy.ts", "src/main.ts"],
+        );
+        assert_eq!(
+            builder.orphan_files(&["src/main.ts".to_string(), "**/index.ts".to_string()]),
+            vec!["src/legacy.ts"],
+        );
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_unused_exports() {
+        let root = write_workspace(&[
+            ("src/util.ts", "export const used = 1;\nexport const unused = 2;\nexport default function main() {}\n"),
+            ("src/app.ts", "import { used } from './util';\nimport { renamed } from './barrel';\nimport * as all from './star';\n"),
+            ("src/impl.ts", "export const a = 1;\nexport const b = 2;\nexport const c = 3;\n"),
+            ("src/barrel.ts", "export { a, b as renamed } from './impl';\n"),
+            ("src/star.ts", "export const s1 = 1;\nexport const s2 = 2;\n"),
+            ("src/index.ts", "export const api = 1;\n"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        let unused = builder.unused_exports(&[]);
+        assert_eq!(unused.len(), 4);
+        assert_eq!(unused["src/util.ts"], ["unused"]);
+        assert_eq!(unused["src/impl.ts"], ["c"]);
+        assert_eq!(unused["src/barrel.ts"], ["a"]);
+        assert_eq!(unused["src/index.ts"], ["api"]);
+
+        let unused = builder.unused_exports(&["**/index.ts".to_string(), "src/barrel.ts".to_string()]);
+        let mut files: Vec<&String> = unused.keys().collect();
+        files.sort();
+        assert_eq!(files, ["src/impl.ts", "src/util.ts"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_fan_in_weighted_risk() {
+        let mut files = vec![("src/util.ts".to_string(), "export const u = 1;\n".to_string())];
+        for i in 0..20 {
+            files.push((format!("src/page{}.ts", i), "import { u } from './util';\n".to_string()));
+        }
+        let files: Vec<(&str, &str)> = files.iter().map(|(path, content)| (path.as_str(), content.as_str())).collect();
+        let root = write_workspace(&files);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        let leaf = builder.analyze_impact(&["src/page0.ts".to_string()]);
+        assert_eq!(leaf.risk_score, 1.0);
+        assert!(matches!(leaf.risk_level, RiskLevel::Low));
+
+        // Even with no impacted files counted, a widely imported file is high risk
+        let util = builder.analyze_impact_with_depth(&["src/util.ts".to_string()], 0);
+        assert!(util.impacted_files.is_empty());
+        assert_eq!(util.risk_score, 11.0);
+        assert!(matches!(util.risk_level, RiskLevel::High));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_symbol_level_impact() {
+        let root = write_workspace(&[
+            ("src/util.ts", "export function formatDate() {}\nexport function parseDate() {}\n"),
+            ("src/report.ts", "import { formatDate } from './util';\n"),
+            ("src/parser.ts", "import { parseDate } from './util';\n"),
+            ("src/all.ts", "import * as util from './util';\n"),
+            ("src/app.ts", "import { report } from './report';\n"),
+            ("src/barrel.ts", "export { parseDate } from './util';\n"),
+            ("src/reader.ts", "import { parseDate } from './barrel';\n"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        let mut impacted = builder.analyze_symbol_impact(&[("src/util.ts".to_string(), "formatDate".to_string())])
+            .impacted_files;
+        impacted.sort();
+        assert_eq!(impacted, vec!["src/all.ts", "src/app.ts", "src/report.ts"]);
+
+        let analysis = builder.analyze_symbol_impact(&[("src/util.ts".to_string(), "parseDate".to_string())]);
+        let mut impacted = analysis.impacted_files.clone();
+        impacted.sort();
+        assert_eq!(impacted, vec!["src/all.ts", "src/barrel.ts", "src/parser.ts", "src/reader.ts"]);
+        assert_eq!(analysis.depths["src/reader.ts"], 2);
+
+        let analysis = builder.analyze_symbol_impact(&[("./src\\util.ts".to_string(), "formatDate".to_string())]);
+        let mut impacted = analysis.impacted_files.clone();
+        impacted.sort();
+        assert_eq!(impacted, vec!["src/all.ts", "src/app.ts", "src/report.ts"]);
+        assert_eq!(analysis.changed_files, vec!["src/util.ts"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_incremental_graph_update() {
+        let root = write_workspace(&[
+            ("a.scss", "$x: 1;\n"),
+            ("b.scss", "@import 'a';\n"),
+            ("c.scss", "$y: 1;\n"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+        assert!(builder.has_edge("b.scss", "a.scss"));
+
+        std::fs::write(root.join("b.scss"), "@import 'c';\n").unwrap();
+        builder.update_file("b.scss").unwrap();
+        assert!(!builder.has_edge("b.scss", "a.scss"));
+        assert!(builder.has_edge("b.scss", "c.scss"));
+
+        std::fs::write(root.join("d.scss"), "@import 'b';\n").unwrap();
+        builder.update_file("d.scss").unwrap();
+        assert_eq!(builder.dependents_of("b.scss"), vec!["d.scss"]);
+
+        builder.remove_file("c.scss");
+        assert!(!builder.has_edge("b.scss", "c.scss"));
+        assert!(builder.dependents_of("c.scss").is_empty());
+
+        std::fs::remove_file(root.join("d.scss")).unwrap();
+        builder.update_file("d.scss").unwrap();
+        assert!(builder.dependents_of("b.scss").is_empty());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_in_memory_engine_sessions() {
+        let mut engine = crate::Engine::new_in_memory();
+        assert!(!engine.get_status().unwrap().is_active);
+
+        let session_id = engine.start_session().unwrap();
+        let status = engine.get_status().unwrap();
+        assert!(status.is_active);
+        assert_eq!(status.session_id, Some(session_id));
+        assert!(engine.start_session().is_err());
+
+        engine.stop_session().unwrap();
+        assert!(!engine.get_status().unwrap().is_active);
+        assert!(engine.stop_session().is_err());
+
+        let store = crate::Store::in_memory();
+        store.put("diff/s1/b", "2").unwrap();
+        store.put("diff/s1/a", "1").unwrap();
+        store.put("diff/s2/a", "3").unwrap();
+        let values: Vec<Vec<u8>> = store.scan_prefix(b"diff/s1/").unwrap()
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect();
+        assert_eq!(values, vec![b"1".to_vec(), b"2".to_vec()]);
+        store.delete("diff/s1/a").unwrap();
+        assert!(store.get("diff/s1/a").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_session_history() {
+        let mut engine = crate::Engine::new_in_memory();
+        let first = engine.start_session().unwrap();
+        engine.stop_session().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let second = engine.start_session().unwrap();
+
+        let sessions = engine.list_sessions().unwrap();
+        let ids: Vec<&str> = sessions.iter().map(|session| session.id.as_str()).collect();
+        assert_eq!(ids, vec![second.as_str(), first.as_str()]);
+        assert!(sessions[0].end_time.is_none());
+        assert!(sessions[1].duration_seconds().is_some_and(|seconds| seconds >= 0));
+
+        assert!(crate::Engine::new().list_sessions().is_err());
+    }
+
+    #[test]
+    fn test_tracking_uses_session_workspace() {
+        let root = write_workspace(&[("notes.ts", "one\n")]);
+
+        let session = crate::Session {
+            workspace_path: root.join("notes.ts").to_string_lossy().to_string(),
+            ..crate::Session::new()
+        };
+        assert!(session.validate_workspace().unwrap_err().to_string().contains("not a directory"));
+        let session = crate::Session {
+            workspace_path: root.join("missing").to_string_lossy().to_string(),
+            ..crate::Session::new()
+        };
+        assert!(session.validate_workspace().unwrap_err().to_string().contains("does not exist"));
+
+        let mut tracker = crate::DiffTracker::new(".shadow");
+        tracker.start_tracking(&root).unwrap();
+        std::fs::write(root.join("notes.ts"), "two\n").unwrap();
+        tracker.capture_changes().unwrap();
+        assert_eq!(tracker.changed_paths(), vec!["notes.ts"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_session_metadata() {
+        let mut engine = crate::Engine::new_in_memory();
+        assert!(engine.set_session_metadata("branch", "main").is_err());
+        assert!(engine.get_status().unwrap().metadata.is_none());
+
+        engine.start_session().unwrap();
+        engine.set_session_metadata("branch", "feature/login").unwrap();
+        engine.set_session_metadata("pr", "42").unwrap();
+        engine.set_session_metadata("pr", "43").unwrap();
+
+        let metadata = engine.get_status().unwrap().metadata.unwrap();
+        assert_eq!(metadata.len(), 2);
+        assert_eq!(metadata["branch"], "feature/login");
+        assert_eq!(metadata["pr"], "43");
+
+        engine.stop_session().unwrap();
+        let restored: crate::Session = serde_json::from_str(&serde_json::to_string(&engine.list_sessions().unwrap()[0]).unwrap()).unwrap();
+        assert_eq!(restored.metadata, metadata);
+    }
+
+    #[test]
+    fn test_concurrent_sessions_on_shared_store() {
+        let store = std::sync::Arc::new(crate::Store::in_memory());
+        let mut first = crate::Engine::with_store(store.clone());
+        let mut second = crate::Engine::with_store(store);
+
+        let first_id = first.start_session().unwrap();
+        assert!(first.get_status().unwrap().warning.is_none());
+
+        let status = second.get_status().unwrap();
+        assert_eq!(status.session_id, Some(first_id));
+        assert!(status.warning.unwrap().contains("another Shadow instance"));
+        assert!(second.start_session().unwrap_err().to_string().contains("already active"));
+
+        let second_id = second.start_session_with_force(true).unwrap();
+
+        // Nor may the replaced session write its metadata over the newer one
+        first.set_session_metadata("branch", "stale").unwrap();
+        assert_eq!(second.get_status().unwrap().session_id, Some(second_id.clone()));
+        assert!(second.get_status().unwrap().metadata.unwrap().is_empty());
+
+        // Stopping the replaced session must not clear the newer one
+        first.stop_session().unwrap();
+        assert_eq!(first.get_status().unwrap().session_id, Some(second_id));
+        second.stop_session().unwrap();
+        assert!(!first.get_status().unwrap().is_active);
+    }
+
+    // Needs a directory the scan can't read, which a privileged user can still read
+    #[cfg(unix)]
+    #[test]
+    fn test_failed_session_start_is_not_stored() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = write_workspace(&[("a.ts", "export const a = 1;\n"), ("locked/b.ts", "export const b = 1;\n")]);
+        let locked = root.join("locked");
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+        let readable = std::fs::read_dir(&locked).is_ok();
+
+        let mut engine = crate::Engine::new();
+        engine.initialize_in_memory(&root.join(".shadow").to_string_lossy());
+        let mut session = crate::Session::new();
+        session.workspace_path = root.to_string_lossy().to_string();
+        if !readable {
+            assert!(engine.start_new_session(session, false).is_err());
+            assert!(!engine.get_status().unwrap().is_active);
+            assert!(engine.list_sessions().unwrap().is_empty());
+        }
+
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let mut session = crate::Session::new();
+        session.workspace_path = root.to_string_lossy().to_string();
+        engine.start_new_session(session, false).unwrap();
+        assert!(engine.get_status().unwrap().is_active);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_compare_archived_sessions() {
+        let root = write_workspace(&[
+            ("util.ts", "export const u = 1;\n"),
+            ("api.ts", "import { u } from './util';\nexport const api = u;\n"),
+            ("app.ts", "import { api } from './api';\n"),
+        ]);
+
+        let mut engine = crate::Engine::new();
+        engine.initialize_in_memory(&root.join(".shadow").to_string_lossy());
+        engine.build_dependency_graph(&root.to_string_lossy()).unwrap();
+
+        let mut session = crate::Session::new();
+        session.workspace_path = root.to_string_lossy().to_string();
+        let first = engine.start_new_session(session, false).unwrap();
+        std::fs::write(root.join("util.ts"), "export const u = 2;\n").unwrap();
+        engine.stop_session().unwrap();
+
+        let mut session = crate::Session::new();
+        session.workspace_path = root.to_string_lossy().to_string();
+        let second = engine.start_new_session(session, false).unwrap();
+        std::fs::write(root.join("app.ts"), "import { api } from './api';\nconsole.log(api);\n").unwrap();
+        engine.stop_session().unwrap();
+
+        // Stopping archives each session's changed files and diffs
+        let diffs = engine.session_diffs(&first).unwrap();
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(std::path::Path::new(&diffs[0].path), root.join("util.ts"));
+        assert_eq!(diffs[0].modified_content, "export const u = 2;\n");
+
+        let comparison = engine.compare_sessions(&first, &second).unwrap();
+        assert_eq!(comparison.changed_only_in_a, ["util.ts"]);
+        assert_eq!(comparison.changed_only_in_b, ["app.ts"]);
+        let mut impacted = comparison.session_a.impacted_files.clone();
+        impacted.sort();
+        assert_eq!(impacted, ["api.ts", "app.ts"]);
+        assert!(comparison.session_b.impacted_files.is_empty());
+
+        assert!(engine.compare_sessions(&first, "missing").is_err());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_graph_build_progress() {
+        let files: Vec<(String, String)> = (0..120)
+            .map(|i| (format!("src/file{}.ts", i), "export const x = 1;\n".to_string()))
+            .collect();
+        let files: Vec<(&str, &str)> = files.iter().map(|(path, content)| (path.as_str(), content.as_str())).collect();
+        let root = write_workspace(&files);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+
+        let mut reports = Vec::new();
+        builder.build_graph_with_progress(&mut |scanned, total| reports.push((scanned, total))).unwrap();
+        assert_eq!(reports, vec![(50, 120), (100, 120), (120, 120)]);
+        assert_eq!(builder.get_graph().nodes.len(), 120);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_cancelled_graph_build() {
+        let root = write_workspace(&[
+            ("a.scss", "$x: 1;\n"),
+            ("b.scss", "@import 'a';\n"),
+        ]);
+        let flag = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.set_cancel_flag(flag.clone());
+        builder.build_graph().unwrap();
+        assert_eq!(builder.get_graph().nodes.len(), 2);
+
+        flag.store(true, std::sync::atomic::Ordering::Relaxed);
+        let error = builder.build_graph().unwrap_err();
+        assert!(matches!(error, crate::ShadowError::Cancelled));
+        assert!(builder.get_graph().nodes.is_empty());
+        assert!(builder.get_graph().edges.is_empty());
+
+        // A cancel raised while no build runs stops the next one, and only that one
+        let mut engine = crate::Engine::new();
+        engine.cancel();
+        let error = engine.build_dependency_graph(&root.to_string_lossy()).unwrap_err();
+        assert!(matches!(error, crate::ShadowError::Cancelled));
+        engine.build_dependency_graph(&root.to_string_lossy()).unwrap();
+        assert!(engine.has_edge("b.scss", "a.scss").unwrap());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_cancel_graph_build_from_another_thread() {
+        use std::sync::atomic::Ordering;
+        use std::sync::mpsc;
+
+        let sources: Vec<(String, String)> = (0..120)
+            .map(|i| (format!("src/file{:03}.ts", i), format!("export const value{} = {};\n", i, i)))
+            .collect();
+        let files: Vec<(&str, &str)> = sources.iter().map(|(path, content)| (path.as_str(), content.as_str())).collect();
+        let root = write_workspace(&files);
+        let mut engine = crate::Engine::new();
+        engine.build_dependency_graph(&root.to_string_lossy()).unwrap();
+        let impacted = |engine: &crate::Engine| engine.analyze_impact(&["src/file000.ts".to_string()]).unwrap().impacted_files;
+        assert!(impacted(&engine).is_empty());
+
+        std::fs::write(root.join("src/extra.ts"), "import { value0 } from './file000';\n").unwrap();
+        let handle = engine.cancel_handle();
+        let (reached_tx, reached_rx) = mpsc::channel();
+        let (cancelled_tx, cancelled_rx) = mpsc::channel();
+        let canceller = std::thread::spawn(move || {
+            reached_rx.recv().unwrap();
+            handle.store(true, Ordering::Relaxed);
+            cancelled_tx.send(()).unwrap();
+        });
+
+        // The first progress report hands over to the other thread and waits for its cancel
+        let mut reports = 0;
+        let error = engine.build_dependency_graph_with_progress(&root.to_string_lossy(), &mut |_, _| {
+            reports += 1;
+            if reports == 1 {
+                reached_tx.send(()).unwrap();
+                cancelled_rx.recv().unwrap();
+            }
+        }).unwrap_err();
+        canceller.join().unwrap();
+        assert!(matches!(error, crate::ShadowError::Cancelled));
+        assert_eq!(reports, 1);
+
+        // The previous graph stays in place until a build completes
+        assert!(impacted(&engine).is_empty());
+        engine.build_dependency_graph(&root.to_string_lossy()).unwrap();
+        assert_eq!(impacted(&engine), ["src/extra.ts"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_ast_diffs_keep_input_order() {
+        let engine = crate::Engine::new();
+        let changes: Vec<(String, String, String)> = (0..40)
+            .map(|i| (
+                format!("src/file{}.ts", i),
+                String::new(),
+                format!("function f{}() {{\n}}\n", i),
+            ))
+            .collect();
+
+        let diffs = engine.compute_ast_diffs(&changes).unwrap();
+        let paths: Vec<&str> = diffs.iter().map(|diff| diff.file_path.as_str()).collect();
+        let expected: Vec<&str> = changes.iter().map(|(path, _, _)| path.as_str()).collect();
+        assert_eq!(paths, expected);
+        assert!(diffs.iter().all(|diff| !diff.changes.is_empty()));
+    }
+
+    #[test]
+    fn test_engine_dependency_graph() {
+        let mut engine = crate::Engine::new();
+        let error = engine.dependency_graph().unwrap_err();
+        assert_eq!(error.to_string(), engine.analyze_impact(&[]).unwrap_err().to_string());
+
+        let root = write_workspace(&[
+            ("a.scss", "$x: 1;\n"),
+            ("b.scss", "@import 'a';\n"),
+        ]);
+        engine.build_dependency_graph(&root.to_string_lossy()).unwrap();
+        let graph = engine.dependency_graph().unwrap();
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.edges["b.scss"], vec!["a.scss"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_ts_comments_and_strings_ignored() {
+        let parser = TypeScriptParser::new();
+        let content = "// function foo() {}\n\
+/* class Hidden {\n\
+} */\n\
+const label = \"export class Bar {\";\n\
+const doc = `\n\
+function inTemplate() {\n\
+}\n\
+`;\n\
+import { x } from './x'; // trailing\n\
+function real() {\n\
+  return '}';\n\
+}\n";
+        let ast = parser.parse(content).unwrap();
+
+        let names: Vec<(&str, &str, u32, u32)> = ast.children.iter()
+            .map(|node| (node.node_type.as_str(), node.name.as_deref().unwrap_or(""), node.start_line, node.end_line))
+            .collect();
+        assert_eq!(names, vec![
+            ("ImportDeclaration", "./x", 9, 9),
+            ("FunctionDeclaration", "real", 10, 12),
+        ]);
+    }
+
+    #[test]
+    fn test_arrow_function_shapes() {
+        let parser = TypeScriptParser::new();
+        let names = |content: &str| -> Vec<String> {
+            parser.parse(content).unwrap().children.iter()
+                .filter(|node| node.node_type == "FunctionDeclaration")
+                .filter_map(|node| node.name.clone())
+                .collect()
+        };
+
+        assert_eq!(names("const f: () => void = () => {\n};\n"), vec!["f"]);
+        assert_eq!(names("export const g = () => {\n};\n"), vec!["g"]);
+        assert_eq!(names("let h: Handler<Event> = async (e) => e;\n"), vec!["h"]);
+        assert_eq!(names("var k = x => x * 2;\n"), vec!["k"]);
+        assert_eq!(names("const m: Map<string, () => void> = new Map();\n"), Vec::<String>::new());
+        assert_eq!(names("const { a, b } = () => ({ a: 1, b: 2 });\n"), Vec::<String>::new());
+        assert_eq!(names("const [first] = list.map(x => x);\n"), Vec::<String>::new());
+        assert_eq!(names("const doubled = items.map(x => x * 2);\n"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_deleted_and_emptied_files() {
+        let root = write_workspace(&[
+            ("oldModule.ts", "export const old = 1;\n"),
+            ("emptied.ts", "export const gone = 1;\n"),
+            ("kept.ts", "export const kept = 1;\n"),
+        ]);
+        let mut tracker = crate::DiffTracker::new(".shadow");
+        tracker.start_tracking(&root).unwrap();
+
+        std::fs::remove_file(root.join("oldModule.ts")).unwrap();
+        std::fs::write(root.join("emptied.ts"), "").unwrap();
+        std::fs::write(root.join("created.ts"), "export const fresh = 1;\n").unwrap();
+        tracker.capture_changes().unwrap();
+
+        let csv = tracker.to_csv();
+        let kinds: Vec<&str> = csv.lines().skip(1)
+            .map(|line| line.rsplit(',').next().unwrap())
+            .collect();
+        assert_eq!(tracker.changed_paths(), vec!["created.ts", "emptied.ts", "oldModule.ts"]);
+        assert_eq!(kinds, vec!["added", "modified", "deleted"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_ignore_whitespace_changes() {
+        let root = write_workspace(&[
+            ("endings.ts", "let a = 1;\nlet b = 2;\n"),
+            ("indent.ts", "if (x) {\n  run();\n}\n"),
+            ("real.ts", "let c = 3;\n"),
+        ]);
+        let mut tracker = crate::DiffTracker::new(".shadow");
+        tracker.set_ignore_whitespace(true);
+        tracker.start_tracking(&root).unwrap();
+
+        std::fs::write(root.join("endings.ts"), "let a = 1;   \r\nlet b = 2;\r\n").unwrap();
+        std::fs::write(root.join("indent.ts"), "if (x) {\n    run();\n}\n").unwrap();
+        std::fs::write(root.join("real.ts"), "let c = 4;\n").unwrap();
+
+        tracker.capture_changes().unwrap();
+        assert_eq!(tracker.changed_paths(), vec!["indent.ts", "real.ts"]);
+
+        tracker.set_ignore_indentation(true);
+        tracker.capture_changes().unwrap();
+        assert_eq!(tracker.changed_paths(), vec!["real.ts"]);
+
+        tracker.set_ignore_whitespace(false);
+        tracker.capture_changes().unwrap();
+        assert_eq!(tracker.changed_paths(), vec!["endings.ts", "indent.ts", "real.ts"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_engine_changed_files() {
+        let engine = crate::Engine::new();
+        assert!(engine.get_changed_files().unwrap().is_empty());
+
+        let mut tracker = crate::DiffTracker::new(".shadow");
+        let root = write_workspace(&[("src/b.ts", "b\n"), ("a.ts", "a\n")]);
+        tracker.start_tracking(&root).unwrap();
+        std::fs::write(root.join("src/b.ts"), "bb\n").unwrap();
+        std::fs::write(root.join("a.ts"), "aa\n").unwrap();
+        tracker.capture_changes().unwrap();
+        assert_eq!(tracker.changed_paths(), vec!["a.ts", "src/b.ts"]);
+
+        // Through the engine, changes show up once captured and are gone after the stop
+        let mut engine = crate::Engine::new();
+        engine.initialize_in_memory(&root.join(".shadow").to_string_lossy());
+        let mut session = crate::Session::new();
+        session.workspace_path = root.to_string_lossy().to_string();
+        engine.start_new_session(session, false).unwrap();
+        std::fs::write(root.join("src/b.ts"), "bbb\n").unwrap();
+        std::fs::write(root.join("c.ts"), "c\n").unwrap();
+        assert!(engine.get_changed_files().unwrap().is_empty());
+        engine.capture_changes().unwrap();
+        assert_eq!(engine.get_changed_files().unwrap(), vec!["c.ts", "src/b.ts"]);
+        engine.stop_session().unwrap();
+        assert!(engine.get_changed_files().unwrap().is_empty());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let root = write_workspace(&[
+            ("src/a.ts", "a1\n"),
+            ("b.ts", "b1\n"),
+        ]);
+        let shadow_dir = root.join(".shadow");
+        let mut tracker = crate::DiffTracker::new(&shadow_dir.to_string_lossy());
+        tracker.start_tracking(&root).unwrap();
+
+        assert!(tracker.list_snapshots().unwrap().is_empty());
+        tracker.create_snapshot("before").unwrap();
+        assert!(tracker.create_snapshot("before").is_err());
+        assert!(tracker.create_snapshot("../escape").is_err());
+        assert_eq!(tracker.list_snapshots().unwrap(), vec!["before"]);
+
+        std::fs::write(root.join("src/a.ts"), "a2\n").unwrap();
+        std::fs::remove_file(root.join("b.ts")).unwrap();
+        std::fs::write(root.join("c.ts"), "c1\n").unwrap();
+
+        assert_eq!(tracker.restore_snapshot("before").unwrap(), vec!["b.ts", "src/a.ts"]);
+        assert_eq!(std::fs::read_to_string(root.join("src/a.ts")).unwrap(), "a1\n");
+        assert_eq!(std::fs::read_to_string(root.join("b.ts")).unwrap(), "b1\n");
+        assert_eq!(std::fs::read_to_string(root.join("c.ts")).unwrap(), "c1\n");
+        assert!(tracker.restore_snapshot("before").unwrap().is_empty());
+        assert!(tracker.restore_snapshot("missing").is_err());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_moved_nodes() {
+        let engine = AstDiffEngine::new();
+        let old = "function first() {\n  return 1;\n}\nfunction second() {\n  return 2;\n}\nfunction third() {\n  return 3;\n}\n";
+
+        // Moving `first` to the end reports a move, not a modification
+        let moved = "function second() {\n  return 2;\n}\nfunction third() {\n  return 3;\n}\nfunction first() {\n  return 1;\n}\n";
+        let diff = engine.compute_diff("a.ts", old, moved).unwrap();
+        assert_eq!(diff.changes.len(), 1);
+        assert_eq!(diff.changes[0].name.as_deref(), Some("first"));
+        assert!(matches!(diff.changes[0].change_type, ChangeType::Moved { old_line_range: (1, 3) }));
+        assert_eq!(diff.changes[0].line_range, (7, 9));
+
+        // Lines added above shift everything, which is not a move
+        let shifted = format!("// header\n// more\n// more\n{}", old);
+        assert!(engine.compute_diff("a.ts", old, &shifted).unwrap().changes.is_empty());
+
+        // Editing a body is still a modification
+        let edited = old.replace("return 1;", "return 10;");
+        let diff = engine.compute_diff("a.ts", old, &edited).unwrap();
+        assert_eq!(diff.changes.len(), 1);
+        assert!(matches!(diff.changes[0].change_type, ChangeType::Modified));
+    }
+
+    #[test]
+    fn test_diff_stats_from_ast_diffs() {
+        let engine = AstDiffEngine::new();
+        let old = "function a() {\n  return 1;\n}\nfunction b() {\n  return 2;\n}\n";
+        let new = "function a() {\n  return 10;\n}\nfunction c() {\n  return 3;\n}\nclass D {\n}\n";
+
+        let diffs = vec![
+            engine.compute_diff("a.ts", old, new).unwrap(),
+            engine.compute_diff("b.ts", old, old).unwrap(),
+        ];
+        let stats = DiffStats::from_ast_diffs(&diffs);
+
+        assert_eq!(stats.files_changed, 1);
+        let functions = &stats.by_node_type["FunctionDeclaration"];
+        assert_eq!(functions.modified, 1);
+        assert_eq!(functions.added + functions.removed + functions.renamed, 2);
+        assert_eq!(stats.by_node_type["ClassDeclaration"].added, 1);
+    }
+
+    #[test]
+    fn test_session_summary_from_captured_changes() {
+        let root = write_workspace(&[
+            ("src/a.ts", "export function keep() {\n  return 1;\n}\n"),
+            ("notes.md", "first\n"),
+        ]);
+
+        let mut engine = crate::Engine::new();
+        engine.initialize_in_memory(&root.join(".shadow").to_string_lossy());
+        engine.set_tracked_extensions(vec!["ts".to_string(), "md".to_string()]).unwrap();
+        let mut session = crate::Session::new();
+        session.workspace_path = root.to_string_lossy().to_string();
+        engine.start_new_session(session, false).unwrap();
+
+        std::fs::write(root.join("src/a.ts"), "export function keep() {\n  return 1;\n}\n\nexport function added() {\n  return 2;\n}\n").unwrap();
+        std::fs::write(root.join("notes.md"), "first\nsecond\nthird\n").unwrap();
+        engine.capture_changes().unwrap();
+
+        let summary = engine.session_summary().unwrap();
+        assert_eq!(summary.files_changed, 2);
+        assert_eq!((summary.lines_added, summary.lines_removed), (6, 0));
+        assert_eq!(summary.ast_stats.files_changed, 1);
+        assert_eq!(summary.ast_stats.by_node_type["FunctionDeclaration"].added, 1);
+
+        engine.stop_session().unwrap();
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_session_patch() {
+        let root = write_workspace(&[
+            ("lib/keep.ts", "export const keep = 1;\nexport const edit = 1;\n"),
+            ("lib/drop.ts", "export const drop = 1;\n"),
+        ]);
+
+        let mut engine = crate::Engine::new();
+        assert_eq!(engine.session_patch(), "");
+        engine.initialize_in_memory(&root.join(".shadow").to_string_lossy());
+        let mut session = crate::Session::new();
+        session.workspace_path = root.to_string_lossy().to_string();
+        engine.start_new_session(session, false).unwrap();
+
+        std::fs::write(root.join("lib/keep.ts"), "export const keep = 1;\nexport const edit = 2;\n").unwrap();
+        std::fs::remove_file(root.join("lib/drop.ts")).unwrap();
+        std::fs::write(root.join("lib/add.ts"), "export const add = 1;\n").unwrap();
+        engine.capture_changes().unwrap();
+
+        assert_eq!(engine.session_patch(), concat!(
+            "diff --git a/lib/add.ts b/lib/add.ts\n",
+            "new file mode 100644\n",
+            "--- /dev/null\n",
+            "+++ b/lib/add.ts\n",
+            "@@ -0,0 +1,1 @@\n",
+            "+export const add = 1;\n",
+            "diff --git a/lib/drop.ts b/lib/drop.ts\n",
+            "deleted file mode 100644\n",
+            "--- a/lib/drop.ts\n",
+            "+++ /dev/null\n",
+            "@@ -1,1 +0,0 @@\n",
+            "-export const drop = 1;\n",
+            "diff --git a/lib/keep.ts b/lib/keep.ts\n",
+            "--- a/lib/keep.ts\n",
+            "+++ b/lib/keep.ts\n",
+            "@@ -1,2 +1,2 @@\n",
+            " export const keep = 1;\n",
+            "-export const edit = 1;\n",
+            "+export const edit = 2;\n",
+        ));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_component_script_blocks() {
+        let component = "<template>\n  <div>import x from './not-an-import'</div>\n</template>\n<script setup lang=\"ts\">\nimport Child from './Child.vue'\nfunction greet() {\n  return 1;\n}\n</script>\n";
+        let script = crate::ast_diff::languages::ts::extract_script_blocks(component);
+        assert_eq!(script.split('\n').count(), 9);
+        assert_eq!(script.lines().nth(4), Some("import Child from './Child.vue'"));
+        assert!(!script.contains("not-an-import"));
+
+        // Nodes keep their line numbers within the whole file
+        let ast = AstDiffEngine::new().parse("App.vue", component).unwrap();
+        let greet = ast.children.iter().find(|node| node.name.as_deref() == Some("greet")).unwrap();
+        assert_eq!((greet.start_line, greet.end_line), (6, 8));
+        assert_eq!(ast.end_line, 9);
+
+        let root = write_workspace(&[
+            ("App.vue", component),
+            ("Child.vue", "<script>\nexport default {}\n</script>\n<style>p { color: red }</style>\n"),
+            ("widget.svelte", "<script>\nimport App from './App.vue'\n</script>\n<p>{name}</p>\n"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+        let graph = builder.get_graph();
+
+        assert_eq!(graph.edges["App.vue"], vec!["Child.vue".to_string()]);
+        assert_eq!(graph.edges["widget.svelte"], vec!["App.vue".to_string()]);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_barrel_relays_impact() {
+        let root = write_workspace(&[
+            ("src/lib/bar.ts", "export const bar = 1;\n"),
+            ("src/lib/types.ts", "export type Id = string;\n"),
+            ("src/lib/index.ts", "export * from './bar';\nexport * as shapes from './shapes';\nexport type { Id } from './types';\n"),
+            ("src/lib/shapes.ts", "export const circle = 1;\n"),
+            ("src/app.ts", "import { bar } from './lib';\n"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        let mut impacted = builder.analyze_impact(&["src/lib/bar.ts".to_string()]).impacted_files;
+        impacted.sort();
+        assert_eq!(impacted, vec!["src/app.ts", "src/lib/index.ts"]);
+        assert_eq!(builder.dependents_of("src/lib/shapes.ts"), vec!["src/lib/index.ts"]);
+        assert_eq!(builder.get_graph().nodes["src/lib/index.ts"].exports, vec!["shapes", "Id"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_external_dependencies() {
+        let root = write_workspace(&[
+            ("src/a.ts", "import React from 'react';\nimport { readFile } from 'node:fs';\nconst r = require('react');\n"),
+            ("src/b.ts", "import merge from 'lodash/merge';\nimport { useState } from 'react';\nimport { a } from './a';\n"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        let external = builder.external_dependencies();
+        assert_eq!(external.len(), 3);
+        assert_eq!(external["react"], 2);
+        assert_eq!(external["node:fs"], 1);
+        assert_eq!(external["lodash/merge"], 1);
+        assert_eq!(builder.get_graph().nodes["src/b.ts"].external_imports, vec!["lodash/merge", "react"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_anonymous_nodes_are_diffed() {
+        let engine = AstDiffEngine::new();
+        let old = "export default function () {\n  return 1;\n}\n";
+
+        let edited = "export default function () {\n  return 2;\n}\n";
+        let diff = engine.compute_diff("a.ts", old, edited).unwrap();
+        assert_eq!(diff.changes.len(), 1);
+        assert!(matches!(diff.changes[0].change_type, ChangeType::Modified));
+        assert_eq!(diff.changes[0].name, None);
+        assert_eq!(diff.changes[0].node_type, "FunctionDeclaration");
+
+        // A second anonymous node of another type doesn't collide with the first
+        let added = format!("{}export default class {{\n}}\n", old);
+        let diff = engine.compute_diff("a.ts", old, &added).unwrap();
+        assert_eq!(diff.changes.len(), 1);
+        assert!(matches!(diff.changes[0].change_type, ChangeType::Added));
+        assert_eq!(diff.changes[0].node_type, "ClassDeclaration");
+
+        let diff = engine.compute_diff("a.ts", old, "function named() {\n  return 1;\n}\n").unwrap();
+        assert!(diff.changes.iter().any(|change| matches!(change.change_type, ChangeType::Removed) && change.name.is_none()));
+    }
+
+    #[test]
+    fn test_import_changes() {
+        let engine = AstDiffEngine::new();
+        let old = "import { a } from './a';\n\nfunction f() {\n  return a;\n}\n";
+        let new = "import { a } from './a';\nimport {\n  b,\n  c,\n} from './bc';\nimport './polyfills';\n\nfunction f() {\n  return a;\n}\n";
+
+        let diff = engine.compute_diff("a.ts", old, new).unwrap();
+        let mut added: Vec<_> = diff.changes.iter()
+            .filter(|change| change.node_type == "ImportDeclaration" && matches!(change.change_type, ChangeType::Added))
+            .collect();
+        added.sort_by_key(|change| change.line_range);
+        assert_eq!(added.len(), 2);
+
+        assert_eq!(added[0].name.as_deref(), Some("./bc"));
+        assert_eq!(added[0].line_range, (2, 5));
+        assert_eq!(added[0].new_content.as_deref(), Some("import {\n  b,\n  c,\n} from './bc';"));
+        assert_eq!(added[1].name.as_deref(), Some("./polyfills"));
+        assert_eq!(added[1].new_content.as_deref(), Some("import './polyfills';"));
+
+        let diff = engine.compute_diff("a.ts", new, old).unwrap();
+        let removed = diff.changes.iter()
+            .find(|change| change.name.as_deref() == Some("./bc"))
+            .unwrap();
+        assert!(matches!(removed.change_type, ChangeType::Removed));
+        assert!(removed.old_content.as_deref().unwrap().contains("} from './bc';"));
+    }
+
+    #[test]
+    fn test_diff_directories() {
+        let root = write_workspace(&[
+            ("old/src/same.ts", "let a = 1;\n"),
+            ("old/src/edited.ts", "let b = 1;\n"),
+            ("old/removed.ts", "let c = 1;\n"),
+            ("old/notes.md", "ignored\n"),
+            ("new/src/same.ts", "let a = 1;\n"),
+            ("new/src/edited.ts", "let b = 2;\n"),
+            ("new/added.ts", "let d = 1;\n"),
+            ("new/.shadow/state.json", "{}"),
+        ]);
+
+        let tracker = crate::DiffTracker::new_lazy(".shadow");
+        let diffs = tracker.diff_directories(&root.join("old"), &root.join("new")).unwrap();
+        let summary: Vec<(&str, crate::FileChangeKind)> = diffs.iter()
+            .map(|diff| (diff.path.as_str(), diff.kind))
+            .collect();
+        assert_eq!(summary, vec![
+            ("added.ts", crate::FileChangeKind::Added),
+            ("removed.ts", crate::FileChangeKind::Deleted),
+            ("src/edited.ts", crate::FileChangeKind::Modified),
+        ]);
+
+        assert!(tracker.diff_directories(&root.join("old"), &root.join("missing")).is_err());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_changes_are_sorted() {
+        let engine = AstDiffEngine::new();
+        let old = "function a() {}\nfunction b() {}\nfunction c() {}\nfunction d() {}\n";
+        let new = "function e() { return 5; }\nfunction b() { return 2; }\nfunction f() { return 6; }\nfunction d() { return 4; }\n";
+
+        let first = engine.compute_diff("a.ts", old, new).unwrap();
+        let lines: Vec<u32> = first.changes.iter().map(|change| change.line_range.0).collect();
+        let mut sorted = lines.clone();
+        sorted.sort();
+        assert_eq!(lines, sorted);
+
+        let serialized = serde_json::to_string(&first).unwrap();
+        for _ in 0..10 {
+            let again = engine.compute_diff("a.ts", old, new).unwrap();
+            assert_eq!(serde_json::to_string(&again).unwrap(), serialized);
+        }
+    }
+
+    #[test]
+    fn test_strict_parsing_rejects_unbalanced_input() {
+        let truncated = "function a() {\n  if (x) {\n    return [1, 2];\n";
+        let balanced = "function a() {\n  // not a real brace: }\n  return \"(\";\n}\n";
+
+        let lenient = AstDiffEngine::new();
+        assert!(lenient.compute_diff("a.ts", balanced, truncated).is_ok());
+
+        let mut strict = AstDiffEngine::new();
+        strict.register_parser(Box::new(TypeScriptParser::new().with_strict(true)));
+        assert!(strict.compute_diff("a.ts", balanced, balanced).unwrap().changes.is_empty());
+
+        let err = strict.compute_diff("a.ts", balanced, truncated).unwrap_err();
+        assert_eq!(err.to_string(), "Unclosed '{' from line 2");
+        let err = strict.parse("a.ts", "let a = (1];\n").unwrap_err();
+        assert_eq!(err.to_string(), "Mismatched ']' on line 1 closes '(' from line 1");
+        assert!(strict.parse("a.ts", "}\n").is_err());
+    }
+
+    #[test]
+    fn test_rust_parser() {
+        let source = r#"use std::fmt;
+
+pub(crate) struct Point {
+    x: i32,
+}
+
+pub enum Shape { Circle, Square }
+
+struct Unit;
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // fn fake() {
+        write!(f, "{{ fn also_fake() }}")
+    }
+}
+
+impl<T: Clone> Wrapper<T> where T: Send {
+    pub const fn get(&self) -> char {
+        '{'
+    }
+}
+
+pub trait Area {
+    fn area(&self) -> f64;
+}
+
+mod tests;
+
+pub async unsafe fn run<'a>(input: &'a str) {}
+"#;
+        let ast = AstDiffEngine::new().parse("lib.rs", source).unwrap();
+        let items: Vec<(&str, &str, u32, u32)> = ast.children.iter()
+            .map(|node| (node.node_type.as_str(), node.name.as_deref().unwrap(), node.start_line, node.end_line))
+            .collect();
+        assert_eq!(items, vec![
+            ("StructDeclaration", "Point", 3, 5),
+            ("EnumDeclaration", "Shape", 7, 7),
+            ("StructDeclaration", "Unit", 9, 9),
+            ("ImplBlock", "fmt::Display for Point", 11, 16),
+            ("ImplBlock", "Wrapper<T>", 18, 22),
+            ("TraitDeclaration", "Area", 24, 26),
+            ("ModuleDeclaration", "tests", 28, 28),
+            ("FunctionDeclaration", "run", 30, 30),
+        ]);
+
+        let display = &ast.children[3];
+        assert_eq!(display.children.len(), 1);
+        assert_eq!(display.children[0].node_type, "MethodDefinition");
+        assert_eq!(display.children[0].name.as_deref(), Some("fmt"));
+        assert_eq!(ast.children[4].children[0].name.as_deref(), Some("get"));
+        assert_eq!(ast.children[5].children[0].node_type, "MethodDefinition");
+
+        let edited = source.replace("'{'", "'}'");
+        let diff = AstDiffEngine::new().compute_diff("lib.rs", source, &edited).unwrap();
+        assert_eq!(diff.changes.len(), 1);
+        assert_eq!(diff.changes[0].name.as_deref(), Some("get"));
+    }
+
+    #[test]
+    fn test_relative_imports_resolve_from_importer_directory() {
+        let root = write_workspace(&[
+            ("src/utils/date.ts", "export const a = 1;\n"),
+            ("src/a/utils/date.ts", "export const b = 1;\n"),
+            ("src/a/b.ts", "import { a } from '../utils/date';\nimport { b } from './utils/date';\n"),
+            ("src/a/c.ts", "import { x } from '../../../outside';\nimport { y } from './missing';\n"),
+        ]);
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        let graph = builder.get_graph();
+        assert_eq!(graph.nodes["src/a/b.ts"].imports, vec!["src/utils/date", "src/a/utils/date"]);
+        assert_eq!(graph.edges["src/a/b.ts"], vec!["src/utils/date.ts", "src/a/utils/date.ts"]);
+        assert!(graph.edges["src/a/c.ts"].is_empty());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_session_duration() {
+        let store = std::sync::Arc::new(crate::Store::in_memory());
+        let mut engine = crate::Engine::with_store(store.clone());
+        assert!(engine.session_duration().is_none());
+        assert_eq!(engine.session_elapsed_seconds().unwrap(), None);
+
+        engine.start_session().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let duration = engine.session_duration().unwrap();
+        assert!(duration >= std::time::Duration::from_millis(5));
+        assert!(engine.session_elapsed_seconds().unwrap().is_some_and(|seconds| seconds >= duration.as_secs()));
+
+        // Another engine reads the persisted session
+        let observer = crate::Engine::with_store(store);
+        assert!(observer.session_duration().is_some());
+
+        engine.stop_session().unwrap();
+        assert!(engine.session_duration().is_none());
+        assert!(observer.session_duration().is_none());
+    }
+
+    #[test]
+    fn test_wasm_session_duration() {
+        use std::future::Future;
+        use std::task::{Context, Poll, Waker};
+
+        // The engine calls never await, so one poll runs them to completion
+        fn run<F: Future>(future: F) -> F::Output {
+            match std::pin::pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+                Poll::Ready(output) => output,
+                Poll::Pending => panic!("engine call did not complete"),
+            }
+        }
+
+        let store = std::sync::Arc::new(crate::Store::in_memory());
+        let shadow = crate::ShadowEngine { engine: crate::Engine::with_store(store.clone()) };
+        assert_eq!(run(shadow.get_session_duration_secs()).unwrap(), None);
+
+        let mut engine = crate::Engine::with_store(store);
+        engine.start_session().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let seconds = run(shadow.get_session_duration_secs()).unwrap().unwrap();
+        assert!(seconds >= 0.005);
+    }
+
+    #[test]
+    fn test_session_listeners() {
+        use std::sync::{Arc, Mutex};
+
+        struct Recorder(Arc<Mutex<Vec<String>>>);
+        impl crate::SessionListener for Recorder {
+            fn on_start(&self, session: &crate::Session) {
+                self.0.lock().unwrap().push(format!("start {}", session.id));
+            }
+            fn on_stop(&self, session: &crate::Session, diffs: &[crate::FileDiff]) {
+                assert!(session.end_time.is_some());
+                self.0.lock().unwrap().push(format!("stop {} {}", session.id, diffs.len()));
+            }
+        }
+
+        struct Panicker;
+        impl crate::SessionListener for Panicker {
+            fn on_start(&self, _: &crate::Session) {
+                panic!("listener failure");
+            }
+            fn on_stop(&self, _: &crate::Session, _: &[crate::FileDiff]) {
+                panic!("listener failure");
+            }
+        }
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = crate::Engine::new_in_memory();
+        engine.register_listener(Box::new(Panicker));
+        engine.register_listener(Box::new(Recorder(events.clone())));
+
+        let session_id = engine.start_session().unwrap();
+        assert!(engine.get_status().unwrap().is_active);
+        engine.stop_session().unwrap();
+        assert!(!engine.get_status().unwrap().is_active);
+
+        assert_eq!(*events.lock().unwrap(), vec![format!("start {}", session_id), format!("stop {} 0", session_id)]);
+        assert!(engine.start_session().is_ok());
+    }
+
+    #[test]
+    fn test_file_diff_lookup() {
+        let root = write_workspace(&[("src/a.ts", "after\n")]);
+        let absolute = root.join("src/a.ts").to_string_lossy().to_string();
+
+        let mut tracker = crate::DiffTracker::new_lazy(".shadow");
+        tracker.start_tracking(&root).unwrap();
+        tracker.record_change("src/a.ts", Some("before\n")).unwrap();
+        tracker.capture_changes().unwrap();
+        assert_eq!(tracker.file_diff("src/a.ts").unwrap().original_content, "before\n");
+        assert!(tracker.file_diff(&absolute).is_some());
+        assert!(tracker.file_diff("src/b.ts").is_none());
+
+        // Once stopped, a session's diffs are read back from the store
+        let store = std::sync::Arc::new(crate::Store::in_memory());
+        let session = crate::Session {
+            id: "s1".to_string(),
+            workspace_path: root.to_string_lossy().to_string(),
+            end_time: Some(chrono::Utc::now()),
+            ..crate::Session::new()
+        };
+        store.put("session/s1", serde_json::to_vec(&session).unwrap()).unwrap();
+        store.put("diff/s1/src/a.ts", serde_json::to_vec(tracker.file_diff("src/a.ts").unwrap()).unwrap()).unwrap();
+
+        let engine = crate::Engine::with_store(store);
+        assert_eq!(engine.get_all_diffs().unwrap().len(), 1);
+        assert_eq!(engine.get_file_diff("src/a.ts").unwrap().unwrap().modified_content, "after\n");
+        assert!(engine.get_file_diff(&absolute).unwrap().is_some());
+        assert!(engine.get_file_diff("src/b.ts").unwrap().is_none());
+        assert!(crate::Engine::new().get_all_diffs().unwrap().is_empty());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_engine_errors_are_typed() {
+        let mut engine = crate::Engine::new_in_memory();
+
+        let error = engine.stop_session().unwrap_err();
+        assert!(matches!(error, crate::ShadowError::NoActiveSession));
+        assert_eq!(error.code(), "NO_ACTIVE_SESSION");
+
+        let error = engine.find_cycles().unwrap_err();
+        assert!(matches!(error, crate::ShadowError::GraphNotBuilt));
+        assert_eq!(error.code(), "GRAPH_NOT_BUILT");
+
+        let session_id = engine.start_session().unwrap();
+        match engine.start_session().unwrap_err() {
+            crate::ShadowError::SessionAlreadyActive { session_id: active, owner_pid } => {
+                assert_eq!(active, session_id);
+                assert_eq!(owner_pid, None);
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+
+        let error = AstDiffEngine::new().compute_diff("notes.txt", "a", "b").unwrap_err();
+        assert!(matches!(error, crate::ShadowError::NoParser(ref extension) if extension == "txt"));
+    }
+
+    #[test]
+    fn test_multiple_exports_per_line() {
+        let root = write_workspace(&[
+            ("lib.ts", "export { a, b as renamed, type T } from './impl';\nexport const x = 1, y = [1, 2], z = call(3, 4);\nexport const { first, second: alias, ...rest } = config;\nexport let [head, , tail = 'a,b'] = list;\nexport const { withDefault = 1, after, nested: { deep = 2, last } = {} } = options;\nexport {\n  multi,\n  line as spread,\n};\n"),
+            ("impl.ts", "export const a = 1;\n"),
+        ]);
+
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+        let exports = &builder.get_graph().nodes["lib.ts"].exports;
+
+        for name in [
+            "a", "renamed", "T", "x", "y", "z", "first", "alias", "rest", "head", "tail",
+            "withDefault", "after", "deep", "last", "multi", "spread",
+        ] {
+            assert!(exports.contains(&name.to_string()), "missing export {}", name);
+        }
+        assert!(!exports.contains(&"b".to_string()));
+        assert!(!exports.contains(&"second".to_string()));
+        assert!(!exports.contains(&"nested".to_string()));
+        assert_eq!(exports.len(), 17);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_json_config_diff() {
+        let old = "{\n  \"name\": \"app\",\n  // comments are allowed\n  \"dependencies\": {\n    \"react\": \"^17.0.0\",\n    \"lodash\": \"^4.0.0\"\n  },\n  \"files\": [\"dist\", \"lib\"],\n}\n";
+        let new = "{\n  \"name\": \"app\",\n  \"dependencies\": {\n    \"react\": \"^18.0.0\",\n    \"lodash\": \"^4.0.0\",\n    \"zod\": \"^3.0.0\"\n  },\n  \"files\": [\"dist\", \"types\"]\n}\n";
+
+        let root = languages::json::JsonParser::new().parse(new).unwrap();
+        let dependencies = root.children.iter().find(|c| c.name.as_deref() == Some("dependencies")).unwrap();
+        assert_eq!((dependencies.start_line, dependencies.end_line), (3, 7));
+        assert_eq!(dependencies.children.len(), 3);
+        let files = root.children.iter().find(|c| c.name.as_deref() == Some("files")).unwrap();
+        assert_eq!(files.children[1].node_type, "Element");
+        assert_eq!(files.children[1].name.as_deref(), Some("1"));
+
+        let diff = AstDiffEngine::new().compute_diff("package.json", old, new).unwrap();
+        let changed = |name: &str| diff.changes.iter().find(|c| c.name.as_deref() == Some(name));
+        assert!(matches!(changed("react").unwrap().change_type, ChangeType::Modified));
+        assert!(matches!(changed("zod").unwrap().change_type, ChangeType::Added));
+        assert!(matches!(changed("1").unwrap().change_type, ChangeType::Modified));
+        // A trailing comma added after it doesn't count as a change to `lodash`
+        assert!(changed("lodash").is_none());
+        assert!(changed("name").is_none());
+
+        assert!(languages::json::JsonParser::new().parse("{\"a\": }").is_err());
+    }
+
+    #[test]
+    fn test_toml_config_diff() {
+        let old = "[package]\nname = \"app\" # the crate\nversion = \"0.1.0\"\n\n[dependencies]\nserde = { version = \"1.0\", features = [\"derive\"] }\nrayon = \"1.7\"\n\n[[bin]]\nname = \"cli\"\n";
+        let new = "[package]\nname = \"app\"\nversion = \"0.1.0\"\nauthors = [\n  \"someone\",\n]\n\n[dependencies]\nserde = { version = \"1.0\", features = [\"derive\"] }\nrayon = \"1.8\"\n\n[[bin]]\nname = \"cli\"\n\n[[bin]]\nname = \"server\"\n";
+
+        let root = languages::toml::TomlParser::new().parse(new).unwrap();
+        let package = &root.children[0];
+        assert_eq!((package.start_line, package.end_line), (1, 6));
+        let authors = package.children.iter().find(|c| c.name.as_deref() == Some("authors")).unwrap();
+        assert_eq!((authors.start_line, authors.end_line), (4, 6));
+        let bins = root.children.iter().find(|c| c.name.as_deref() == Some("bin")).unwrap();
+        assert_eq!(bins.children.len(), 2);
+        assert_eq!(bins.children[1].children[0].name.as_deref(), Some("name"));
+
+        let diff = AstDiffEngine::new().compute_diff("Cargo.toml", old, new).unwrap();
+        let changed = |name: &str| diff.changes.iter().find(|c| c.name.as_deref() == Some(name));
+        assert!(matches!(changed("rayon").unwrap().change_type, ChangeType::Modified));
+        assert!(matches!(changed("authors").unwrap().change_type, ChangeType::Added));
+        assert!(matches!(changed("1").unwrap().change_type, ChangeType::Added));
+        assert!(changed("serde").is_none());
+    }
+
+    #[test]
+    fn test_async_and_generator_functions() {
+        let source = "async function load() {\n}\nexport async function save(data) {\n}\nfunction* ids() {\n}\nexport async function *pages<T>(url) {\n}\nconst fetchAll = async (urls) => Promise.all(urls.map(u => fetch(u)));\nclass Queue {\n  async *drain() {\n  }\n  size() {\n  }\n}\n";
+        let root = TypeScriptParser::new().parse(source).unwrap();
+
+        let modifiers = |name: &str| {
+            let node = root.children.iter()
+                .chain(root.children.iter().flat_map(|child| child.children.iter()))
+                .find(|node| node.name.as_deref() == Some(name))
+                .unwrap_or_else(|| panic!("missing {}", name));
+            node.modifiers.clone()
+        };
+        assert_eq!(modifiers("load"), vec!["async"]);
+        assert_eq!(modifiers("save"), vec!["async"]);
+        assert_eq!(modifiers("ids"), vec!["generator"]);
+        assert_eq!(modifiers("pages"), vec!["async", "generator"]);
+        assert_eq!(modifiers("fetchAll"), vec!["async"]);
+        assert_eq!(modifiers("drain"), vec!["async", "generator"]);
+        assert!(modifiers("size").is_empty());
+
+        // Dropping `async` is a change to the function even when the line hashes the same
+        let old = TypeScriptParser::new().parse("async function load() {\n}\n").unwrap();
+        let mut new = old.clone();
+        new.children[0].modifiers.clear();
+        let diff = AstDiffEngine::new()
+            .diff_parsed("a.ts", &old, &new, "async function load() {\n}\n", "async function load() {\n}\n")
+            .unwrap();
+        assert_eq!(diff.changes.len(), 1);
+        assert!(matches!(diff.changes[0].change_type, ChangeType::Modified));
+    }
+
+    #[test]
+    fn test_engine_with_config() {
+        let root = write_workspace(&[
+            ("old/app.ts", "let a = 1;\n"),
+            ("old/lib.py", "a = 1\n"),
+            ("new/app.ts", "let a = 2;\n"),
+            ("new/lib.py", "a = 2\n"),
+            ("new/generated/api.ts", "let b = 1;\n"),
+            ("new/big.ts", "let c = 'a long line';\n"),
+        ]);
+
+        let mut engine = crate::Engine::with_config(crate::EngineConfig {
+            shadow_dir: Some(root.join(".shadow").to_string_lossy().to_string()),
+            tracked_extensions: Some(vec!["ts".to_string()]),
+            ignore_globs: vec!["generated/**".to_string()],
+            max_file_bytes: Some(16),
+            risk_thresholds: Some(crate::RiskThresholds { low_max: 1, medium_max: 3 }),
+            rename_threshold: None,
+            follow_symlinks: false,
+            max_scan_depth: None,
+            backend: crate::StorageBackend::InMemory,
+        }).unwrap();
+
+        assert_eq!(engine.tracked_extensions(), vec!["ts"]);
+        let diffs = engine.diff_directories(&root.join("old").to_string_lossy(), &root.join("new").to_string_lossy()).unwrap();
+        let paths: Vec<&str> = diffs.iter().map(|diff| diff.path.as_str()).collect();
+        assert_eq!(paths, vec!["app.ts"]);
+        assert!(!root.join(".shadow").exists());
+
+        // The dependency graph skips the same globs
+        engine.build_dependency_graph(&root.join("new").to_string_lossy()).unwrap();
+        let mut files: Vec<&String> = engine.dependency_graph().unwrap().nodes.keys().collect();
+        files.sort();
+        assert_eq!(files, ["app.ts", "big.ts"]);
+
+        // In-memory without a shadow dir still has somewhere to keep sessions, and tracks changes
+        let mut engine = crate::Engine::with_config(crate::EngineConfig {
+            backend: crate::StorageBackend::InMemory,
+            ..Default::default()
+        }).unwrap();
+        assert!(engine.list_sessions().is_ok());
+        let mut session = crate::Session::new();
+        session.workspace_path = root.join("old").to_string_lossy().to_string();
+        assert!(engine.start_new_session(session, false).is_ok());
+        std::fs::write(root.join("old/app.ts"), "let a = 3;\n").unwrap();
+        engine.capture_changes().unwrap();
+        assert_eq!(engine.get_diff_count().unwrap(), 1);
+
+        let invalid = crate::Engine::with_config(crate::EngineConfig {
+            risk_thresholds: Some(crate::RiskThresholds { low_max: 5, medium_max: 1 }),
+            ..Default::default()
+        });
+        assert!(matches!(invalid, Err(crate::ShadowError::InvalidInput(_))));
+        let invalid = crate::Engine::with_config(crate::EngineConfig {
+            tracked_extensions: Some(vec![" . ".to_string()]),
+            ..Default::default()
+        });
+        assert!(matches!(invalid, Err(crate::ShadowError::InvalidInput(_))));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_capture_detects_renames() {
+        let body: String = (0..10).map(|i| format!("export const v{} = {};\n", i, i)).collect();
+        let root = write_workspace(&[
+            ("src/a.ts", &body),
+            ("src/gone.ts", "export const gone = true;\n"),
+        ]);
+
+        let mut tracker = crate::DiffTracker::new(".shadow");
+        tracker.start_tracking(&root).unwrap();
+
+        std::fs::remove_file(root.join("src/a.ts")).unwrap();
+        std::fs::write(root.join("src/b.ts"), body.replace("v9 = 9", "v9 = 10")).unwrap();
+        std::fs::remove_file(root.join("src/gone.ts")).unwrap();
+        std::fs::write(root.join("src/fresh.ts"), "export const fresh = 1;\n").unwrap();
+        tracker.capture_changes().unwrap();
+
+        let summary: Vec<(String, crate::FileChangeKind, Option<String>)> = tracker.diffs().iter()
+            .map(|diff| (
+                diff.path.strip_prefix(root.to_string_lossy().as_ref()).unwrap().to_string(),
+                diff.kind,
+                diff.renamed_from.as_ref().map(|from| from.strip_prefix(root.to_string_lossy().as_ref()).unwrap().to_string()),
+            ))
+            .collect();
+        assert_eq!(summary, vec![
+            ("/src/b.ts".to_string(), crate::FileChangeKind::Renamed, Some("/src/a.ts".to_string())),
+            ("/src/fresh.ts".to_string(), crate::FileChangeKind::Added, None),
+            ("/src/gone.ts".to_string(), crate::FileChangeKind::Deleted, None),
+        ]);
+
+        assert_eq!(tracker.changed_paths(), ["src/a.ts", "src/b.ts", "src/fresh.ts", "src/gone.ts"]);
+
+        let renamed = &tracker.diffs()[0];
+        assert_eq!(renamed.original_content, body);
+        assert_eq!(renamed.line_stats(), (1, 1));
+        let patch = tracker.export_patch();
+        assert!(patch.contains("diff --git a/src/a.ts b/src/b.ts\nsimilarity index 90%\nrename from src/a.ts\nrename to src/b.ts\n--- a/src/a.ts\n+++ b/src/b.ts\n"));
+
+        // A threshold above 1.0 keeps the delete/add pair
+        tracker.set_rename_threshold(1.1);
+        tracker.capture_changes().unwrap();
+        let kinds: Vec<crate::FileChangeKind> = tracker.diffs().iter().map(|diff| diff.kind).collect();
+        assert_eq!(kinds.iter().filter(|kind| **kind == crate::FileChangeKind::Deleted).count(), 2);
+        assert!(!kinds.contains(&crate::FileChangeKind::Renamed));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_ast_node_similarity() {
+        let parser = TypeScriptParser::new();
+        let original = parser.parse("class Cart {\n  add() {\n  }\n  remove() {\n  }\n  total() {\n  }\n}\n").unwrap();
+        let edited = parser.parse("class Cart {\n  add() {\n  }\n  clear() {\n  }\n  total() {\n  }\n}\n").unwrap();
+        let unrelated = parser.parse("interface Shape {\n}\n").unwrap().children[0].clone();
+
+        let cart = &original.children[0];
+        assert_eq!(cart.similarity(cart), 1.0);
+        assert_eq!(original.similarity(&original.clone()), 1.0);
+        assert_eq!(cart.similarity(&unrelated), 0.0);
+        assert_eq!(unrelated.similarity(cart), 0.0);
+
+        // Two of three methods carry over
+        let score = cart.similarity(&edited.children[0]);
+        assert!((score - (2.0 + 2.0 * (2.0 / 3.0)) / 4.0).abs() < 1e-9);
+        assert_eq!(score, edited.children[0].similarity(cart));
+        assert!(original.similarity(&edited) > 0.0 && original.similarity(&edited) < 1.0);
+    }
+
+    #[test]
+    fn test_export_patch() {
+        let root = write_workspace(&[
+            ("src/edit.ts", "let a = 1;\nlet b = 2;\n"),
+            ("src/old.ts", "export {};\n"),
+        ]);
+
+        let mut tracker = crate::DiffTracker::new(".shadow");
+        tracker.start_tracking(&root).unwrap();
+        std::fs::write(root.join("src/edit.ts"), "let a = 1;\nlet b = 3;").unwrap();
+        std::fs::remove_file(root.join("src/old.ts")).unwrap();
+        std::fs::write(root.join("new.ts"), "let c = 1;\n").unwrap();
+        tracker.capture_changes().unwrap();
+
+        assert_eq!(tracker.export_patch(), concat!(
+            "diff --git a/new.ts b/new.ts\n",
+            "new file mode 100644\n",
+            "--- /dev/null\n",
+            "+++ b/new.ts\n",
+            "@@ -0,0 +1,1 @@\n",
+            "+let c = 1;\n",
+            "diff --git a/src/edit.ts b/src/edit.ts\n",
+            "--- a/src/edit.ts\n",
+            "+++ b/src/edit.ts\n",
+            "@@ -1,2 +1,2 @@\n",
+            " let a = 1;\n",
+            "-let b = 2;\n",
+            "+let b = 3;\n",
+            "\\ No newline at end of file\n",
+            "diff --git a/src/old.ts b/src/old.ts\n",
+            "deleted file mode 100644\n",
+            "--- a/src/old.ts\n",
+            "+++ /dev/null\n",
+            "@@ -1,1 +0,0 @@\n",
+            "-export {};\n",
+        ));
+        assert_eq!(tracker.to_git_patch(), tracker.export_patch());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_analyze_impact_or_build() {
+        let first = write_workspace(&[
+            ("a.ts", "export const a = 1;\n"),
+            ("b.ts", "import { a } from './a';\n"),
+        ]);
+        let second = write_workspace(&[
+            ("a.ts", "export const a = 1;\n"),
+            ("c.ts", "import { a } from './a';\n"),
+        ]);
+        let changed = vec!["a.ts".to_string()];
+
+        let mut engine = crate::Engine::new();
+        assert!(matches!(engine.analyze_impact(&changed), Err(crate::ShadowError::GraphNotBuilt)));
+
+        let impact = engine.analyze_impact_or_build(&first.to_string_lossy(), &changed).unwrap();
+        assert_eq!(impact.impacted_files, vec!["b.ts"]);
+
+        // The cached graph is reused, so a file added since isn't picked up
+        std::fs::write(first.join("d.ts"), "import { a } from './a';\n").unwrap();
+        let impact = engine.analyze_impact_or_build(&first.to_string_lossy(), &changed).unwrap();
+        assert_eq!(impact.impacted_files, vec!["b.ts"]);
+        // So is it when the same root is spelled differently
+        let respelled = format!("{}/./", first.to_string_lossy());
+        let impact = engine.analyze_impact_or_build(&respelled, &changed).unwrap();
+        assert_eq!(impact.impacted_files, vec!["b.ts"]);
+
+        let impact = engine.analyze_impact_or_build(&second.to_string_lossy(), &changed).unwrap();
+        assert_eq!(impact.impacted_files, vec!["c.ts"]);
+
+        std::fs::remove_dir_all(first).unwrap();
+        std::fs::remove_dir_all(second).unwrap();
+    }
+
+    #[test]
+    fn test_method_body_change_in_class() {
+        let engine = AstDiffEngine::new();
+        let cases = [
+            (
+                "cart.ts",
+                "class Cart {\n  add(item) {\n    this.items.push(item);\n  }\n\n  remove(item) {\n    this.items = this.items.filter(i => i !== item);\n  }\n\n  total() {\n    return this.items.length;\n  }\n}\n",
+                "this.items.filter(i => i !== item)",
+                "this.items.filter(i => i.id !== item.id)",
+            ),
+            (
+                "cart.py",
+                "class Cart:\n    def add(self, item):\n        self.items.append(item)\n\n    def remove(self, item):\n        self.items.remove(item)\n\n    def total(self):\n        return len(self.items)\n",
+                "self.items.remove(item)",
+                "self.items = [i for i in self.items if i != item]",
+            ),
+            (
+                "cart.rs",
+                "impl Cart {\n    fn add(&mut self, item: Item) {\n        self.items.push(item);\n    }\n\n    fn remove(&mut self, item: &Item) {\n        self.items.retain(|i| i != item);\n    }\n\n    fn total(&self) -> usize {\n        self.items.len()\n    }\n}\n",
+                "self.items.retain(|i| i != item);",
+                "self.items.retain(|i| i.id != item.id);",
+            ),
+        ];
+
+        for (path, old, before, after) in cases {
+            let new = old.replace(before, after);
+            let diff = engine.compute_diff(path, old, &new).unwrap();
+
+            assert_eq!(diff.changes.len(), 1, "{}: {:?}", path, diff.changes);
+            let change = &diff.changes[0];
+            assert!(matches!(change.change_type, ChangeType::Modified), "{}", path);
+            assert_eq!(change.name.as_deref(), Some("remove"), "{}", path);
+            assert!(change.new_content.as_deref().unwrap().contains(after), "{}", path);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_directories() {
+        let root = write_workspace(&[
+            ("src/a.ts", "export const a = 1;\n"),
+            ("shared/b.ts", "import { a } from '../src/a';\n"),
+        ]);
+        std::os::unix::fs::symlink(root.join("shared"), root.join("src/linked")).unwrap();
+        // A link back to the root would recurse forever if followed blindly
+        std::os::unix::fs::symlink(&root, root.join("src/loop")).unwrap();
+
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+        let mut files: Vec<&String> = builder.get_graph().nodes.keys().collect();
+        files.sort();
+        assert_eq!(files, vec!["shared/b.ts", "src/a.ts"]);
+
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.set_follow_symlinks(true);
+        let mut progress = Vec::new();
+        builder.build_graph_with_progress(&mut |scanned, total| progress.push((scanned, total))).unwrap();
+        // `shared` sorts before `src`, so b.ts is reached under its own path, not the link
+        let mut files: Vec<&String> = builder.get_graph().nodes.keys().collect();
+        files.sort();
+        assert_eq!(files, vec!["shared/b.ts", "src/a.ts"]);
+        assert_eq!(progress.last(), Some(&(2, 2)));
+
+        let old = write_workspace(&[("keep.ts", "let a = 1;\n")]);
+        std::os::unix::fs::symlink(&root, old.join("loop")).unwrap();
+        let mut tracker = crate::DiffTracker::new(".shadow");
+        assert!(tracker.diff_directories(&old, &old).unwrap().is_empty());
+        tracker.set_follow_symlinks(true);
+        assert!(tracker.diff_directories(&old, &old).unwrap().is_empty());
+
+        tracker.start_tracking(&root).unwrap();
+        std::fs::write(root.join("shared/b.ts"), "export {};\n").unwrap();
+        tracker.capture_changes().unwrap();
+        assert_eq!(tracker.diffs().len(), 1);
+        assert_eq!(std::path::Path::new(&tracker.diffs()[0].path), root.join("shared/b.ts"));
+
+        std::fs::remove_dir_all(root).unwrap();
+        std::fs::remove_dir_all(old).unwrap();
+    }
+
+    #[test]
+    fn test_import_path() {
+        let root = write_workspace(&[
+            ("app.ts", "import { c } from './c';\nimport { b } from './b';\nimport { d } from './d';\n"),
+            ("b.ts", "import { s } from './shared';\n"),
+            ("c.ts", "import { s } from './shared';\n"),
+            ("d.ts", "import { e } from './e';\n"),
+            ("e.ts", "import { s } from './shared';\n"),
+            ("shared.ts", "import { app } from './app';\n"),
+            ("lonely.ts", "export {};\n"),
+        ]);
+
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        // b.ts and c.ts both give a two-hop chain; b.ts sorts first
+        assert_eq!(builder.import_path("app.ts", "shared.ts"), Some(vec!["app.ts".to_string(), "b.ts".to_string(), "shared.ts".to_string()]));
+        assert_eq!(builder.import_path("d.ts", "app.ts").unwrap().len(), 4);
+        assert_eq!(builder.import_path("app.ts", "app.ts"), Some(vec!["app.ts".to_string()]));
+        assert_eq!(builder.import_path("app.ts", "lonely.ts"), None);
+        assert_eq!(builder.import_path("missing.ts", "app.ts"), None);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_coupling_metrics() {
+        let root = write_workspace(&[
+            ("util.ts", "export const u = 1;\n"),
+            ("api.ts", "import { u } from './util';\nexport const api = u;\n"),
+            ("a.ts", "import { u } from './util';\nimport { api } from './api';\nimport React from 'react';\n"),
+            ("b.ts", "import { u } from './util';\nimport { api } from './api';\n"),
+            ("c.ts", "import { api } from './api';\n"),
+            ("index.ts", "export { api } from './api';\n"),
+        ]);
+
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+
+        let metrics = builder.coupling_metrics();
+        assert_eq!(metrics.len(), 6);
+        assert_eq!(metrics["util.ts"], (3, 0));
+        assert_eq!(metrics["api.ts"], (4, 1));
+        assert_eq!(metrics["a.ts"], (0, 2));
+        assert_eq!(metrics["c.ts"], (0, 1));
+        assert_eq!(metrics["index.ts"], (0, 1));
+
+        assert_eq!(builder.most_depended_on(1), vec![("api.ts".to_string(), 4)]);
+        assert_eq!(builder.most_depended_on(10), vec![("api.ts".to_string(), 4), ("util.ts".to_string(), 3)]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_dependency_graph_persistence() {
+        let root = write_workspace(&[
+            ("a.ts", "import { b } from './b';\n"),
+            ("b.ts", "export const b = 1;\n"),
+        ]);
+        let workspace = root.to_string_lossy().to_string();
+
+        let mut engine = crate::Engine::new_in_memory();
+        assert!(matches!(engine.save_dependency_graph(), Err(crate::ShadowError::GraphNotBuilt)));
+        assert!(!engine.load_dependency_graph(&workspace).unwrap());
+        assert!(engine.load_dependency_graph(&workspace).unwrap());
+        assert_eq!(engine.dependency_graph().unwrap().edges["a.ts"], vec!["b.ts".to_string()]);
+
+        std::fs::write(root.join("b.ts"), "export const b = 1;\nexport const c = 2;\n").unwrap();
+        assert!(!engine.load_dependency_graph(&workspace).unwrap());
+        assert!(engine.dependency_graph().unwrap().nodes["b.ts"].exports.contains(&"c".to_string()));
+        assert!(engine.load_dependency_graph(&format!("{}/", workspace)).unwrap());
+
+        // Saving after an edit must not vouch for a graph built before it
+        engine.build_dependency_graph(&workspace).unwrap();
+        std::fs::write(root.join("b.ts"), "export const b = 2;\n").unwrap();
+        engine.save_dependency_graph().unwrap();
+        assert!(!engine.load_dependency_graph(&workspace).unwrap());
+        assert!(!engine.dependency_graph().unwrap().nodes["b.ts"].exports.contains(&"c".to_string()));
+
+        // Files the build skips are skipped by the fingerprint too, so editing one leaves it current
+        std::fs::write(root.join(".gitignore"), "generated.ts\n").unwrap();
+        std::fs::write(root.join("generated.ts"), "export const g = 1;\n").unwrap();
+        std::fs::create_dir_all(root.join("vendor")).unwrap();
+        let mut builder = DependencyGraphBuilder::new(&workspace);
+        builder.set_ignore_globs(vec!["vendor/**".to_string()]);
+        builder.build_graph().unwrap();
+        assert!(builder.fingerprint().is_some());
+        std::fs::write(root.join("generated.ts"), "export const g = 2;\n").unwrap();
+        std::fs::write(root.join("vendor/lib.ts"), "export const v = 1;\n").unwrap();
+        assert_eq!(Some(builder.workspace_fingerprint().unwrap()), builder.fingerprint());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(crate::normalize_path("src\\lib\\a.ts"), "src/lib/a.ts");
+        assert_eq!(crate::normalize_path("C:\\Work\\app\\.\\src//a.ts"), "c:/Work/app/src/a.ts");
+        assert_eq!(crate::normalize_path("c:/Work/app/src/../lib\\b.ts"), "c:/Work/app/lib/b.ts");
+        assert_eq!(crate::normalize_path("./src/../../shared/c.ts"), "../shared/c.ts");
+        assert_eq!(crate::normalize_path("/work/../../app/"), "/app");
+        assert_eq!(crate::normalize_path("\\\\server\\share\\a.ts"), "//server/share/a.ts");
+
+        assert_eq!(crate::paths::relative_path("C:\\Work\\app", "c:/work/APP\\src\\a.ts"), "src/a.ts");
+        assert_eq!(crate::paths::relative_path("/work/app/", "/work/app\\src/./a.ts"), "src/a.ts");
+        assert_eq!(crate::paths::relative_path("/work/app", "/work/app2/a.ts"), "/work/app2/a.ts");
+        assert_eq!(crate::paths::relative_path("/work/app", "src\\a.ts"), "src/a.ts");
+    }
+
+    #[test]
+    fn test_mixed_separator_paths_match_graph_and_diffs() {
+        let root = write_workspace(&[
+            ("src/a.ts", "import { b } from '.\\\\lib\\\\b';\n"),
+            ("src/lib/b.ts", "export const b = 1;\n"),
+        ]);
+        let workspace = root.to_string_lossy().to_string();
+
+        let mut builder = DependencyGraphBuilder::new(&workspace);
+        builder.build_graph().unwrap();
+        assert!(builder.has_edge("src/a.ts", "src/lib/b.ts"));
+        assert!(builder.has_edge("src\\a.ts", "./src/lib\\b.ts"));
+
+        let impacted = builder.analyze_impact(&["src\\lib\\b.ts".to_string()]).impacted_files;
+        assert!(impacted.contains(&"src/a.ts".to_string()));
+        let absolute = format!("{}\\src\\lib/b.ts", workspace);
+        assert_eq!(builder.dependents_of(&absolute), vec!["src/a.ts".to_string()]);
+        assert_eq!(builder.import_path("src\\a.ts", "src/lib\\b.ts").unwrap(), vec!["src/a.ts", "src/lib/b.ts"]);
+        assert!(builder.is_test_file(".\\src\\a.test.ts"));
+        assert_eq!(builder.impact_context(&["src\\lib\\b.ts".to_string()], &[]).max_fan_in, 1);
+
+        let mut tracker = crate::DiffTracker::new(".shadow");
+        tracker.start_tracking(&root).unwrap();
+        std::fs::write(root.join("src/lib/b.ts"), "export const b = 2;\n").unwrap();
+        tracker.capture_changes().unwrap();
+        assert!(tracker.file_diff("src\\lib\\b.ts").is_some());
+        assert!(tracker.file_diff(&absolute).is_some());
+        assert!(tracker.compute_unified_diff("src\\lib/b.ts").unwrap().contains("+export const b = 2;"));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_scan_depth_limit() {
+        let root = write_workspace(&[
+            ("a.ts", "export const a = 1;\n"),
+            ("x/y/b.ts", "export const b = 1;\n"),
+            ("x/y/z/deep.ts", "export const deep = 1;\n"),
+            ("x/y/z/w/deeper.ts", "export const deeper = 1;\n"),
+        ]);
+
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.set_max_scan_depth(2);
+        builder.build_graph().unwrap();
+        let mut files: Vec<&String> = builder.get_graph().nodes.keys().collect();
+        files.sort();
+        assert_eq!(files, ["a.ts", "x/y/b.ts"]);
+        assert_eq!(builder.too_deep_dirs(), ["x/y/z"]);
+
+        let mut tracker = crate::DiffTracker::new(".shadow");
+        tracker.set_max_scan_depth(2);
+        tracker.start_tracking(&root).unwrap();
+        std::fs::write(root.join("x/y/z/deep.ts"), "export const deep = 2;\n").unwrap();
+        tracker.capture_changes().unwrap();
+        assert!(tracker.diffs().is_empty());
+        assert_eq!(tracker.too_deep_dirs(), ["x/y/z"]);
+
+        let mut builder = DependencyGraphBuilder::new(&root.to_string_lossy());
+        builder.build_graph().unwrap();
+        assert_eq!(builder.get_graph().nodes.len(), 4);
+        assert!(builder.too_deep_dirs().is_empty());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_engine_parse_file() {
+        let mut engine = crate::Engine::new();
+        let ast = engine.parse_file("src/util.ts", "export async function load() {\n  return 1;\n}\n").unwrap();
+        assert_eq!(ast.node_type, "Program");
+        let function = ast.children.iter().find(|node| node.name.as_deref() == Some("load")).unwrap();
+        assert_eq!(function.modifiers, ["async"]);
+
+        let ast = engine.parse_file("Cargo.toml", "[package]\nname = \"shadow\"\n").unwrap();
+        assert_eq!(ast.children[0].name.as_deref(), Some("package"));
+
+        engine.set_generic_diff_fallback(true);
+        let error = engine.parse_file("notes.txt", "plain text").unwrap_err();
+        assert!(matches!(error, crate::ShadowError::NoParser(ref extension) if extension == "txt"));
+    }
+
+    #[test]
+    fn test_qualified_names() {
+        let old = "class ClassA {\n  get() {\n    return 1;\n  }\n}\n\nclass ClassB {\n  get() {\n    return 2;\n  }\n}\n";
+        let new = "class ClassA {\n  get() {\n    return 1;\n  }\n}\n\nclass ClassB {\n  get() {\n    return 3;\n  }\n}\n";
+
+        let ast = TypeScriptParser::new().parse(old).unwrap();
+        let methods: Vec<Option<&str>> = ast.children.iter()
+            .flat_map(|class| class.children.iter().map(|method| method.qualified_name.as_deref()))
+            .collect();
+        assert_eq!(methods, [Some("ClassA.get"), Some("ClassB.get")]);
+
+        let diff = AstDiffEngine::new().compute_diff("a.ts", old, new).unwrap();
+        assert_eq!(diff.changes.len(), 1);
+        assert!(matches!(diff.changes[0].change_type, ChangeType::Modified));
+        assert_eq!(diff.changes[0].name.as_deref(), Some("get"));
+        assert_eq!(diff.changes[0].qualified_name.as_deref(), Some("ClassB.get"));
+        assert_eq!(diff.changes[0].line_range, (8, 10));
+
+        let ast = AstDiffEngine::new().parse("tsconfig.json", "{\"compilerOptions\": {\"paths\": {}}}").unwrap();
+        assert_eq!(ast.children[0].children[0].qualified_name.as_deref(), Some("compilerOptions.paths"));
+    }
+}
@@ -1,11 +1,92 @@
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::path::Path;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+use crate::ast_diff::languages::ts::extract_script_blocks;
+use crate::glob::glob_match;
+use crate::ignore::IgnoreRules;
+use crate::paths::{normalize_path, relative_path};
+use crate::walk::{sorted_entries, DirWalk, DEFAULT_MAX_SCAN_DEPTH};
+use crate::ShadowError;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct DependencyGraph {
     pub nodes: HashMap<String, GraphNode>,
     pub edges: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub reexport_edges: HashMap<String, Vec<String>>,
+}
+
+impl DependencyGraph {
+    // Graphviz output with files and edges sorted so regenerated files diff cleanly
+    pub fn to_dot(&self) -> String {
+        let files: HashSet<&str> = self.nodes.keys().map(String::as_str)
+            .chain(self.edges.iter().flat_map(|(from, targets)| {
+                std::iter::once(from.as_str()).chain(targets.iter().map(String::as_str))
+            }))
+            .collect();
+        self.render_dot(&files)
+    }
+
+    // Only the files within `depth` hops of a root, following edges in either direction
+    pub fn to_dot_filtered(&self, roots: &[String], depth: usize) -> String {
+        let mut neighbours: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (from, targets) in &self.edges {
+            for to in targets {
+                neighbours.entry(from.as_str()).or_default().push(to.as_str());
+                neighbours.entry(to.as_str()).or_default().push(from.as_str());
+            }
+        }
+
+        let mut files: HashSet<&str> = HashSet::new();
+        let mut queue: VecDeque<(&str, usize)> = VecDeque::new();
+        for root in roots {
+            if files.insert(root.as_str()) {
+                queue.push_back((root.as_str(), 0));
+            }
+        }
+
+        while let Some((file, distance)) = queue.pop_front() {
+            if distance == depth {
+                continue;
+            }
+            for next in neighbours.get(file).into_iter().flatten() {
+                if files.insert(next) {
+                    queue.push_back((next, distance + 1));
+                }
+            }
+        }
+
+        self.render_dot(&files)
+    }
+
+    fn render_dot(&self, files: &HashSet<&str>) -> String {
+        let mut sorted_files: Vec<&str> = files.iter().copied().collect();
+        sorted_files.sort();
+
+        let mut dot = String::from("digraph dependencies {\n");
+        for file in &sorted_files {
+            dot.push_str(&format!("  {};\n", dot_id(file)));
+        }
+
+        let mut edges: Vec<(&str, &str)> = self.edges.iter()
+            .filter(|(from, _)| files.contains(from.as_str()))
+            .flat_map(|(from, targets)| targets.iter().map(move |to| (from.as_str(), to.as_str())))
+            .filter(|(_, to)| files.contains(to))
+            .collect();
+        edges.sort();
+        edges.dedup();
+
+        for (from, to) in edges {
+            dot.push_str(&format!("  {} -> {};\n", dot_id(from), dot_id(to)));
+        }
+        dot.push_str("}\n");
+        dot
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -13,6 +94,16 @@ pub struct GraphNode {
     pub file_path: String,
     pub imports: Vec<String>,
     pub exports: Vec<String>,
+    #[serde(default)]
+    pub reexports: Vec<String>,
+    #[serde(default)]
+    pub external_imports: Vec<String>,
+    // Names used from each relative import, keyed like `imports`; `*` means all exports
+    #[serde(default)]
+    pub imported_symbols: HashMap<String, Vec<String>>,
+    // Set on synthetic nodes standing for a `declare module '...'` block
+    #[serde(default)]
+    pub ambient_module: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -20,8 +111,47 @@ pub struct ImpactAnalysis {
     pub changed_files: Vec<String>,
     pub impacted_files: Vec<String>,
     pub risk_level: RiskLevel,
+    // Number of import hops from the nearest changed file to each impacted file
+    #[serde(default)]
+    pub depths: HashMap<String, usize>,
+    // Human-readable factors behind risk_level, most severe first
+    #[serde(default)]
+    pub risk_reasons: Vec<String>,
+    #[serde(default)]
+    pub risk_score: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GlobImpactAnalysis {
+    pub analysis: ImpactAnalysis,
+    pub unmatched_patterns: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Coupling {
+    pub a_to_b: usize,
+    pub b_to_a: usize,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RiskContribution {
+    pub file: String,
+    pub impacted_count: usize,
+    pub share: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GraphMetrics {
+    pub density: f64,
+    pub avg_out_degree: f64,
+    pub max_out_degree: usize,
+    pub num_components: usize,
+    pub num_cycles: usize,
+}
+
+// (importing_file, specifier, missing_name)
+pub type ImportMismatch = (String, String, String);
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum RiskLevel {
     Low,
@@ -29,9 +159,119 @@ pub enum RiskLevel {
     High,
 }
 
+// Everything a risk scorer gets to look at for one impact analysis
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ImpactContext {
+    pub changed_count: usize,
+    pub impacted_count: usize,
+    // Highest number of direct importers among the changed files
+    pub max_fan_in: usize,
+    // Impacted count plus each changed file weighted by its fan-in; what thresholds bucket on
+    pub risk_score: f64,
+    // Changed or impacted files span more than one top-level directory
+    pub cross_package: bool,
+    // analyze_impact cannot know this; callers scoring their own contexts may set it
+    pub breaking_change: bool,
+}
+
+pub type RiskScorer = Box<dyn Fn(&ImpactContext) -> RiskLevel + Send + Sync>;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum RiskPreset {
+    Default,
+    Conservative,
+    Lenient,
+}
+
+impl RiskPreset {
+    pub fn scorer(self) -> RiskScorer {
+        match self {
+            RiskPreset::Default => RiskThresholds::default().scorer(),
+            RiskPreset::Conservative => Box::new(|context: &ImpactContext| {
+                if context.breaking_change || context.cross_package || context.max_fan_in >= 10 {
+                    return RiskLevel::High;
+                }
+                RiskThresholds { low_max: 1, medium_max: 4 }.level(context)
+            }),
+            RiskPreset::Lenient => RiskThresholds { low_max: 5, medium_max: 20 }.scorer(),
+        }
+    }
+}
+
+// Extra score a changed file adds per direct importer
+const FAN_IN_WEIGHT: f64 = 0.5;
+
+// Buckets on ImpactContext::risk_score: up to `low_max` is Low, up to `medium_max` Medium
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RiskThresholds {
+    pub low_max: usize,
+    pub medium_max: usize,
+}
+
+impl Default for RiskThresholds {
+    fn default() -> Self {
+        RiskThresholds { low_max: 2, medium_max: 7 }
+    }
+}
+
+impl RiskThresholds {
+    pub fn new(low_max: usize, medium_max: usize) -> Result<Self, ShadowError> {
+        if medium_max < low_max {
+            return Err(ShadowError::InvalidInput(format!("medium_max ({}) must not be below low_max ({})", medium_max, low_max)));
+        }
+        Ok(RiskThresholds { low_max, medium_max })
+    }
+
+    pub fn level(&self, context: &ImpactContext) -> RiskLevel {
+        if context.risk_score <= self.low_max as f64 {
+            RiskLevel::Low
+        } else if context.risk_score <= self.medium_max as f64 {
+            RiskLevel::Medium
+        } else {
+            RiskLevel::High
+        }
+    }
+
+    pub fn scorer(self) -> RiskScorer {
+        Box::new(move |context: &ImpactContext| self.level(context))
+    }
+}
+
+// Returned when files can't be ordered because they import each other
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CycleError {
+    pub cycles: Vec<Vec<String>>,
+}
+
+impl std::fmt::Display for CycleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cycles: Vec<String> = self.cycles.iter().map(|cycle| cycle.join(" -> ")).collect();
+        write!(f, "Dependency graph contains cycles: {}", cycles.join("; "))
+    }
+}
+
+impl std::error::Error for CycleError {}
+
 pub struct DependencyGraphBuilder {
     graph: DependencyGraph,
     workspace_root: String,
+    include_stylesheets: bool,
+    tests_as_sinks: bool,
+    test_patterns: Vec<String>,
+    risk_scorer: RiskScorer,
+    respect_gitignore: bool,
+    ignore_globs: Vec<String>,
+    tsconfig_path: Option<PathBuf>,
+    // tsconfig `paths` pattern -> workspace-relative targets, longest prefix first
+    path_aliases: Vec<(String, Vec<String>)>,
+    cancel_flag: Option<Arc<AtomicBool>>,
+    follow_symlinks: bool,
+    max_scan_depth: usize,
+    // Directories the last build skipped for being deeper than max_scan_depth
+    too_deep_dirs: Vec<String>,
+    // Workspace fingerprint taken just before the graph was scanned; None once the
+    // graph has been patched file by file
+    fingerprint: Option<u64>,
 }
 
 impl DependencyGraphBuilder {
@@ -40,120 +280,1026 @@ impl DependencyGraphBuilder {
             graph: DependencyGraph {
                 nodes: HashMap::new(),
                 edges: HashMap::new(),
+                reexport_edges: HashMap::new(),
             },
             workspace_root: workspace_root.to_string(),
+            include_stylesheets: true,
+            tests_as_sinks: false,
+            test_patterns: vec![
+                "**/*.test.*".to_string(),
+                "**/*.spec.*".to_string(),
+                "**/__tests__/**".to_string(),
+            ],
+            risk_scorer: RiskPreset::Default.scorer(),
+            respect_gitignore: true,
+            ignore_globs: Vec::new(),
+            tsconfig_path: None,
+            path_aliases: Vec::new(),
+            cancel_flag: None,
+            follow_symlinks: false,
+            max_scan_depth: DEFAULT_MAX_SCAN_DEPTH,
+            too_deep_dirs: Vec::new(),
+            fingerprint: None,
         }
     }
 
-    pub fn build_graph(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        self.scan_workspace(&self.workspace_root.clone())?;
-        self.build_edges()?;
+    // Symlinked directories are skipped while scanning unless this is set
+    pub fn set_follow_symlinks(&mut self, enabled: bool) {
+        self.follow_symlinks = enabled;
+    }
+
+    // Directories nested deeper than this below the workspace root are not scanned
+    pub fn set_max_scan_depth(&mut self, depth: usize) {
+        self.max_scan_depth = depth;
+    }
+
+    pub fn too_deep_dirs(&self) -> &[String] {
+        &self.too_deep_dirs
+    }
+
+    pub fn workspace_root(&self) -> &str {
+        &self.workspace_root
+    }
+
+    // Builds check this flag as they go and stop with `ShadowError::Cancelled` once it is set
+    pub fn set_cancel_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.cancel_flag = Some(flag);
+    }
+
+    pub fn set_risk_scorer(&mut self, scorer: RiskScorer) {
+        self.risk_scorer = scorer;
+    }
+
+    pub fn set_risk_preset(&mut self, preset: RiskPreset) {
+        self.risk_scorer = preset.scorer();
+    }
+
+    pub fn set_risk_thresholds(&mut self, thresholds: RiskThresholds) {
+        self.risk_scorer = thresholds.scorer();
+    }
+
+    pub fn with_risk_thresholds(mut self, thresholds: RiskThresholds) -> Self {
+        self.set_risk_thresholds(thresholds);
+        self
+    }
+
+    // Test files can still be impacted, but no longer pass impact on to their importers
+    pub fn set_tests_as_sinks(&mut self, enabled: bool) {
+        self.tests_as_sinks = enabled;
+    }
+
+    pub fn set_test_patterns(&mut self, patterns: Vec<String>) {
+        self.test_patterns = patterns;
+    }
+
+    pub fn is_test_file(&self, file: &str) -> bool {
+        let file = self.graph_key(file);
+        self.test_patterns.iter().any(|pattern| glob_match(pattern, &file))
+    }
+
+    pub fn set_include_stylesheets(&mut self, include: bool) {
+        self.include_stylesheets = include;
+    }
+
+    // Skip paths matched by .gitignore files while scanning (on by default)
+    pub fn set_respect_gitignore(&mut self, enabled: bool) {
+        self.respect_gitignore = enabled;
+    }
+
+    // Workspace-relative globs skipped while scanning, on top of .gitignore
+    pub fn set_ignore_globs(&mut self, globs: Vec<String>) {
+        self.ignore_globs = globs;
+    }
+
+    // Defaults to tsconfig.json at the workspace root; relative paths are resolved against it
+    pub fn set_tsconfig_path(&mut self, path: &str) {
+        self.tsconfig_path = Some(Path::new(&self.workspace_root).join(path));
+    }
+
+    pub fn build_graph(&mut self) -> Result<(), ShadowError> {
+        self.build_with(&mut |_, _| {})
+    }
+
+    // Like build_graph, reporting (files scanned, total) every PROGRESS_INTERVAL files
+    // and once more when done. The total is known before the first file is read.
+    pub fn build_graph_with_progress(&mut self, on_progress: &mut dyn FnMut(usize, usize)) -> Result<(), ShadowError> {
+        let mut scanned = 0;
+        self.build_with(&mut |node, total| {
+            if node.ambient_module.is_some() {
+                return;
+            }
+            scanned += 1;
+            if scanned % PROGRESS_INTERVAL == 0 {
+                on_progress(scanned, total.max(scanned));
+            }
+        })?;
+
+        on_progress(scanned, scanned);
+        Ok(())
+    }
+
+    pub fn scan_streaming<F>(&mut self, mut on_node: F) -> Result<(), ShadowError>
+    where
+        F: FnMut(&GraphNode),
+    {
+        // Same as build_graph, but hands out each node as soon as it is analyzed
+        self.build_with(&mut |node, _| on_node(node))
+    }
+
+    // `on_node` also gets the number of files the scan is going to read
+    fn build_with(&mut self, on_node: &mut dyn FnMut(&GraphNode, usize)) -> Result<(), ShadowError> {
+        self.load_path_aliases();
+        let mut walk = self.dir_walk();
+        // The fingerprint covers exactly the files the scan reads. It is taken before any
+        // of them is read, so an edit made during the scan makes it stale rather than
+        // vouching for a graph that missed it.
+        let result = self.supported_files(&mut walk)
+            .and_then(|files| {
+                self.fingerprint = Some(self.fingerprint_files(&files));
+                self.analyze_files(&files, on_node)
+            })
+            .and_then(|_| self.build_edges());
+
+        let mut too_deep_dirs: Vec<String> = walk.too_deep().iter().map(|dir| self.get_relative_path(dir)).collect();
+        too_deep_dirs.sort();
+        self.too_deep_dirs = too_deep_dirs;
+
+        // A cancelled or failed build must not leave a half-populated graph behind
+        if result.is_err() {
+            self.graph = DependencyGraph::default();
+            self.fingerprint = None;
+        }
+        result
+    }
+
+    fn dir_walk(&self) -> DirWalk {
+        DirWalk::new(Path::new(&self.workspace_root), self.follow_symlinks, self.max_scan_depth)
+    }
+
+    fn check_cancelled(&self) -> Result<(), ShadowError> {
+        match &self.cancel_flag {
+            Some(flag) if flag.load(Ordering::Relaxed) => Err(ShadowError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    // Re-reads one file (relative to the workspace root) and patches its nodes and
+    // outgoing edges. A file that no longer exists is removed instead.
+    pub fn update_file(&mut self, file_path: &str) -> Result<(), ShadowError> {
+        self.fingerprint = None;
+        let file_path = self.graph_key(file_path);
+        let path = Path::new(&self.workspace_root).join(&file_path);
+        if !path.is_file() {
+            self.remove_file(&file_path);
+            return Ok(());
+        }
+        if !self.is_supported_file(&path) {
+            return Ok(());
+        }
+
+        let relative_path = self.get_relative_path(&path);
+        let is_new = !self.graph.nodes.contains_key(&relative_path);
+        let old_ambient: Vec<String> = self.file_node_keys(&relative_path).into_iter()
+            .filter(|key| *key != relative_path)
+            .collect();
+        for key in &old_ambient {
+            self.graph.nodes.remove(key);
+            self.graph.edges.remove(key);
+            self.graph.reexport_edges.remove(key);
+        }
+
+        let nodes = self.analyze_file(&path)?;
+        let new_ambient: Vec<String> = nodes.iter().skip(1).map(|node| node.file_path.clone()).collect();
+        for node in nodes {
+            self.graph.nodes.insert(node.file_path.clone(), node);
+        }
+
+        // Existing imports may start resolving to a new file, so only then do all edges
+        // need another pass; otherwise just this file's own nodes are re-linked
+        if is_new || old_ambient != new_ambient {
+            return self.build_edges();
+        }
+
+        let ambient_modules = self.ambient_modules();
+        for key in self.file_node_keys(&relative_path) {
+            self.build_edges_for(&key, &ambient_modules);
+        }
         Ok(())
     }
 
+    // Drops a file's nodes and every edge leading into or out of them
+    pub fn remove_file(&mut self, file_path: &str) {
+        self.fingerprint = None;
+        let keys = self.file_node_keys(&self.graph_key(file_path));
+        for key in &keys {
+            self.graph.nodes.remove(key);
+            self.graph.edges.remove(key);
+            self.graph.reexport_edges.remove(key);
+        }
+        self.prune_edges_to(&keys);
+    }
+
+    // The file's own node plus any `declare module` nodes it contributes
+    fn file_node_keys(&self, file_path: &str) -> Vec<String> {
+        let ambient_prefix = format!("{}#", file_path);
+        let mut keys: Vec<String> = self.graph.nodes.keys()
+            .filter(|key| *key == file_path || key.starts_with(&ambient_prefix))
+            .cloned()
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    fn prune_edges_to(&mut self, removed: &[String]) {
+        for targets in self.graph.edges.values_mut().chain(self.graph.reexport_edges.values_mut()) {
+            targets.retain(|target| !removed.contains(target));
+        }
+    }
+
+    // Files that import `file` directly
+    pub fn dependents_of(&self, file: &str) -> Vec<String> {
+        let mut dependents = self.find_dependents(&self.graph_key(file)).unwrap_or_default();
+        dependents.sort();
+        dependents
+    }
+
+    // Every file that reaches `file` through one or more imports
+    pub fn transitive_dependents_of(&self, file: &str) -> Vec<String> {
+        let file = self.graph_key(file);
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::from([file.to_string()]);
+
+        while let Some(current_file) = queue.pop_front() {
+            for dependent in self.find_dependents(&current_file).unwrap_or_default() {
+                if dependent != file && seen.insert(dependent.clone()) {
+                    queue.push_back(dependent);
+                }
+            }
+        }
+
+        let mut dependents: Vec<String> = seen.into_iter().collect();
+        dependents.sort();
+        dependents
+    }
+
+    // Shortest import chain from `from` to `to`, both included. Imports are explored in
+    // sorted order, so among equally short chains the lexicographically smallest wins.
+    pub fn import_path(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        let (from, to) = (self.graph_key(from), self.graph_key(to));
+        let (from, to) = (from.as_str(), to.as_str());
+        if !self.graph.nodes.contains_key(from) {
+            return None;
+        }
+
+        let mut parents: HashMap<&str, &str> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        while let Some(current_file) = queue.pop_front() {
+            if current_file == to {
+                let mut path = vec![to.to_string()];
+                let mut file = to;
+                while let Some(parent) = parents.get(file) {
+                    path.push(parent.to_string());
+                    file = parent;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            let mut imports: Vec<&str> = self.graph.edges.get(current_file).into_iter()
+                .flatten()
+                .map(String::as_str)
+                .collect();
+            imports.sort();
+            for import in imports {
+                if import != from && !parents.contains_key(import) {
+                    parents.insert(import, current_file);
+                    queue.push_back(import);
+                }
+            }
+        }
+
+        None
+    }
+
+    // Files nothing imports and that import nothing local; re-exports count as imports
+    // both ways. Files matching one of `ignore_entrypoints` (paths or globs), and
+    // anything they reach, are left out.
+    pub fn orphan_files(&self, ignore_entrypoints: &[String]) -> Vec<String> {
+        let imported: HashSet<&String> = self.graph.edges.values()
+            .chain(self.graph.reexport_edges.values())
+            .flatten()
+            .collect();
+
+        let mut reachable: HashSet<&str> = HashSet::new();
+        let mut queue: VecDeque<&str> = self.graph.nodes.keys()
+            .filter(|file| ignore_entrypoints.iter().any(|pattern| glob_match(pattern, file)))
+            .map(|file| file.as_str())
+            .collect();
+        while let Some(current_file) = queue.pop_front() {
+            if !reachable.insert(current_file) {
+                continue;
+            }
+            let targets = self.graph.edges.get(current_file).into_iter()
+                .chain(self.graph.reexport_edges.get(current_file))
+                .flatten();
+            queue.extend(targets.map(|target| target.as_str()));
+        }
+
+        let mut orphans: Vec<String> = self.graph.nodes.keys()
+            .filter(|file| !imported.contains(file) && !reachable.contains(file.as_str()))
+            .filter(|file| {
+                self.graph.edges.get(*file).into_iter()
+                    .chain(self.graph.reexport_edges.get(*file))
+                    .all(|dependencies| dependencies.is_empty())
+            })
+            .cloned()
+            .collect();
+        orphans.sort();
+        orphans
+    }
+
+    // Exported names no other file imports, keyed by file. A name re-exported through a
+    // barrel counts as used by it, and so does everything in a file pulled in whole
+    // (`import *`, `export *`, require). Default exports are always treated as used.
+    // Files matching one of `ignore_entrypoints` (paths or globs) are left out, since
+    // their exports are consumed outside the workspace.
+    pub fn unused_exports(&self, ignore_entrypoints: &[String]) -> HashMap<String, Vec<String>> {
+        // target file -> names other files take from it; None once something takes them all
+        let mut used: HashMap<String, Option<HashSet<&str>>> = HashMap::new();
+        for (importer, node) in &self.graph.nodes {
+            for specifier in node.imports.iter().chain(&node.reexports) {
+                let Some(target) = self.resolve_import_to_file(specifier) else {
+                    continue;
+                };
+                if target == *importer {
+                    continue;
+                }
+
+                let names = used.entry(target).or_insert_with(|| Some(HashSet::new()));
+                match node.imported_symbols.get(specifier) {
+                    Some(symbols) if !symbols.iter().any(|symbol| symbol == "*") => {
+                        if let Some(names) = names {
+                            names.extend(symbols.iter().map(String::as_str));
+                        }
+                    }
+                    _ => *names = None,
+                }
+            }
+        }
+
+        let mut unused = HashMap::new();
+        for (file, node) in &self.graph.nodes {
+            if node.ambient_module.is_some() || ignore_entrypoints.iter().any(|pattern| glob_match(pattern, file)) {
+                continue;
+            }
+
+            let used_names = used.get(file);
+            let mut names: Vec<String> = node.exports.iter()
+                .filter(|name| *name != "default")
+                .filter(|name| match used_names {
+                    Some(Some(used_names)) => !used_names.contains(name.as_str()),
+                    Some(None) => false,
+                    None => true,
+                })
+                .cloned()
+                .collect();
+            if names.is_empty() {
+                continue;
+            }
+            names.sort();
+            names.dedup();
+            unused.insert(file.clone(), names);
+        }
+        unused
+    }
+
+    // Bare package specifiers (`react`, `node:fs`, `lodash/merge`) and how many files use each
+    pub fn external_dependencies(&self) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for node in self.graph.nodes.values() {
+            let used: HashSet<&String> = node.external_imports.iter().collect();
+            for package in used {
+                *counts.entry(package.clone()).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
     pub fn analyze_impact(&self, changed_files: &[String]) -> ImpactAnalysis {
-        let mut impacted = HashSet::new();
+        self.analyze_impact_with_depth(changed_files, usize::MAX)
+    }
+
+    // Like analyze_impact, but files more than `max_depth` imports away are not reached
+    pub fn analyze_impact_with_depth(&self, changed_files: &[String], max_depth: usize) -> ImpactAnalysis {
+        let changed_files: Vec<String> = changed_files.iter().map(|file| self.graph_key(file)).collect();
+        let mut depths: HashMap<String, usize> = HashMap::new();
         let mut queue = VecDeque::new();
 
         // Start with directly changed files
-        for file in changed_files {
-            queue.push_back(file.clone());
-            impacted.insert(file.clone());
+        for file in &changed_files {
+            if self.propagates_impact(file) {
+                queue.push_back(file.clone());
+            }
+            depths.insert(file.clone(), 0);
         }
 
         // BFS to find all impacted files
         while let Some(current_file) = queue.pop_front() {
+            let depth = depths[&current_file];
+            if depth >= max_depth {
+                continue;
+            }
+
             if let Some(dependents) = self.find_dependents(&current_file) {
                 for dependent in dependents {
-                    if !impacted.contains(&dependent) {
-                        impacted.insert(dependent.clone());
+                    if !depths.contains_key(&dependent) {
+                        depths.insert(dependent.clone(), depth + 1);
+                        if self.propagates_impact(&dependent) {
+                            queue.push_back(dependent);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.impact_analysis(&changed_files, depths)
+    }
+
+    // Only importers that use one of the changed exports are impacted; from there
+    // impact spreads file by file. Re-exports pass the symbol on to their own importers.
+    pub fn analyze_symbol_impact(&self, changed: &[(String, String)]) -> ImpactAnalysis {
+        let changed: Vec<(String, String)> = changed.iter()
+            .map(|(file, symbol)| (self.graph_key(file), symbol.clone()))
+            .collect();
+        let mut changed_files: Vec<String> = Vec::new();
+        for (file, _) in &changed {
+            if !changed_files.contains(file) {
+                changed_files.push(file.clone());
+            }
+        }
+
+        let mut depths: HashMap<String, usize> = changed_files.iter()
+            .map(|file| (file.clone(), 0))
+            .collect();
+        let mut visited: HashSet<(String, String)> = HashSet::new();
+        let mut symbol_queue: VecDeque<(String, String, usize)> = changed.iter()
+            .map(|(file, symbol)| (file.clone(), symbol.clone(), 0))
+            .collect();
+        let mut queue = VecDeque::new();
+
+        while let Some((file, symbol, depth)) = symbol_queue.pop_front() {
+            if !visited.insert((file.clone(), symbol.clone())) {
+                continue;
+            }
+
+            for (importer, imported, reexported) in self.symbol_importers(&file, &symbol) {
+                depths.entry(importer.clone()).or_insert(depth + 1);
+                // A pure barrel only forwards the symbol, so its other importers are unaffected
+                if imported && !queue.contains(&importer) && self.propagates_impact(&importer) {
+                    queue.push_back(importer.clone());
+                }
+                if reexported {
+                    symbol_queue.push_back((importer, symbol.clone(), depth + 1));
+                }
+            }
+        }
+
+        while let Some(current_file) = queue.pop_front() {
+            let depth = depths[&current_file];
+            for dependent in self.find_dependents(&current_file).unwrap_or_default() {
+                if !depths.contains_key(&dependent) {
+                    depths.insert(dependent.clone(), depth + 1);
+                    if self.propagates_impact(&dependent) {
                         queue.push_back(dependent);
                     }
                 }
             }
         }
 
-        // Remove the originally changed files from impacted list
-        let impacted_files: Vec<String> = impacted.iter()
-            .filter(|file| !changed_files.contains(file))
-            .cloned()
-            .collect();
-
-        let risk_level = self.calculate_risk_level(changed_files.len(), impacted_files.len());
-
-        ImpactAnalysis {
-            changed_files: changed_files.to_vec(),
-            impacted_files,
-            risk_level,
+        self.impact_analysis(&changed_files, depths)
+    }
+
+    // Files using `symbol` from `file`, with whether they import it and whether they re-export it.
+    // Imports without a recorded name list (require, dynamic import) count as using everything.
+    fn symbol_importers(&self, file: &str, symbol: &str) -> Vec<(String, bool, bool)> {
+        let mut importers = Vec::new();
+
+        for (importer, node) in &self.graph.nodes {
+            let uses = |specifier: &String| {
+                self.resolve_import_to_file(specifier).as_deref() == Some(file)
+                    && node.imported_symbols.get(specifier)
+                        .is_none_or(|names| names.iter().any(|name| name == symbol || name == "*"))
+            };
+
+            let imported = node.imports.iter().any(uses);
+            let reexported = node.reexports.iter().any(uses);
+            if imported || reexported {
+                importers.push((importer.clone(), imported, reexported));
+            }
+        }
+
+        importers.sort();
+        importers
+    }
+
+    fn impact_analysis(&self, changed_files: &[String], mut depths: HashMap<String, usize>) -> ImpactAnalysis {
+        // Remove the originally changed files from impacted list, and the synthetic
+        // `file.d.ts#module` nodes impact passes through on its way to their importers
+        depths.retain(|file, _| {
+            !changed_files.contains(file)
+                && self.graph.nodes.get(file).is_none_or(|node| node.ambient_module.is_none())
+        });
+        let impacted_files: Vec<String> = depths.keys().cloned().collect();
+
+        let context = self.impact_context(changed_files, &impacted_files);
+        let risk_level = self.calculate_risk_level(&context);
+        let risk_reasons = self.risk_reasons(changed_files, &impacted_files, &context);
+
+        ImpactAnalysis {
+            changed_files: changed_files.to_vec(),
+            impacted_files,
+            risk_level,
+            depths,
+            risk_reasons,
+            risk_score: context.risk_score,
+        }
+    }
+
+    // Reasons come in a fixed order (entrypoints, fan-in, spread, size) so the output is stable
+    fn risk_reasons(&self, changed_files: &[String], impacted_files: &[String], context: &ImpactContext) -> Vec<String> {
+        let mut reasons = Vec::new();
+
+        let mut entrypoints: Vec<&String> = changed_files.iter()
+            .chain(impacted_files)
+            .filter(|file| self.is_entrypoint(file))
+            .collect();
+        entrypoints.sort();
+        entrypoints.dedup();
+        for entrypoint in entrypoints {
+            reasons.push(format!("change reaches entrypoint {}", entrypoint));
+        }
+
+        if context.max_fan_in > 0 {
+            let noun = if context.max_fan_in == 1 { "other" } else { "others" };
+            reasons.push(format!("touches file imported by {} {}", context.max_fan_in, noun));
+        }
+
+        if context.cross_package {
+            reasons.push("change spans multiple top-level directories".to_string());
+        }
+
+        if !impacted_files.is_empty() {
+            let noun = if impacted_files.len() == 1 { "file" } else { "files" };
+            reasons.push(format!("{} {} impacted", impacted_files.len(), noun));
+        }
+
+        reasons
+    }
+
+    pub fn impact_of(&self, file: &str) -> Vec<String> {
+        let mut impacted = self.analyze_impact(&[file.to_string()]).impacted_files;
+        impacted.sort();
+        impacted
+    }
+
+    pub fn risk_contributors(&self, changed_files: &[String]) -> Vec<RiskContribution> {
+        let total_impacted = self.analyze_impact(changed_files).impacted_files.len();
+
+        let mut contributions: Vec<RiskContribution> = changed_files.iter()
+            .map(|file| {
+                let impacted_count = self.impact_of(file).len();
+                let share = if total_impacted == 0 {
+                    0.0
+                } else {
+                    impacted_count as f64 / total_impacted as f64
+                };
+
+                RiskContribution {
+                    file: self.graph_key(file),
+                    impacted_count,
+                    share,
+                }
+            })
+            .collect();
+
+        // Largest blast radius first, ties broken by path
+        contributions.sort_by(|a, b| {
+            b.impacted_count.cmp(&a.impacted_count).then_with(|| a.file.cmp(&b.file))
+        });
+        contributions
+    }
+
+    pub fn analyze_impact_globs(&self, patterns: &[String]) -> GlobImpactAnalysis {
+        let mut changed_files: Vec<String> = Vec::new();
+        let mut unmatched_patterns = Vec::new();
+
+        // Expand each pattern against the known graph nodes
+        for pattern in patterns {
+            let mut matched: Vec<&String> = self.graph.nodes.keys()
+                .filter(|file| glob_match(pattern, file))
+                .collect();
+
+            if matched.is_empty() {
+                unmatched_patterns.push(pattern.clone());
+                continue;
+            }
+
+            matched.sort();
+            for file in matched {
+                if !changed_files.contains(file) {
+                    changed_files.push(file.clone());
+                }
+            }
+        }
+
+        GlobImpactAnalysis {
+            analysis: self.analyze_impact(&changed_files),
+            unmatched_patterns,
+        }
+    }
+
+    pub fn has_edge(&self, from: &str, to: &str) -> bool {
+        let to = self.graph_key(to);
+        self.graph.edges.get(&self.graph_key(from))
+            .is_some_and(|dependencies| dependencies.contains(&to))
+    }
+
+    pub fn coupling(&self, dir_a: &str, dir_b: &str) -> Coupling {
+        let prefix_a = format!("{}/", self.graph_key(dir_a));
+        let prefix_b = format!("{}/", self.graph_key(dir_b));
+        let mut coupling = Coupling { a_to_b: 0, b_to_a: 0 };
+
+        // A re-export ties two directories together like an import does; a file that
+        // both imports and re-exports another is one dependency, not two
+        let dependencies: HashSet<(&String, &String)> = self.graph.edges.iter()
+            .chain(&self.graph.reexport_edges)
+            .flat_map(|(from, targets)| targets.iter().map(move |to| (from, to)))
+            .collect();
+
+        for (from, to) in dependencies {
+            if from.starts_with(&prefix_a) && to.starts_with(&prefix_b) {
+                coupling.a_to_b += 1;
+            }
+            if from.starts_with(&prefix_b) && to.starts_with(&prefix_a) {
+                coupling.b_to_a += 1;
+            }
+        }
+
+        coupling
+    }
+
+    pub fn metrics(&self) -> GraphMetrics {
+        let node_count = self.graph.nodes.len();
+        let edge_count: usize = self.graph.edges.values().map(|targets| targets.len()).sum();
+        let max_out_degree = self.graph.edges.values().map(|targets| targets.len()).max().unwrap_or(0);
+
+        let density = if node_count > 1 {
+            edge_count as f64 / (node_count * (node_count - 1)) as f64
+        } else {
+            0.0
+        };
+        let avg_out_degree = if node_count > 0 {
+            edge_count as f64 / node_count as f64
+        } else {
+            0.0
+        };
+
+        GraphMetrics {
+            density,
+            avg_out_degree,
+            max_out_degree,
+            num_components: self.count_components(),
+            num_cycles: find_cycles_in(&self.graph.edges).len(),
+        }
+    }
+
+    // (fan_in, fan_out) for every file: how many files import or re-export it, and how
+    // many files it imports or re-exports. Only edges between files in the graph count,
+    // each pair once.
+    pub fn coupling_metrics(&self) -> HashMap<String, (usize, usize)> {
+        let mut metrics: HashMap<String, (usize, usize)> = self.graph.nodes.keys()
+            .map(|file| (file.clone(), (0, 0)))
+            .collect();
+
+        for file in self.graph.nodes.keys() {
+            let targets: HashSet<&String> = self.graph.edges.get(file).into_iter()
+                .chain(self.graph.reexport_edges.get(file))
+                .flatten()
+                .filter(|target| *target != file && self.graph.nodes.contains_key(*target))
+                .collect();
+
+            for target in &targets {
+                if let Some((fan_in, _)) = metrics.get_mut(*target) {
+                    *fan_in += 1;
+                }
+            }
+            if let Some((_, fan_out)) = metrics.get_mut(file) {
+                *fan_out = targets.len();
+            }
+        }
+
+        metrics
+    }
+
+    // The `n` files imported by the most others, ties broken by path. Files nothing
+    // imports are left out.
+    pub fn most_depended_on(&self, n: usize) -> Vec<(String, usize)> {
+        let mut hotspots: Vec<(String, usize)> = self.coupling_metrics().into_iter()
+            .filter(|(_, (fan_in, _))| *fan_in > 0)
+            .map(|(file, (fan_in, _))| (file, fan_in))
+            .collect();
+        hotspots.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        hotspots.truncate(n);
+        hotspots
+    }
+
+    pub fn import_mismatches(&self) -> Vec<ImportMismatch> {
+        let mut mismatches = Vec::new();
+
+        for (file_path, node) in &self.graph.nodes {
+            for (specifier, names) in &node.imported_symbols {
+                let Some(target) = self.resolve_import_to_file(specifier) else {
+                    continue;
+                };
+
+                // Skip targets whose export surface can't be fully determined
+                let Some(available) = self.exported_names(&target, &mut HashSet::new()) else {
+                    continue;
+                };
+                if available.is_empty() {
+                    continue;
+                }
+
+                for name in names {
+                    if name != "*" && name != "default" && !available.contains(name) {
+                        mismatches.push((file_path.clone(), specifier.clone(), name.clone()));
+                    }
+                }
+            }
+        }
+
+        mismatches.sort();
+        mismatches
+    }
+
+    // Everything a file exports, following `export * from` chains.
+    // Returns None when a star re-export points somewhere unresolvable.
+    fn exported_names(&self, file: &str, visited: &mut HashSet<String>) -> Option<HashSet<String>> {
+        let mut names = HashSet::new();
+        if !visited.insert(file.to_string()) {
+            return Some(names);
+        }
+
+        let node = self.graph.nodes.get(file)?;
+        names.extend(node.exports.iter().cloned());
+
+        for source in &node.reexports {
+            let is_star = node.imported_symbols.get(source)
+                .is_some_and(|symbols| symbols.iter().any(|symbol| symbol == "*"));
+            if is_star {
+                let target = self.resolve_import_to_file(source)?;
+                names.extend(self.exported_names(&target, visited)?);
+            }
+        }
+
+        Some(names)
+    }
+
+    // Import cycles, each listed from its lexicographically smallest file
+    pub fn find_cycles(&self) -> Vec<Vec<String>> {
+        find_cycles_in(&self.graph.edges)
+    }
+
+    // Kahn's algorithm: every file comes after all files it imports. Ready files are
+    // taken in lexicographic order so the result is stable between runs.
+    pub fn topological_order(&self) -> Result<Vec<String>, CycleError> {
+        let mut files: HashSet<&str> = self.graph.nodes.keys().map(String::as_str).collect();
+        for (file, targets) in &self.graph.edges {
+            files.insert(file);
+            files.extend(targets.iter().map(String::as_str));
+        }
+
+        let mut remaining_imports: HashMap<&str, usize> = HashMap::new();
+        let mut importers: HashMap<&str, Vec<&str>> = HashMap::new();
+        for file in &files {
+            let targets: HashSet<&str> = self.graph.edges.get(*file)
+                .map(|targets| targets.iter().map(String::as_str).collect())
+                .unwrap_or_default();
+            remaining_imports.insert(file, targets.len());
+            for target in targets {
+                importers.entry(target).or_default().push(file);
+            }
+        }
+
+        let mut ready: std::collections::BTreeSet<&str> = remaining_imports.iter()
+            .filter(|(_, count)| **count == 0)
+            .map(|(file, _)| *file)
+            .collect();
+        let mut order = Vec::with_capacity(files.len());
+
+        while let Some(file) = ready.pop_first() {
+            order.push(file.to_string());
+            for importer in importers.get(file).into_iter().flatten() {
+                let count = remaining_imports.get_mut(importer).expect("importer is a known file");
+                *count -= 1;
+                if *count == 0 {
+                    ready.insert(importer);
+                }
+            }
+        }
+
+        if order.len() < files.len() {
+            return Err(CycleError { cycles: self.find_cycles() });
+        }
+
+        Ok(order)
+    }
+
+    pub fn reexport_cycles(&self) -> Vec<Vec<String>> {
+        find_cycles_in(&self.graph.reexport_edges)
+    }
+
+    pub fn get_graph(&self) -> &DependencyGraph {
+        &self.graph
+    }
+
+    // Adopts a previously built graph (e.g. one loaded from the store) instead of scanning
+    pub fn restore_graph(&mut self, graph: DependencyGraph, fingerprint: Option<u64>) {
+        self.load_path_aliases();
+        self.graph = graph;
+        self.fingerprint = fingerprint;
+    }
+
+    // The workspace_fingerprint the current graph was built against, if it still holds
+    pub fn fingerprint(&self) -> Option<u64> {
+        self.fingerprint
+    }
+
+    // Cheap stand-in for a content hash: the path, size and modification time of every
+    // file a scan would read, plus the tsconfig aliases come from. Reads no file contents.
+    pub fn workspace_fingerprint(&self) -> Result<u64, ShadowError> {
+        Ok(self.fingerprint_files(&self.supported_files(&mut self.dir_walk())?))
+    }
+
+    fn fingerprint_files(&self, files: &[PathBuf]) -> u64 {
+        let root = Path::new(&self.workspace_root);
+        let mut files = files.to_vec();
+        files.push(self.tsconfig_path.clone().unwrap_or_else(|| root.join("tsconfig.json")));
+        files.sort();
+
+        let mut hasher = DefaultHasher::new();
+        for file in &files {
+            relative_path(&self.workspace_root, &file.to_string_lossy()).hash(&mut hasher);
+            if let Ok(metadata) = std::fs::metadata(file) {
+                metadata.len().hash(&mut hasher);
+                if let Ok(modified) = metadata.modified() {
+                    modified.hash(&mut hasher);
+                }
+            }
+        }
+        hasher.finish()
+    }
+
+    fn analyze_files(&mut self, files: &[PathBuf], on_node: &mut dyn FnMut(&GraphNode, usize)) -> Result<(), ShadowError> {
+        for file_path in files {
+            self.check_cancelled()?;
+            for node in self.analyze_file(file_path)? {
+                on_node(&node, files.len());
+                self.graph.nodes.insert(node.file_path.clone(), node);
+            }
         }
+
+        Ok(())
     }
 
-    pub fn get_graph(&self) -> &DependencyGraph {
-        &self.graph
+    // Every file a build reads, in scan order
+    fn supported_files(&self, walk: &mut DirWalk) -> Result<Vec<PathBuf>, ShadowError> {
+        let mut files = Vec::new();
+        self.collect_supported_files(&self.workspace_root, &mut IgnoreRules::default(), walk, &mut files)?;
+        Ok(files)
     }
 
-    fn scan_workspace(&mut self, dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+    fn collect_supported_files(&self, dir: &str, ignore: &mut IgnoreRules, walk: &mut DirWalk, files: &mut Vec<PathBuf>) -> Result<(), ShadowError> {
         let path = Path::new(dir);
-        
+        let root = Path::new(&self.workspace_root).to_path_buf();
         if !path.exists() {
             return Ok(());
         }
 
-        for entry in std::fs::read_dir(path)? {
-            let entry = entry?;
-            let file_path = entry.path();
-            
+        if self.respect_gitignore {
+            ignore.load(&root, path);
+        }
+
+        for file_path in sorted_entries(path)? {
+            self.check_cancelled()?;
+
+            if ignore.is_ignored(&root, &file_path, file_path.is_dir()) || self.glob_ignored(&file_path) {
+                continue;
+            }
+
             if file_path.is_dir() {
                 let dir_name = file_path.file_name()
                     .and_then(|name| name.to_str())
                     .unwrap_or("");
-                
-                // Skip shadow directory and node_modules
-                if dir_name != ".shadow" && dir_name != "node_modules" && dir_name != ".git" {
-                    self.scan_workspace(&file_path.to_string_lossy())?;
+                if dir_name != ".shadow" && dir_name != "node_modules" && dir_name != ".git" && walk.enter(&file_path) {
+                    self.collect_supported_files(&file_path.to_string_lossy(), ignore, walk, files)?;
                 }
             } else if self.is_supported_file(&file_path) {
-                self.analyze_file(&file_path)?;
+                files.push(file_path);
             }
         }
 
         Ok(())
     }
 
+    fn glob_ignored(&self, path: &Path) -> bool {
+        if self.ignore_globs.is_empty() {
+            return false;
+        }
+        let relative = relative_path(&self.workspace_root, &path.to_string_lossy());
+        self.ignore_globs.iter().any(|glob| glob_match(glob, &relative))
+    }
+
     fn is_supported_file(&self, path: &Path) -> bool {
         if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
-            matches!(ext, "ts" | "js" | "tsx" | "jsx")
+            matches!(ext, "ts" | "js" | "tsx" | "jsx") || is_component_ext(ext) ||
+                (self.include_stylesheets && is_stylesheet_ext(ext))
         } else {
             false
         }
     }
 
-    fn analyze_file(&mut self, file_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    fn analyze_file(&self, file_path: &Path) -> Result<Vec<GraphNode>, ShadowError> {
         let content = std::fs::read_to_string(file_path)?;
         let relative_path = self.get_relative_path(file_path);
+
+        if file_path.extension().and_then(|ext| ext.to_str()).is_some_and(is_stylesheet_ext) {
+            return Ok(vec![GraphNode {
+                imports: self.extract_style_imports(&content, &relative_path),
+                file_path: relative_path,
+                exports: Vec::new(),
+                reexports: Vec::new(),
+                external_imports: Vec::new(),
+                imported_symbols: HashMap::new(),
+                ambient_module: None,
+            }]);
+        }
         
-        let imports = self.extract_imports(&content);
+        // Components are scanned through their script blocks only, so markup and
+        // template text never look like imports or exports
+        let content = if file_path.extension().and_then(|ext| ext.to_str()).is_some_and(is_component_ext) {
+            extract_script_blocks(&content)
+        } else {
+            content
+        };
+
+        let (imports, external_imports) = self.extract_imports(&content, &relative_path);
         let exports = self.extract_exports(&content);
+        let reexports = self.extract_reexports(&content, &relative_path);
+        let imported_symbols = self.extract_imported_symbols(&content, &relative_path);
 
-        let node = GraphNode {
-            file_path: relative_path.clone(),
+        // Each `declare module` block becomes a synthetic node that depends on its
+        // declaration file, so editing the block reaches consumers of the module
+        let mut nodes = Vec::new();
+        if relative_path.ends_with(".d.ts") {
+            for module_name in self.extract_ambient_modules(&content) {
+                nodes.push(GraphNode {
+                    file_path: format!("{}#{}", relative_path, module_name),
+                    imports: vec![relative_path.clone()],
+                    exports: Vec::new(),
+                    reexports: Vec::new(),
+                    external_imports: Vec::new(),
+                    imported_symbols: HashMap::new(),
+                    ambient_module: Some(module_name),
+                });
+            }
+        }
+
+        nodes.insert(0, GraphNode {
+            file_path: relative_path,
             imports,
             exports,
-        };
-
-        self.graph.nodes.insert(relative_path, node);
-        Ok(())
+            reexports,
+            external_imports,
+            imported_symbols,
+            ambient_module: None,
+        });
+        Ok(nodes)
     }
 
     fn get_relative_path(&self, file_path: &Path) -> String {
-        file_path.strip_prefix(&self.workspace_root)
-            .unwrap_or(file_path)
-            .to_string_lossy()
-            .replace('\\', "/")
+        relative_path(&self.workspace_root, &file_path.to_string_lossy())
+    }
+
+    // Graph key for a caller-supplied path, which may be absolute or use `\\`
+    fn graph_key(&self, file: &str) -> String {
+        relative_path(&self.workspace_root, file)
     }
 
-    fn extract_imports(&self, content: &str) -> Vec<String> {
+    // Returns (relative imports, bare package imports)
+    fn extract_imports(&self, content: &str, importer: &str) -> (Vec<String>, Vec<String>) {
         let mut imports = Vec::new();
+        let mut external_imports = Vec::new();
         
         for line in content.lines() {
             let trimmed = line.trim();
@@ -163,44 +1309,283 @@ impl DependencyGraphBuilder {
                 if let Some(from_pos) = trimmed.find(" from ") {
                     let module_part = &trimmed[from_pos + 6..];
                     let module_name = module_part.trim()
+                        .trim_matches(';')
                         .trim_matches('\'')
-                        .trim_matches('"')
-                        .trim_matches(';');
+                        .trim_matches('"');
                     
-                    // Only track relative imports
-                    if module_name.starts_with('.') {
-                        imports.push(self.resolve_import_path(module_name));
+                    if !module_name.is_empty() {
+                        self.push_import(importer, module_name, &mut imports, &mut external_imports);
                     }
                 }
             }
             
-            // Match require statements
-            if let Some(require_start) = trimmed.find("require(") {
-                let after_require = &trimmed[require_start + 8..];
-                if let Some(quote_end) = after_require.find(')') {
-                    let module_name = &after_require[..quote_end]
+            // Match require() calls and dynamic import() expressions
+            for call in ["require(", "import("] {
+                for (call_start, _) in trimmed.match_indices(call) {
+                    // Skip identifiers that merely end in the call name, e.g. `reimport(`
+                    let preceded_by_ident = trimmed[..call_start].chars().next_back()
+                        .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '$' || c == '.');
+                    if preceded_by_ident {
+                        continue;
+                    }
+
+                    if let Some(module_name) = string_literal_argument(&trimmed[call_start + call.len()..]) {
+                        self.push_import(importer, &module_name, &mut imports, &mut external_imports);
+                    }
+                }
+            }
+        }
+        
+        (imports, external_imports)
+    }
+
+    // Reads `compilerOptions.baseUrl` and `compilerOptions.paths`. A missing or
+    // unparsable tsconfig just means no aliases.
+    fn load_path_aliases(&mut self) {
+        self.path_aliases.clear();
+
+        let root = Path::new(&self.workspace_root);
+        let tsconfig_path = self.tsconfig_path.clone().unwrap_or_else(|| root.join("tsconfig.json"));
+        let Ok(content) = std::fs::read_to_string(&tsconfig_path) else {
+            return;
+        };
+
+        // tsconfig allows line comments, which serde_json does not
+        let content: String = content.lines()
+            .filter(|line| !line.trim_start().starts_with("//"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let Ok(config) = serde_json::from_str::<serde_json::Value>(&content) else {
+            return;
+        };
+
+        let options = &config["compilerOptions"];
+        let tsconfig_dir = tsconfig_path.parent()
+            .and_then(|dir| dir.strip_prefix(root).ok())
+            .map(|dir| normalize_path(&dir.to_string_lossy()))
+            .unwrap_or_default();
+        // join_relative resolves against a file's directory, so anchor on a file inside it
+        let anchor = |dir: &str| if dir.is_empty() { "tsconfig.json".to_string() } else { format!("{}/tsconfig.json", dir) };
+        let base_url = join_relative(&anchor(&tsconfig_dir), options["baseUrl"].as_str().unwrap_or("."));
+
+        if let Some(paths) = options["paths"].as_object() {
+            for (pattern, targets) in paths {
+                let targets = targets.as_array()
+                    .map(|targets| {
+                        targets.iter()
+                            .filter_map(|target| target.as_str())
+                            .map(|target| join_relative(&anchor(&base_url), target))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                self.path_aliases.push((pattern.clone(), targets));
+            }
+        }
+
+        self.path_aliases.sort_by_key(|(pattern, _)| std::cmp::Reverse(pattern.split('*').next().unwrap_or("").len()));
+    }
+
+    // Maps an aliased specifier to the first target that exists on disk
+    fn resolve_alias(&self, specifier: &str) -> Option<String> {
+        let root = Path::new(&self.workspace_root);
+
+        for (pattern, targets) in &self.path_aliases {
+            let captured = match pattern.split_once('*') {
+                Some((prefix, suffix)) => specifier.strip_prefix(prefix)
+                    .and_then(|rest| rest.strip_suffix(suffix)),
+                None => (specifier == pattern).then_some(""),
+            };
+            let Some(captured) = captured else {
+                continue;
+            };
+
+            for target in targets {
+                let candidate = target.replacen('*', captured, 1);
+                let exact = std::iter::once(candidate.clone());
+                if let Some(path) = exact.chain(import_candidates(&candidate)).find(|path| root.join(path).is_file()) {
+                    return Some(path);
+                }
+            }
+        }
+
+        None
+    }
+
+    // Relative and aliased specifiers become graph imports, anything else is a package
+    fn push_import(&self, importer: &str, module_name: &str, imports: &mut Vec<String>, external_imports: &mut Vec<String>) {
+        if module_name.starts_with('.') {
+            imports.push(self.resolve_import_path(importer, module_name));
+        } else if let Some(aliased) = self.resolve_alias(module_name) {
+            imports.push(aliased);
+        } else {
+            external_imports.push(module_name.to_string());
+        }
+    }
+
+    fn extract_ambient_modules(&self, content: &str) -> Vec<String> {
+        content.lines()
+            .filter_map(|line| line.trim().strip_prefix("declare module "))
+            .filter_map(|rest| quoted_strings(rest).into_iter().next())
+            .collect()
+    }
+
+    fn extract_style_imports(&self, content: &str, importer: &str) -> Vec<String> {
+        let mut imports = Vec::new();
+        let importer_ext = Path::new(importer).extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("scss");
+
+        for line in content.lines() {
+            let trimmed = line.trim();
+
+            let directive = ["@import", "@use", "@forward"].iter()
+                .find(|directive| trimmed.starts_with(*directive));
+            let Some(directive) = directive else {
+                continue;
+            };
+
+            // @import may list several modules; @use/@forward take a single one
+            let mut specs = quoted_strings(&trimmed[directive.len()..]);
+            if *directive != "@import" {
+                specs.truncate(1);
+            }
+
+            for spec in specs {
+                // Skip built-in modules, package imports and remote stylesheets
+                if spec.starts_with("sass:") || spec.starts_with('~') ||
+                   spec.contains("://") || spec.ends_with(".css") {
+                    continue;
+                }
+
+                let mut resolved = join_relative(importer, &spec);
+                if Path::new(&resolved).extension().is_none() {
+                    resolved = format!("{}.{}", resolved, importer_ext);
+                }
+                imports.push(resolved);
+            }
+        }
+
+        imports
+    }
+
+    fn extract_reexports(&self, content: &str, importer: &str) -> Vec<String> {
+        let mut reexports = Vec::new();
+
+        for line in content.lines() {
+            let trimmed = line.trim();
+
+            // Match `export { a } from '...'` and `export * from '...'`
+            if trimmed.starts_with("export ") {
+                if let Some(from_pos) = trimmed.find(" from ") {
+                    let module_name = trimmed[from_pos + 6..].trim()
+                        .trim_matches(';')
                         .trim_matches('\'')
                         .trim_matches('"');
-                    
+
                     if module_name.starts_with('.') {
-                        imports.push(self.resolve_import_path(module_name));
+                        reexports.push(self.resolve_import_path(importer, module_name));
                     }
                 }
             }
         }
-        
-        imports
+
+        reexports
+    }
+
+    fn extract_imported_symbols(&self, content: &str, importer: &str) -> HashMap<String, Vec<String>> {
+        let mut symbols: HashMap<String, Vec<String>> = HashMap::new();
+
+        for line in content.lines() {
+            let trimmed = line.trim();
+
+            let clause_start = if trimmed.starts_with("import ") {
+                "import ".len()
+            } else if trimmed.starts_with("export ") {
+                "export ".len()
+            } else {
+                continue;
+            };
+            let Some(from_pos) = trimmed.find(" from ") else {
+                continue;
+            };
+
+            let module_name = trimmed[from_pos + 6..].trim()
+                .trim_matches(';')
+                .trim_matches('\'')
+                .trim_matches('"');
+            if !module_name.starts_with('.') {
+                continue;
+            }
+
+            let clause = trimmed[clause_start..from_pos].trim();
+            let clause = clause.strip_prefix("type ").unwrap_or(clause);
+            let (outside_braces, braced) = match clause.split_once('{') {
+                Some((before, rest)) => (before, rest.split('}').next().unwrap_or("")),
+                None => (clause, ""),
+            };
+
+            let mut names = Vec::new();
+            let outside_braces = outside_braces.trim().trim_end_matches(',').trim();
+            if outside_braces.starts_with('*') {
+                names.push("*".to_string());
+            } else if !outside_braces.is_empty() {
+                names.push("default".to_string());
+            }
+
+            // `{ a, b as c }` uses the target's `a` and `b`
+            for (name, _) in parse_brace_list(braced) {
+                names.push(name);
+            }
+
+            symbols.entry(self.resolve_import_path(importer, module_name))
+                .or_default()
+                .extend(names);
+        }
+
+        symbols
     }
 
     fn extract_exports(&self, content: &str) -> Vec<String> {
         let mut exports = Vec::new();
+        // An `export {` list still waiting for its closing brace
+        let mut pending_list: Option<String> = None;
         
         for line in content.lines() {
             let trimmed = line.trim();
+
+            if let Some(list) = pending_list.as_mut() {
+                list.push(' ');
+                list.push_str(trimmed);
+                if trimmed.contains('}') {
+                    exports.extend(parse_brace_list(list.split('}').next().unwrap_or(""))
+                        .into_iter()
+                        .map(|(_, exported)| exported));
+                    pending_list = None;
+                }
+                continue;
+            }
             
             if trimmed.starts_with("export ") {
                 // Extract export names (simplified)
-                if trimmed.contains("function ") {
+                if trimmed.starts_with("export default") {
+                    exports.push("default".to_string());
+                } else if let Some(namespace) = trimmed.strip_prefix("export * as ") {
+                    // `export * as ns from '...'` exposes a single name; a bare
+                    // `export *` is followed through the re-export edges instead
+                    let name: String = namespace.chars().take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '$').collect();
+                    if !name.is_empty() {
+                        exports.push(name);
+                    }
+                } else if let Some(list) = trimmed.strip_prefix("export {").or_else(|| trimmed.strip_prefix("export type {")) {
+                    if !list.contains('}') {
+                        pending_list = Some(list.to_string());
+                        continue;
+                    }
+                    // `export { a, b as c }` exposes the external names `a` and `c`
+                    exports.extend(parse_brace_list(list.split('}').next().unwrap_or(""))
+                        .into_iter()
+                        .map(|(_, exported)| exported));
+                } else if trimmed.contains("function ") {
                     if let Some(func_name) = self.extract_function_name_from_export(trimmed) {
                         exports.push(func_name);
                     }
@@ -209,9 +1594,7 @@ impl DependencyGraphBuilder {
                         exports.push(class_name);
                     }
                 } else if trimmed.contains("const ") || trimmed.contains("let ") || trimmed.contains("var ") {
-                    if let Some(var_name) = self.extract_var_name_from_export(trimmed) {
-                        exports.push(var_name);
-                    }
+                    exports.extend(self.extract_var_names_from_export(trimmed));
                 }
             }
         }
@@ -219,17 +1602,10 @@ impl DependencyGraphBuilder {
         exports
     }
 
-    fn resolve_import_path(&self, import_path: &str) -> String {
-        // Simple path resolution - normalize relative paths
-        let mut resolved = import_path.to_string();
-        
-        // Add .ts extension if missing
-        if !resolved.ends_with(".ts") && !resolved.ends_with(".js") && 
-           !resolved.ends_with(".tsx") && !resolved.ends_with(".jsx") {
-            resolved.push_str(".ts");
-        }
-        
-        resolved
+    // Workspace-relative path of a relative import, normalized against the importing
+    // file's directory. Extensions and index files are resolved later against the known files.
+    fn resolve_import_path(&self, importer: &str, import_path: &str) -> String {
+        join_relative(importer, import_path)
     }
 
     fn extract_function_name_from_export(&self, line: &str) -> Option<String> {
@@ -254,51 +1630,151 @@ impl DependencyGraphBuilder {
         None
     }
 
-    fn extract_var_name_from_export(&self, line: &str) -> Option<String> {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        for (i, part) in parts.iter().enumerate() {
-            if matches!(*part, "const" | "let" | "var") && i + 1 < parts.len() {
-                let name = parts[i + 1].split('=').next()?.trim();
-                return Some(name.to_string());
+    // Every name bound by `export const x = 1, y = 2` or a destructuring
+    // `export const { a, b: c } = obj`
+    fn extract_var_names_from_export(&self, line: &str) -> Vec<String> {
+        let start = ["const ", "let ", "var "]
+            .iter()
+            .filter_map(|keyword| line.find(keyword).map(|index| index + keyword.len()))
+            .min();
+        let Some(start) = start else {
+            return Vec::new();
+        };
+        let declarators = &line[start..];
+
+        let mut names = Vec::new();
+        for declarator in split_top_level(declarators.trim_end_matches(';'), ',') {
+            let declarator = declarator.trim();
+            if declarator.starts_with(['{', '[']) {
+                let pattern = split_top_level(declarator, '=')[0];
+                names.extend(pattern_bindings(pattern));
+            } else {
+                let name: String = declarator.chars().take_while(|c| is_js_identifier_char(*c)).collect();
+                if !name.is_empty() {
+                    names.push(name);
+                }
             }
         }
-        None
+        names
     }
 
-    fn build_edges(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        for (file_path, node) in &self.graph.nodes.clone() {
-            let mut dependencies = Vec::new();
-            
-            for import in &node.imports {
-                // Find the actual file that matches this import
-                if let Some(target_file) = self.resolve_import_to_file(import) {
-                    dependencies.push(target_file);
-                }
-            }
-            
-            self.graph.edges.insert(file_path.clone(), dependencies);
+    fn build_edges(&mut self) -> Result<(), ShadowError> {
+        let ambient_modules = self.ambient_modules();
+
+        for file_path in self.graph.nodes.keys().cloned().collect::<Vec<_>>() {
+            self.check_cancelled()?;
+            self.build_edges_for(&file_path, &ambient_modules);
         }
         
         Ok(())
     }
 
+    fn ambient_modules(&self) -> HashMap<String, String> {
+        self.graph.nodes.values()
+            .filter_map(|node| node.ambient_module.clone().map(|name| (name, node.file_path.clone())))
+            .collect()
+    }
+
+    fn build_edges_for(&mut self, file_path: &str, ambient_modules: &HashMap<String, String>) {
+        let Some(node) = self.graph.nodes.get(file_path) else {
+            return;
+        };
+        let mut dependencies = Vec::new();
+        
+        for import in &node.imports {
+            // Find the actual file that matches this import
+            if let Some(target_file) = self.resolve_import_to_file(import) {
+                dependencies.push(target_file);
+            }
+        }
+        
+        for external in &node.external_imports {
+            if let Some(ambient_node) = ambient_modules.get(external.as_str()) {
+                dependencies.push(ambient_node.clone());
+            }
+        }
+
+        let reexport_targets = node.reexports.iter()
+            .filter_map(|reexport| self.resolve_import_to_file(reexport))
+            .collect();
+        
+        self.graph.edges.insert(file_path.to_string(), dependencies);
+        self.graph.reexport_edges.insert(file_path.to_string(), reexport_targets);
+    }
+
     fn resolve_import_to_file(&self, import_path: &str) -> Option<String> {
-        // Try to find matching file in nodes
-        for file_path in self.graph.nodes.keys() {
-            if file_path.ends_with(import_path) || 
-               file_path == import_path ||
-               file_path.replace(".ts", "") == import_path.replace(".ts", "") {
-                return Some(file_path.clone());
+        if self.graph.nodes.contains_key(import_path) {
+            return Some(import_path.to_string());
+        }
+
+        // Sass partials: `@import 'foo'` may refer to `_foo.scss`
+        let partial = match import_path.rsplit_once('/') {
+            Some((dir, file)) => format!("{}/_{}", dir, file),
+            None => format!("_{}", import_path),
+        };
+        if self.graph.nodes.contains_key(&partial) {
+            return Some(partial);
+        }
+
+        // Try each candidate in order: the file itself, then the directory's index file
+        import_candidates(import_path).into_iter()
+            .find(|candidate| self.graph.nodes.contains_key(candidate))
+    }
+
+    // Weakly connected components: edge direction is ignored
+    fn count_components(&self) -> usize {
+        let mut neighbours: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (from, targets) in &self.graph.edges {
+            for to in targets {
+                neighbours.entry(from.as_str()).or_default().push(to.as_str());
+                neighbours.entry(to.as_str()).or_default().push(from.as_str());
             }
         }
-        None
+
+        let mut visited = HashSet::new();
+        let mut components = 0;
+
+        for node in self.graph.nodes.keys() {
+            if !visited.insert(node.as_str()) {
+                continue;
+            }
+
+            components += 1;
+            let mut queue = VecDeque::from([node.as_str()]);
+            while let Some(current) = queue.pop_front() {
+                for next in neighbours.get(current).into_iter().flatten() {
+                    if visited.insert(next) {
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        components
+    }
+
+    // An index/main module that nothing else imports
+    fn is_entrypoint(&self, file: &str) -> bool {
+        let name = Path::new(file).file_stem().and_then(|stem| stem.to_str()).unwrap_or("");
+        matches!(name, "index" | "main") && self.find_dependents(file).is_none()
+    }
+
+    fn propagates_impact(&self, file: &str) -> bool {
+        !(self.tests_as_sinks && self.is_test_file(file))
     }
 
     fn find_dependents(&self, file: &str) -> Option<Vec<String>> {
         let mut dependents = Vec::new();
         
         for (dependent_file, dependencies) in &self.graph.edges {
-            if dependencies.contains(file) {
+            if dependencies.iter().any(|dependency| dependency == file) {
+                dependents.push(dependent_file.clone());
+            }
+        }
+
+        // A barrel re-exporting `file` relays its changes to the barrel's own importers
+        for (dependent_file, sources) in &self.graph.reexport_edges {
+            if sources.iter().any(|source| source == file) && !dependents.contains(dependent_file) {
                 dependents.push(dependent_file.clone());
             }
         }
@@ -310,13 +1786,261 @@ impl DependencyGraphBuilder {
         }
     }
 
-    fn calculate_risk_level(&self, changed_count: usize, impacted_count: usize) -> RiskLevel {
-        let total_impact = changed_count + impacted_count;
-        
-        match total_impact {
-            0..=2 => RiskLevel::Low,
-            3..=7 => RiskLevel::Medium,
-            _ => RiskLevel::High,
+    pub fn impact_context(&self, changed_files: &[String], impacted_files: &[String]) -> ImpactContext {
+        let changed_files: Vec<String> = changed_files.iter().map(|file| self.graph_key(file)).collect();
+        let impacted_files: Vec<String> = impacted_files.iter().map(|file| self.graph_key(file)).collect();
+        let fan_ins: Vec<usize> = changed_files.iter()
+            .map(|file| self.find_dependents(file).map_or(0, |dependents| dependents.len()))
+            .collect();
+        let max_fan_in = fan_ins.iter().copied().max().unwrap_or(0);
+
+        // A leaf file counts once, a file with many importers counts for much more
+        let risk_score = impacted_files.len() as f64 + fan_ins.iter()
+            .map(|&fan_in| 1.0 + fan_in as f64 * FAN_IN_WEIGHT)
+            .sum::<f64>();
+
+        let packages: HashSet<&str> = changed_files.iter()
+            .chain(&impacted_files)
+            .map(|file| top_level_dir(file))
+            .collect();
+
+        ImpactContext {
+            changed_count: changed_files.len(),
+            impacted_count: impacted_files.len(),
+            max_fan_in,
+            risk_score,
+            cross_package: packages.len() > 1,
+            breaking_change: false,
+        }
+    }
+
+    pub fn calculate_risk_level(&self, context: &ImpactContext) -> RiskLevel {
+        (self.risk_scorer)(context)
+    }
+}
+
+// Files directly under the workspace root all share the root's "" package
+fn top_level_dir(file: &str) -> &str {
+    file.split_once('/').map_or("", |(dir, _)| dir)
+}
+
+// Tarjan's strongly-connected components, keeping only components that form a cycle
+// (more than one file, or a file that imports itself). Members are sorted so the
+// result is deterministic.
+pub(crate) fn find_cycles_in(edges: &HashMap<String, Vec<String>>) -> Vec<Vec<String>> {
+    struct Tarjan<'a> {
+        edges: &'a HashMap<String, Vec<String>>,
+        index: HashMap<&'a str, usize>,
+        low_link: HashMap<&'a str, usize>,
+        stack: Vec<&'a str>,
+        on_stack: HashSet<&'a str>,
+        cycles: Vec<Vec<String>>,
+    }
+
+    impl<'a> Tarjan<'a> {
+        fn visit(&mut self, node: &'a str) {
+            let next_index = self.index.len();
+            self.index.insert(node, next_index);
+            self.low_link.insert(node, next_index);
+            self.stack.push(node);
+            self.on_stack.insert(node);
+
+            let mut targets: Vec<&'a String> = self.edges.get(node)
+                .map(|targets| targets.iter().collect())
+                .unwrap_or_default();
+            targets.sort();
+
+            for target in targets {
+                let target = target.as_str();
+                if !self.index.contains_key(target) {
+                    self.visit(target);
+                    let low = self.low_link[node].min(self.low_link[target]);
+                    self.low_link.insert(node, low);
+                } else if self.on_stack.contains(target) {
+                    let low = self.low_link[node].min(self.index[target]);
+                    self.low_link.insert(node, low);
+                }
+            }
+
+            if self.low_link[node] == self.index[node] {
+                let mut component = Vec::new();
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack.remove(member);
+                    component.push(member.to_string());
+                    if member == node {
+                        break;
+                    }
+                }
+
+                let self_loop = self.edges.get(node).is_some_and(|targets| targets.iter().any(|t| t == node));
+                if component.len() > 1 || self_loop {
+                    component.sort();
+                    self.cycles.push(component);
+                }
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        edges,
+        index: HashMap::new(),
+        low_link: HashMap::new(),
+        stack: Vec::new(),
+        on_stack: HashSet::new(),
+        cycles: Vec::new(),
+    };
+
+    let mut roots: Vec<&String> = edges.keys().collect();
+    roots.sort();
+    for root in roots {
+        if !tarjan.index.contains_key(root.as_str()) {
+            tarjan.visit(root);
+        }
+    }
+
+    tarjan.cycles.sort();
+    tarjan.cycles
+}
+
+// Parses the inside of `{ a, type b, c as d }` into (local, exported) name pairs
+fn parse_brace_list(list: &str) -> Vec<(String, String)> {
+    list.split(',')
+        .filter_map(|entry| {
+            let entry = entry.trim();
+            let entry = entry.strip_prefix("type ").unwrap_or(entry).trim();
+            if entry.is_empty() {
+                return None;
+            }
+
+            match entry.split_once(" as ") {
+                Some((local, exported)) => Some((local.trim().to_string(), exported.trim().to_string())),
+                None => Some((entry.to_string(), entry.to_string())),
+            }
+        })
+        .collect()
+}
+
+// Splits on `separator` outside brackets and string literals
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '\'' | '"' | '`' => quote = Some(c),
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                c if c == separator && depth == 0 => {
+                    parts.push(&text[start..i]);
+                    start = i + c.len_utf8();
+                }
+                _ => {}
+            },
         }
     }
-}
\ No newline at end of file
+    parts.push(&text[start..]);
+    parts
+}
+
+// Names bound by a destructuring pattern: `{ a, b: c, ...rest }` binds `a`, `c` and `rest`
+fn pattern_bindings(pattern: &str) -> Vec<String> {
+    let inner = pattern.trim();
+    let inner = inner.strip_prefix(['{', '[']).unwrap_or(inner);
+    let inner = inner.strip_suffix(['}', ']']).unwrap_or(inner);
+
+    split_top_level(inner, ',')
+        .into_iter()
+        .flat_map(|element| {
+            let element = element.trim().trim_start_matches("...");
+            // Defaults (`a = 1`) don't change the bound name; renames (`b: c`) bind the target
+            let element = split_top_level(element, '=')[0].trim();
+            let target = element.split_once(':').map_or(element, |(_, target)| target).trim();
+            if target.starts_with(['{', '[']) {
+                pattern_bindings(target)
+            } else {
+                let name: String = target.chars().take_while(|c| is_js_identifier_char(*c)).collect();
+                (!name.is_empty()).then_some(name).into_iter().collect()
+            }
+        })
+        .collect()
+}
+
+fn is_js_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+// How many files build_graph_with_progress scans between progress reports
+const PROGRESS_INTERVAL: usize = 50;
+
+const SCRIPT_EXTENSIONS: [&str; 4] = ["ts", "tsx", "js", "jsx"];
+
+// `./x` may be `./x.ts`, `./x.tsx`, `./x/index.ts`, `./x/index.tsx`, then the same for JS
+fn import_candidates(import_path: &str) -> Vec<String> {
+    let has_extension = Path::new(import_path).extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| SCRIPT_EXTENSIONS.contains(&ext) || is_component_ext(ext));
+    if has_extension {
+        return vec![import_path.to_string()];
+    }
+
+    // TypeScript sources win over plain JavaScript ones
+    let mut candidates = Vec::new();
+    for exts in [["ts", "tsx"], ["js", "jsx"]] {
+        candidates.extend(exts.iter().map(|ext| format!("{}.{}", import_path, ext)));
+        candidates.extend(exts.iter().map(|ext| format!("{}/index.{}", import_path, ext)));
+    }
+    candidates
+}
+
+// Graphviz quoted ID; the label defaults to the ID, so it shows the plain path
+fn dot_id(file: &str) -> String {
+    let escaped = file.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+fn is_stylesheet_ext(ext: &str) -> bool {
+    matches!(ext, "scss" | "less")
+}
+
+fn is_component_ext(ext: &str) -> bool {
+    matches!(ext, "vue" | "svelte")
+}
+
+// The string literal a call's argument list starts with. Template literals with
+// interpolation can't be resolved statically, so they yield None.
+fn string_literal_argument(arguments: &str) -> Option<String> {
+    let arguments = arguments.trim_start();
+    let quote = arguments.chars().next().filter(|c| matches!(c, '\'' | '"' | '`'))?;
+    let (literal, _) = arguments[1..].split_once(quote)?;
+
+    if literal.is_empty() || (quote == '`' && literal.contains("${")) {
+        return None;
+    }
+    Some(literal.to_string())
+}
+
+fn quoted_strings(text: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\'' || c == '"' {
+            let value: String = chars.by_ref().take_while(|&next| next != c).collect();
+            strings.push(value);
+        }
+    }
+
+    strings
+}
+
+// Joins a relative specifier onto the importing file's directory, collapsing `.` and `..`
+// Climbing above the workspace root keeps the `..`, so the path matches no file
+fn join_relative(importer: &str, spec: &str) -> String {
+    let dir = importer.rsplit_once('/').map_or(".", |(dir, _)| dir);
+    normalize_path(&format!("{}/{}", dir, spec))
+}
@@ -2,6 +2,35 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
+use chrono::Utc;
+
+use crate::glob::glob_match;
+use crate::ignore::IgnoreRules;
+use crate::paths;
+use crate::store::Store;
+use crate::walk::{sorted_entries, DirWalk, DEFAULT_MAX_SCAN_DEPTH};
+use crate::ShadowError;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum FileChangeKind {
+    Added,
+    #[default]
+    Modified,
+    Deleted,
+    // A deleted file paired with a similar added one; see FileDiff::renamed_from
+    Renamed,
+}
+
+impl FileChangeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FileChangeKind::Added => "added",
+            FileChangeKind::Modified => "modified",
+            FileChangeKind::Deleted => "deleted",
+            FileChangeKind::Renamed => "renamed",
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct FileDiff {
@@ -9,35 +38,459 @@ pub struct FileDiff {
     pub original_content: String,
     pub modified_content: String,
     pub timestamp: String,
+    // Tells a deleted file apart from one that was emptied
+    #[serde(default)]
+    pub kind: FileChangeKind,
+    // Path the file had in the baseline when `kind` is Renamed
+    #[serde(default)]
+    pub renamed_from: Option<String>,
+}
+
+impl FileDiff {
+    pub fn line_stats(&self) -> (usize, usize) {
+        let old_lines: Vec<&str> = self.original_content.lines().collect();
+        let new_lines: Vec<&str> = self.modified_content.lines().collect();
+
+        diff_lines(&old_lines, &new_lines).iter()
+            .fold((0, 0), |(added, removed), op| match op {
+                LineOp::Insert(_) => (added + 1, removed),
+                LineOp::Delete(_) => (added, removed + 1),
+                LineOp::Equal(_, _) => (added, removed),
+            })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum LineOp {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+// Line-level LCS diff. Indices refer to positions in `old` (Delete) and `new` (Insert).
+pub(crate) fn diff_lines(old: &[&str], new: &[&str]) -> Vec<LineOp> {
+    // Trim the common prefix and suffix so the LCS table only covers the changed region
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+    let (n, m) = (old_mid.len(), new_mid.len());
+
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old_mid[i] == new_mid[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops: Vec<LineOp> = (0..prefix).map(|i| LineOp::Equal(i, i)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old_mid[i] == new_mid[j] {
+            ops.push(LineOp::Equal(prefix + i, prefix + j));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(LineOp::Delete(prefix + i));
+            i += 1;
+        } else {
+            ops.push(LineOp::Insert(prefix + j));
+            j += 1;
+        }
+    }
+    ops.extend((0..suffix).map(|k| LineOp::Equal(prefix + n + k, prefix + m + k)));
+
+    ops
+}
+
+// Renders `@@` hunks in unified format with `context` unchanged lines around each change.
+// Lines keep their terminators so a missing final newline is reported the way git expects.
+pub(crate) fn unified_hunks(old_content: &str, new_content: &str, context: usize) -> String {
+    let old_lines: Vec<&str> = old_content.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new_content.split_inclusive('\n').collect();
+    let ops = diff_lines(&old_lines, &new_lines);
+
+    let changed: Vec<usize> = ops.iter()
+        .enumerate()
+        .filter(|(_, op)| !matches!(op, LineOp::Equal(_, _)))
+        .map(|(index, _)| index)
+        .collect();
+
+    let mut output = String::new();
+    let mut next = 0;
+
+    while next < changed.len() {
+        // Merge changes whose surrounding context would overlap into one hunk
+        let first = changed[next];
+        let mut last = first;
+        while next + 1 < changed.len() && changed[next + 1] - last <= 2 * context + 1 {
+            next += 1;
+            last = changed[next];
+        }
+        next += 1;
+
+        let start = first.saturating_sub(context);
+        let end = (last + context + 1).min(ops.len());
+
+        let consumed_old = ops[..start].iter().filter(|op| !matches!(op, LineOp::Insert(_))).count();
+        let consumed_new = ops[..start].iter().filter(|op| !matches!(op, LineOp::Delete(_))).count();
+        let old_count = ops[start..end].iter().filter(|op| !matches!(op, LineOp::Insert(_))).count();
+        let new_count = ops[start..end].iter().filter(|op| !matches!(op, LineOp::Delete(_))).count();
+
+        output.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            if old_count == 0 { consumed_old } else { consumed_old + 1 },
+            old_count,
+            if new_count == 0 { consumed_new } else { consumed_new + 1 },
+            new_count,
+        ));
+
+        for op in &ops[start..end] {
+            let (prefix, line) = match *op {
+                LineOp::Equal(i, _) => (' ', old_lines[i]),
+                LineOp::Delete(i) => ('-', old_lines[i]),
+                LineOp::Insert(j) => ('+', new_lines[j]),
+            };
+            output.push(prefix);
+            output.push_str(line);
+            if !line.ends_with('\n') {
+                output.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+
+    output
+}
+
+const DEFAULT_UNIFIED_CONTEXT: usize = 3;
+
+const DEFAULT_MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+// Deleted/added pairs at least this similar are captured as one rename
+const DEFAULT_RENAME_THRESHOLD: f64 = 0.8;
+
+// File contents read from disk, plus files left out as binary, oversized or unreadable
+#[derive(Default)]
+struct Scan {
+    files: HashMap<String, String>,
+    skipped: Vec<String>,
+    too_deep: Vec<String>,
 }
 
 pub struct DiffTracker {
     shadow_dir: String,
+    workspace_root: PathBuf,
     tracked_files: HashMap<String, String>,
     diffs: Vec<FileDiff>,
+    lazy: bool,
+    checkpoints: HashMap<String, HashMap<String, String>>,
+    respect_gitignore: bool,
+    tracked_extensions: Vec<String>,
+    max_file_bytes: u64,
+    skipped_files: Vec<String>,
+    ignore_whitespace: bool,
+    ignore_indentation: bool,
+    ignore_globs: Vec<String>,
+    rename_threshold: f64,
+    follow_symlinks: bool,
+    max_scan_depth: usize,
+    too_deep_dirs: Vec<String>,
 }
 
 impl DiffTracker {
     pub fn new(shadow_dir: &str) -> Self {
         DiffTracker {
             shadow_dir: shadow_dir.to_string(),
+            workspace_root: PathBuf::new(),
             tracked_files: HashMap::new(),
             diffs: Vec::new(),
+            lazy: false,
+            checkpoints: HashMap::new(),
+            respect_gitignore: true,
+            tracked_extensions: ["rs", "ts", "js", "json", "toml"].iter().map(|ext| ext.to_string()).collect(),
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            skipped_files: Vec::new(),
+            ignore_whitespace: false,
+            ignore_indentation: false,
+            ignore_globs: Vec::new(),
+            rename_threshold: DEFAULT_RENAME_THRESHOLD,
+            follow_symlinks: false,
+            max_scan_depth: DEFAULT_MAX_SCAN_DEPTH,
+            too_deep_dirs: Vec::new(),
+        }
+    }
+
+    // Captures baselines on first touch instead of scanning the whole workspace
+    pub fn new_lazy(shadow_dir: &str) -> Self {
+        DiffTracker {
+            lazy: true,
+            ..DiffTracker::new(shadow_dir)
         }
     }
 
-    pub fn start_tracking(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        // Initialize tracking by scanning current workspace
+    // Skip paths matched by .gitignore files while scanning (on by default)
+    pub fn set_respect_gitignore(&mut self, enabled: bool) {
+        self.respect_gitignore = enabled;
+    }
+
+    // Extensions are stored lowercase without a leading dot and matched case-insensitively
+    pub fn set_tracked_extensions(&mut self, exts: Vec<String>) -> Result<(), ShadowError> {
+        let exts: Vec<String> = exts.iter()
+            .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+            .filter(|ext| !ext.is_empty())
+            .collect();
+
+        if exts.is_empty() {
+            return Err(ShadowError::InvalidInput("At least one tracked extension is required".to_string()));
+        }
+
+        self.tracked_extensions = exts;
+        Ok(())
+    }
+
+    pub fn tracked_extensions(&self) -> &[String] {
+        &self.tracked_extensions
+    }
+
+    // Drop modifications that only change line endings or trailing whitespace
+    pub fn set_ignore_whitespace(&mut self, enabled: bool) {
+        self.ignore_whitespace = enabled;
+    }
+
+    // Together with ignore_whitespace, also drop modifications that only re-indent lines
+    pub fn set_ignore_indentation(&mut self, enabled: bool) {
+        self.ignore_indentation = enabled;
+    }
+
+    pub fn set_max_file_bytes(&mut self, max_file_bytes: u64) {
+        self.max_file_bytes = max_file_bytes;
+    }
+
+    // Share of lines a deleted and an added file must have in common to count as a
+    // rename; anything above 1.0 turns rename detection off
+    pub fn set_rename_threshold(&mut self, threshold: f64) {
+        self.rename_threshold = threshold;
+    }
+
+    // Symlinked directories are skipped while scanning unless this is set
+    pub fn set_follow_symlinks(&mut self, enabled: bool) {
+        self.follow_symlinks = enabled;
+    }
+
+    // Directories nested deeper than this below the workspace root are not scanned
+    pub fn set_max_scan_depth(&mut self, depth: usize) {
+        self.max_scan_depth = depth;
+    }
+
+    // Workspace-relative globs skipped while scanning, on top of .gitignore
+    pub fn set_ignore_globs(&mut self, globs: Vec<String>) {
+        self.ignore_globs = globs;
+    }
+
+    // Files that matched a tracked extension but were not read as text
+    pub fn skipped_files(&self) -> Vec<String> {
+        let mut paths: Vec<String> = self.skipped_files.iter()
+            .map(|path| self.relative_path(path))
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    // Directories left out of a scan for being deeper than the max scan depth
+    pub fn too_deep_dirs(&self) -> Vec<String> {
+        let mut paths: Vec<String> = self.too_deep_dirs.iter()
+            .map(|path| self.relative_path(path))
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    // The caller decides the workspace so it can't drift from the session's if the process chdirs
+    pub fn start_tracking(&mut self, workspace_root: &Path) -> Result<(), ShadowError> {
+        self.workspace_root = workspace_root.to_path_buf();
+        if self.lazy {
+            return Ok(());
+        }
+
+        // Initialize tracking by scanning the workspace
         self.scan_workspace()?;
         Ok(())
     }
 
-    pub fn stop_tracking(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn record_change(&mut self, path: &str, baseline: Option<&str>) -> Result<(), ShadowError> {
+        let full_path = self.full_path(path);
+        if self.tracked_files.contains_key(&full_path) {
+            return Ok(());
+        }
+
+        let content = match baseline {
+            Some(content) => content.to_string(),
+            None => match self.read_text(Path::new(&full_path)) {
+                Ok(Some(content)) => content,
+                Ok(None) => {
+                    self.skipped_files.push(full_path);
+                    return Ok(());
+                }
+                // A file created during the session has an empty baseline
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+                Err(e) => return Err(e.into()),
+            },
+        };
+
+        self.tracked_files.insert(full_path, content);
+        Ok(())
+    }
+
+    pub fn stop_tracking(&mut self) -> Result<(), ShadowError> {
         self.tracked_files.clear();
+        self.checkpoints.clear();
+        self.skipped_files.clear();
+        self.too_deep_dirs.clear();
+        Ok(())
+    }
+
+    // Compares every tracked baseline with what is on disk now. Files created during the
+    // session have an empty original; deleted files have an empty modified content.
+    pub fn capture_changes(&mut self) -> Result<(), ShadowError> {
+        let scan = self.current_contents()?;
+        let mut diffs = diff_scan(&self.tracked_files, &scan);
+        diffs.retain(|diff| !self.is_whitespace_only(diff));
+        let mut diffs = pair_renames(diffs, self.rename_threshold);
+
+        // A file untouched since the last capture keeps the time it was first seen changed
+        for diff in &mut diffs {
+            let previous = self.diffs.iter().find(|previous| {
+                previous.path == diff.path && previous.modified_content == diff.modified_content
+            });
+            if let Some(previous) = previous {
+                diff.timestamp = previous.timestamp.clone();
+            }
+        }
+        self.diffs = diffs;
+        self.skipped_files.extend(scan.skipped);
+        self.too_deep_dirs.extend(scan.too_deep);
+        Ok(())
+    }
+
+    pub fn checkpoint(&mut self, name: &str) -> Result<(), ShadowError> {
+        if self.checkpoints.contains_key(name) {
+            return Err(ShadowError::AlreadyExists(format!("Checkpoint '{}' already exists", name)));
+        }
+
+        let scan = self.current_contents()?;
+        self.checkpoints.insert(name.to_string(), scan.files);
+        Ok(())
+    }
+
+    pub fn diff_against(&self, name: &str) -> Result<Vec<FileDiff>, ShadowError> {
+        let baseline = self.checkpoints.get(name)
+            .ok_or_else(|| ShadowError::NotFound(format!("Checkpoint '{}' does not exist", name)))?;
+
+        let mut diffs = diff_scan(baseline, &self.current_contents()?);
+        diffs.retain(|diff| !self.is_whitespace_only(diff));
+        Ok(diffs)
+    }
+
+    // Diffs two directory trees, such as stored pre/post snapshots, pairing files by
+    // their path relative to each root
+    pub fn diff_directories(&self, old_root: &Path, new_root: &Path) -> Result<Vec<FileDiff>, ShadowError> {
+        let old_scan = self.scan_tree(old_root)?;
+        let new_scan = self.scan_tree(new_root)?;
+
+        let mut diffs = diff_contents(&old_scan.files, &new_scan.files);
+        diffs.retain(|diff| {
+            !old_scan.skipped.contains(&diff.path)
+                && !new_scan.skipped.contains(&diff.path)
+                && !self.is_whitespace_only(diff)
+        });
+        Ok(diffs)
+    }
+
+    // Copies the current contents of every tracked file to <shadow_dir>/snapshots/<name>/
+    pub fn create_snapshot(&self, name: &str) -> Result<(), ShadowError> {
+        let snapshot_dir = self.snapshot_dir(name)?;
+        if snapshot_dir.exists() {
+            return Err(ShadowError::AlreadyExists(format!("Snapshot '{}' already exists", name)));
+        }
+
+        for (path, content) in self.current_contents()?.files {
+            let target = snapshot_dir.join(self.relative_path(&path));
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(target, content)?;
+        }
+        fs::create_dir_all(&snapshot_dir)?;
         Ok(())
     }
 
-    pub fn clear_diffs(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    // Writes the snapshot's files back into the workspace. Files not in the snapshot are
+    // left alone. Returns the workspace-relative paths whose contents were replaced.
+    pub fn restore_snapshot(&self, name: &str) -> Result<Vec<String>, ShadowError> {
+        let snapshot_dir = self.snapshot_dir(name)?;
+        if !snapshot_dir.is_dir() {
+            return Err(ShadowError::NotFound(format!("Snapshot '{}' does not exist", name)));
+        }
+
+        let mut snapshot_files = Vec::new();
+        collect_files(&snapshot_dir, &mut snapshot_files)?;
+
+        let mut overwritten = Vec::new();
+        for file in snapshot_files {
+            let relative = file.strip_prefix(&snapshot_dir).map_err(|e| ShadowError::InvalidInput(e.to_string()))?;
+            let target = self.workspace_root.join(relative);
+            let content = fs::read(&file)?;
+
+            if fs::read(&target).ok().as_deref() == Some(content.as_slice()) {
+                continue;
+            }
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&target, content)?;
+            overwritten.push(paths::normalize_path(&relative.to_string_lossy()));
+        }
+
+        overwritten.sort();
+        Ok(overwritten)
+    }
+
+    pub fn list_snapshots(&self) -> Result<Vec<String>, ShadowError> {
+        let snapshots_dir = Path::new(&self.shadow_dir).join("snapshots");
+        if !snapshots_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut names = Vec::new();
+        for entry in fs::read_dir(snapshots_dir)? {
+            let entry = entry?;
+            if entry.path().is_dir() {
+                names.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    // Names become directory names, so they may not contain separators or be `.`/`..`
+    fn snapshot_dir(&self, name: &str) -> Result<PathBuf, ShadowError> {
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            return Err(ShadowError::InvalidInput(format!("Invalid snapshot name '{}'", name)));
+        }
+        Ok(Path::new(&self.shadow_dir).join("snapshots").join(name))
+    }
+
+    pub fn clear_diffs(&mut self) -> Result<(), ShadowError> {
         self.diffs.clear();
         
         // Clear diffs directory
@@ -50,44 +503,449 @@ impl DiffTracker {
         Ok(())
     }
 
-    pub fn get_diff_count(&self) -> Result<u32, Box<dyn std::error::Error>> {
+    // Writes every captured diff under `diff/<session_id>/<relative path>`
+    pub fn persist_diffs(&self, db: &Store, session_id: &str) -> Result<(), ShadowError> {
+        for diff in &self.diffs {
+            let key = format!("diff/{}/{}", session_id, self.relative_path(&diff.path));
+            db.put(key, serde_json::to_vec(diff)?)?;
+        }
+        Ok(())
+    }
+
+    pub fn load_diffs(db: &Store, session_id: &str) -> Result<Vec<FileDiff>, ShadowError> {
+        let prefix = format!("diff/{}/", session_id);
+        let mut diffs = Vec::new();
+
+        for (_, value) in db.scan_prefix(prefix.as_bytes())? {
+            diffs.push(serde_json::from_slice(&value)?);
+        }
+
+        Ok(diffs)
+    }
+
+    pub fn get_diff_count(&self) -> Result<u32, ShadowError> {
         Ok(self.diffs.len() as u32)
     }
 
-    fn scan_workspace(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let current_dir = std::env::current_dir()?;
-        self.scan_directory(&current_dir)?;
+    pub fn diffs(&self) -> &[FileDiff] {
+        &self.diffs
+    }
+
+    // The captured diff for a workspace-relative or absolute path
+    pub fn file_diff(&self, path: &str) -> Option<&FileDiff> {
+        let path = self.relative_path(path);
+        self.diffs.iter().find(|diff| self.relative_path(&diff.path) == path)
+    }
+
+    // A renamed file is listed under both paths, since importers of the old one break too
+    pub fn changed_paths(&self) -> Vec<String> {
+        let mut paths: Vec<String> = self.diffs.iter()
+            .flat_map(|diff| std::iter::once(&diff.path).chain(&diff.renamed_from))
+            .map(|path| self.relative_path(path))
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    // Total (added, removed) lines across all captured diffs
+    pub fn line_totals(&self) -> (usize, usize) {
+        self.diffs.iter()
+            .map(FileDiff::line_stats)
+            .fold((0, 0), |(added, removed), (a, r)| (added + a, removed + r))
+    }
+
+    // (relative path, original, modified) for each captured diff, ready for AST diffing
+    // A renamed file is compared under its new path, old content against new
+    pub fn file_changes(&self) -> Vec<(String, String, String)> {
+        self.diffs.iter()
+            .map(|diff| (self.relative_path(&diff.path), diff.original_content.clone(), diff.modified_content.clone()))
+            .collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("path,timestamp,lines_added,lines_removed,kind\n");
+
+        for diff in &self.diffs {
+            let (added, removed) = diff.line_stats();
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                csv_field(&self.relative_path(&diff.path)),
+                csv_field(&diff.timestamp),
+                added,
+                removed,
+                diff.kind.as_str(),
+            ));
+        }
+
+        csv
+    }
+
+    // Every captured diff as one patch that `git apply` takes against the baseline tree
+    pub fn export_patch(&self) -> String {
+        let mut diffs: Vec<&FileDiff> = self.diffs.iter().collect();
+        diffs.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let mut patch = String::new();
+        for diff in diffs {
+            let hunks = unified_hunks(&diff.original_content, &diff.modified_content, DEFAULT_UNIFIED_CONTEXT);
+            let path = self.relative_path(&diff.path);
+
+            if let (FileChangeKind::Renamed, Some(from)) = (diff.kind, &diff.renamed_from) {
+                let from = self.relative_path(from);
+                let similarity = file_similarity(&diff.original_content, &diff.modified_content);
+                patch.push_str(&format!("diff --git a/{} b/{}\n", from, path));
+                patch.push_str(&format!("similarity index {}%\n", (similarity * 100.0).round() as u32));
+                patch.push_str(&format!("rename from {}\nrename to {}\n", from, path));
+                if !hunks.is_empty() {
+                    patch.push_str(&format!("--- a/{}\n+++ b/{}\n", from, path));
+                    patch.push_str(&hunks);
+                }
+                continue;
+            }
+
+            if hunks.is_empty() {
+                continue;
+            }
+
+            patch.push_str(&format!("diff --git a/{} b/{}\n", path, path));
+            match diff.kind {
+                FileChangeKind::Added => {
+                    patch.push_str("new file mode 100644\n");
+                    patch.push_str(&format!("--- /dev/null\n+++ b/{}\n", path));
+                }
+                FileChangeKind::Deleted => {
+                    patch.push_str("deleted file mode 100644\n");
+                    patch.push_str(&format!("--- a/{}\n+++ /dev/null\n", path));
+                }
+                FileChangeKind::Modified | FileChangeKind::Renamed => {
+                    patch.push_str(&format!("--- a/{}\n+++ b/{}\n", path, path));
+                }
+            }
+            patch.push_str(&hunks);
+        }
+
+        patch
+    }
+
+    // The name export_patch had before; kept so existing callers keep building
+    pub fn to_git_patch(&self) -> String {
+        self.export_patch()
+    }
+
+    pub fn compute_unified_diff(&self, path: &str) -> Option<String> {
+        self.compute_unified_diff_with_context(path, DEFAULT_UNIFIED_CONTEXT)
+    }
+
+    // Diffs a tracked file's baseline against what is on disk now. Returns None for
+    // untracked files and an empty string when nothing changed.
+    pub fn compute_unified_diff_with_context(&self, path: &str, context: usize) -> Option<String> {
+        let full_path = self.full_path(path);
+        let original = self.tracked_files.get(&full_path)?;
+        let current = fs::read_to_string(&full_path).unwrap_or_default();
+
+        let hunks = unified_hunks(original, &current, context);
+        if hunks.is_empty() {
+            return Some(String::new());
+        }
+
+        let relative = self.relative_path(&full_path);
+        Some(format!("--- a/{}\n+++ b/{}\n{}", relative, relative, hunks))
+    }
+
+    pub fn activity_timeline(&self) -> Vec<(String, String)> {
+        let mut timeline: Vec<(String, String)> = self.diffs.iter()
+            .map(|diff| (diff.timestamp.clone(), diff.path.clone()))
+            .collect();
+        timeline.sort();
+        timeline
+    }
+
+    fn scan_workspace(&mut self) -> Result<(), ShadowError> {
+        let mut scan = Scan::default();
+        self.scan_root(&self.workspace_root, &mut scan)?;
+        self.tracked_files = scan.files;
+        self.skipped_files = scan.skipped;
+        self.too_deep_dirs = scan.too_deep;
         Ok(())
     }
 
-    fn scan_directory(&mut self, dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    // Reads what is on disk now: the whole workspace, or only touched files in lazy mode
+    fn current_contents(&self) -> Result<Scan, ShadowError> {
+        let mut scan = Scan::default();
+
+        if self.lazy {
+            for path in self.tracked_files.keys() {
+                match self.read_text(Path::new(path)) {
+                    Ok(Some(content)) => {
+                        scan.files.insert(path.clone(), content);
+                    }
+                    Ok(None) => scan.skipped.push(path.clone()),
+                    // Left out of the scan, a file that is gone shows up as deleted
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        } else {
+            self.scan_root(&self.workspace_root, &mut scan)?;
+        }
+
+        Ok(scan)
+    }
+
+    fn is_whitespace_only(&self, diff: &FileDiff) -> bool {
+        if !self.ignore_whitespace || diff.kind != FileChangeKind::Modified {
+            return false;
+        }
+
+        let normalize = |content: &str| -> Vec<String> {
+            content.lines()
+                .map(|line| {
+                    let line = line.trim_end();
+                    if self.ignore_indentation { line.trim_start() } else { line }.to_string()
+                })
+                .collect()
+        };
+        normalize(&diff.original_content) == normalize(&diff.modified_content)
+    }
+
+    // Returns None for content that shouldn't be diffed as text: files over the size
+    // limit, files containing a NUL byte and files that aren't valid UTF-8
+    fn read_text(&self, path: &Path) -> std::io::Result<Option<String>> {
+        if fs::metadata(path)?.len() > self.max_file_bytes {
+            return Ok(None);
+        }
+
+        let bytes = fs::read(path)?;
+        if bytes.contains(&0) {
+            return Ok(None);
+        }
+
+        Ok(String::from_utf8(bytes).ok())
+    }
+
+    fn relative_path(&self, path: &str) -> String {
+        paths::relative_path(&self.workspace_root.to_string_lossy(), path)
+    }
+
+    // A caller-supplied path as the scan spells it in tracked_files, whatever separators
+    // the caller used
+    fn full_path(&self, path: &str) -> String {
+        let relative = self.relative_path(path);
+        if Path::new(&relative).is_absolute() {
+            return relative;
+        }
+        let mut full_path = self.workspace_root.clone();
+        full_path.extend(relative.split('/').filter(|segment| !segment.is_empty()));
+        full_path.to_string_lossy().to_string()
+    }
+
+    // Tracked files under `root`, keyed by their path relative to it
+    fn scan_tree(&self, root: &Path) -> Result<Scan, ShadowError> {
+        if !root.is_dir() {
+            return Err(ShadowError::InvalidInput(format!("{} is not a directory", root.display())));
+        }
+
+        let mut scan = Scan::default();
+        self.scan_root(root, &mut scan)?;
+
+        let root = root.to_string_lossy();
+        let relative = |path: &str| paths::relative_path(&root, path);
+        Ok(Scan {
+            files: scan.files.into_iter().map(|(path, content)| (relative(&path), content)).collect(),
+            skipped: scan.skipped.iter().map(|path| relative(path)).collect(),
+            too_deep: scan.too_deep.iter().map(|path| relative(path)).collect(),
+        })
+    }
+
+    fn scan_root(&self, root: &Path, scan: &mut Scan) -> Result<(), ShadowError> {
+        let mut walk = DirWalk::new(root, self.follow_symlinks, self.max_scan_depth);
+        self.scan_directory(root, root, &mut IgnoreRules::default(), &mut walk, scan)?;
+        scan.too_deep.extend(walk.too_deep().iter().map(|dir| dir.to_string_lossy().to_string()));
+        Ok(())
+    }
+
+    fn scan_directory(&self, root: &Path, dir: &Path, ignore: &mut IgnoreRules, walk: &mut DirWalk, scan: &mut Scan) -> Result<(), ShadowError> {
         if dir.file_name().map_or(false, |name| name == ".shadow") {
             return Ok(());
         }
 
-        for entry in fs::read_dir(dir)? {
-            let entry = entry?;
-            let path = entry.path();
+        if self.respect_gitignore {
+            ignore.load(root, dir);
+        }
+
+        for path in sorted_entries(dir)? {
+            if ignore.is_ignored(root, &path, path.is_dir()) || self.glob_ignored(root, &path) {
+                continue;
+            }
             
             if path.is_dir() {
-                self.scan_directory(&path)?;
+                if walk.enter(&path) {
+                    self.scan_directory(root, &path, ignore, walk, scan)?;
+                }
             } else if self.should_track_file(&path) {
-                let content = fs::read_to_string(&path).unwrap_or_default();
-                self.tracked_files.insert(
-                    path.to_string_lossy().to_string(),
-                    content
-                );
+                let file_path = path.to_string_lossy().to_string();
+                match self.read_text(&path) {
+                    Ok(Some(content)) => {
+                        scan.files.insert(file_path, content);
+                    }
+                    _ => scan.skipped.push(file_path),
+                }
             }
         }
         
         Ok(())
     }
 
+    fn glob_ignored(&self, root: &Path, path: &Path) -> bool {
+        if self.ignore_globs.is_empty() {
+            return false;
+        }
+        let relative = paths::relative_path(&root.to_string_lossy(), &path.to_string_lossy());
+        self.ignore_globs.iter().any(|glob| glob_match(glob, &relative))
+    }
+
     fn should_track_file(&self, path: &Path) -> bool {
-        if let Some(ext) = path.extension() {
-            matches!(ext.to_str(), Some("rs") | Some("ts") | Some("js") | Some("json") | Some("toml"))
+        if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+            let ext = ext.to_lowercase();
+            self.tracked_extensions.contains(&ext)
         } else {
             false
         }
     }
-}
\ No newline at end of file
+}
+
+// Pairs files by path and emits a FileDiff for every one whose content differs.
+// Files missing on one side are compared against empty content.
+fn diff_contents(baseline: &HashMap<String, String>, current: &HashMap<String, String>) -> Vec<FileDiff> {
+    let mut paths: Vec<&String> = baseline.keys().chain(current.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    let timestamp = Utc::now().to_rfc3339();
+    paths.into_iter()
+        .filter_map(|path| {
+            let original = baseline.get(path).map(String::as_str).unwrap_or("");
+            let modified = current.get(path).map(String::as_str).unwrap_or("");
+
+            // An empty baseline also covers files a lazy tracker saw before they existed
+            let kind = if !current.contains_key(path) {
+                FileChangeKind::Deleted
+            } else if original.is_empty() {
+                FileChangeKind::Added
+            } else {
+                FileChangeKind::Modified
+            };
+
+            (original != modified).then(|| FileDiff {
+                path: path.clone(),
+                original_content: original.to_string(),
+                modified_content: modified.to_string(),
+                timestamp: timestamp.clone(),
+                kind,
+                renamed_from: None,
+            })
+        })
+        .collect()
+}
+
+// Share of lines two versions have in common: twice the matched lines over the total
+fn file_similarity(old_content: &str, new_content: &str) -> f64 {
+    let old_lines: Vec<&str> = old_content.lines().collect();
+    let new_lines: Vec<&str> = new_content.lines().collect();
+    let total = old_lines.len() + new_lines.len();
+    if total == 0 {
+        return 1.0;
+    }
+
+    let matched = diff_lines(&old_lines, &new_lines).iter()
+        .filter(|op| matches!(op, LineOp::Equal(_, _)))
+        .count();
+    2.0 * matched as f64 / total as f64
+}
+
+// Merges each deleted file with the most similar added one into a Renamed diff kept at the
+// added path. Pairs are taken most similar first, then by path, so the result is stable.
+fn pair_renames(diffs: Vec<FileDiff>, threshold: f64) -> Vec<FileDiff> {
+    let deleted: Vec<usize> = (0..diffs.len()).filter(|&i| diffs[i].kind == FileChangeKind::Deleted).collect();
+    let added: Vec<usize> = (0..diffs.len()).filter(|&i| diffs[i].kind == FileChangeKind::Added).collect();
+
+    let mut candidates = Vec::new();
+    for &i in &deleted {
+        let old_lines = diffs[i].original_content.lines().count();
+        for &j in &added {
+            // Line counts alone bound the similarity, which skips most hopeless pairs cheaply
+            let new_lines = diffs[j].modified_content.lines().count();
+            let bound = 2.0 * old_lines.min(new_lines) as f64 / (old_lines + new_lines).max(1) as f64;
+            if bound < threshold {
+                continue;
+            }
+
+            let similarity = file_similarity(&diffs[i].original_content, &diffs[j].modified_content);
+            if similarity >= threshold {
+                candidates.push((similarity, i, j));
+            }
+        }
+    }
+
+    candidates.sort_by(|a, b| {
+        b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| diffs[a.1].path.cmp(&diffs[b.1].path))
+            .then_with(|| diffs[a.2].path.cmp(&diffs[b.2].path))
+    });
+
+    // added index -> deleted index
+    let mut renames: HashMap<usize, usize> = HashMap::new();
+    let mut paired_deleted = std::collections::HashSet::new();
+    for (_, i, j) in candidates {
+        if !paired_deleted.contains(&i) && !renames.contains_key(&j) {
+            renames.insert(j, i);
+            paired_deleted.insert(i);
+        }
+    }
+
+    let originals: HashMap<usize, (String, String)> = renames.values()
+        .map(|&i| (i, (diffs[i].path.clone(), diffs[i].original_content.clone())))
+        .collect();
+
+    diffs.into_iter()
+        .enumerate()
+        .filter(|(index, _)| !paired_deleted.contains(index))
+        .map(|(index, mut diff)| {
+            if let Some((from, original)) = renames.get(&index).and_then(|i| originals.get(i)) {
+                diff.kind = FileChangeKind::Renamed;
+                diff.renamed_from = Some(from.clone());
+                diff.original_content = original.clone();
+            }
+            diff
+        })
+        .collect()
+}
+
+// Like diff_contents, but a file that is now skipped isn't reported as deleted
+fn diff_scan(baseline: &HashMap<String, String>, scan: &Scan) -> Vec<FileDiff> {
+    diff_contents(baseline, &scan.files).into_iter()
+        .filter(|diff| !scan.skipped.contains(&diff.path))
+        .collect()
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Quotes a CSV field per RFC 4180 when it contains a delimiter, quote or line break
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
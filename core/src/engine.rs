@@ -1,15 +1,121 @@
-use crate::{Session, SessionStatus, DiffTracker, AstDiffEngine, AstDiff, DependencyGraphBuilder, ImpactAnalysis};
-use rocksdb::{DB, Options};
+use crate::{normalize_path, BoxedSessionListener, SemverBump, Session, SessionListener, SessionComparison, SessionStatus, SessionSummary, DiffStats, DiffTracker, FileDiff, AstDiffEngine, AstDiff, AstNode, DependencyGraph, DependencyGraphBuilder, Coupling, GlobImpactAnalysis, GraphMetrics, GraphNode, ImpactAnalysis, ImportMismatch, RiskContribution, RiskPreset, RiskThresholds, ShadowError, Store};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::panic::AssertUnwindSafe;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use uuid::Uuid;
+
+const DEFAULT_PARSE_CACHE_CAPACITY: usize = 64;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum StorageBackend {
+    #[default]
+    RocksDb,
+    InMemory,
+}
+
+// Where an in-memory engine configured without a shadow_dir keeps its snapshots
+const DEFAULT_SHADOW_DIR: &str = ".shadow";
+
+// Everything an engine can be set up with at construction. Unset fields keep the defaults.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct EngineConfig {
+    // Opens (or creates) the store here right away; otherwise call initialize later
+    pub shadow_dir: Option<String>,
+    pub tracked_extensions: Option<Vec<String>>,
+    pub ignore_globs: Vec<String>,
+    pub max_file_bytes: Option<u64>,
+    pub risk_thresholds: Option<RiskThresholds>,
+    // Similarity at which a deleted and an added file are captured as a rename
+    pub rename_threshold: Option<f64>,
+    pub follow_symlinks: bool,
+    pub max_scan_depth: Option<usize>,
+    pub backend: StorageBackend,
+}
+
+// file path -> (content hash, parsed AST) for the "old" side of diffs. Once full, the
+// least recently used path makes room for a new one.
+#[derive(Default)]
+struct ParseCache {
+    entries: HashMap<String, (u64, AstNode)>,
+    // Least recently used first
+    order: VecDeque<String>,
+}
+
+impl ParseCache {
+    fn get(&mut self, file_path: &str, content_hash: u64) -> Option<AstNode> {
+        let ast = match self.entries.get(file_path) {
+            Some((cached_hash, ast)) if *cached_hash == content_hash => ast.clone(),
+            _ => return None,
+        };
+        self.touch(file_path);
+        Some(ast)
+    }
+
+    fn insert(&mut self, file_path: &str, content_hash: u64, ast: AstNode, capacity: usize) {
+        if self.entries.insert(file_path.to_string(), (content_hash, ast)).is_some() {
+            self.touch(file_path);
+        } else {
+            self.order.push_back(file_path.to_string());
+        }
+        self.shrink_to(capacity);
+    }
+
+    fn touch(&mut self, file_path: &str) {
+        if let Some(index) = self.order.iter().position(|path| path == file_path) {
+            if let Some(path) = self.order.remove(index) {
+                self.order.push_back(path);
+            }
+        }
+    }
+
+    fn shrink_to(&mut self, capacity: usize) {
+        while self.order.len() > capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.entries.remove(&evicted);
+            }
+        }
+    }
+}
+
+// What save_dependency_graph writes under the `dep_graph` key
+#[derive(Serialize, Deserialize)]
+struct StoredDependencyGraph {
+    workspace_root: String,
+    // None for a graph patched since its build, which is never reused
+    fingerprint: Option<u64>,
+    graph: DependencyGraph,
+}
 
 pub struct Engine {
-    db: Option<Arc<DB>>,
+    db: Option<Arc<Store>>,
     session: Option<Session>,
     diff_tracker: Option<DiffTracker>,
     shadow_dir: Option<String>,
     ast_engine: AstDiffEngine,
     dep_graph: Option<DependencyGraphBuilder>,
+    parse_cache: Mutex<ParseCache>,
+    parse_cache_capacity: usize,
+    risk_preset: RiskPreset,
+    risk_thresholds: Option<RiskThresholds>,
+    respect_gitignore: bool,
+    follow_symlinks: bool,
+    max_scan_depth: usize,
+    tsconfig_path: Option<String>,
+    // Distinguishes sessions started by this engine from ones found in a shared database
+    instance_id: String,
+    // Raised by cancel() or a cancel_handle() to stop an in-flight dependency graph build
+    cancel_flag: Arc<AtomicBool>,
+    listeners: Vec<BoxedSessionListener>,
+    // Diff tracker settings applied whenever a store is attached
+    config: EngineConfig,
 }
 
 impl Engine {
@@ -21,103 +127,281 @@ impl Engine {
             shadow_dir: None,
             ast_engine: AstDiffEngine::new(),
             dep_graph: None,
+            parse_cache: Mutex::new(ParseCache::default()),
+            parse_cache_capacity: DEFAULT_PARSE_CACHE_CAPACITY,
+            risk_preset: RiskPreset::Default,
+            risk_thresholds: None,
+            respect_gitignore: true,
+            follow_symlinks: false,
+            max_scan_depth: crate::walk::DEFAULT_MAX_SCAN_DEPTH,
+            tsconfig_path: None,
+            instance_id: Uuid::new_v4().to_string(),
+            cancel_flag: Arc::new(AtomicBool::new(false)),
+            listeners: Vec::new(),
+            config: EngineConfig::default(),
         }
     }
 
-    pub fn initialize(&mut self, shadow_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let db_path = Path::new(shadow_dir).join("session.db");
-        
-        let mut opts = Options::default();
-        opts.create_if_missing(true);
-        
-        let db = DB::open(&opts, db_path)?;
+    pub fn with_config(config: EngineConfig) -> Result<Self, ShadowError> {
+        let mut engine = Self::new();
+
+        if let Some(thresholds) = config.risk_thresholds {
+            engine.risk_thresholds = Some(RiskThresholds::new(thresholds.low_max, thresholds.medium_max)?);
+        }
+
+        // Validated here so attaching a store later can't fail on them
+        if let Some(exts) = &config.tracked_extensions {
+            DiffTracker::new("").set_tracked_extensions(exts.clone())?;
+        }
+
+        engine.follow_symlinks = config.follow_symlinks;
+        if let Some(max_scan_depth) = config.max_scan_depth {
+            engine.max_scan_depth = max_scan_depth;
+        }
+        let shadow_dir = config.shadow_dir.clone();
+        let backend = config.backend;
+        engine.config = config;
+
+        match (shadow_dir, backend) {
+            (Some(shadow_dir), StorageBackend::RocksDb) => engine.initialize(&shadow_dir)?,
+            (Some(shadow_dir), StorageBackend::InMemory) => engine.initialize_in_memory(&shadow_dir),
+            (None, StorageBackend::InMemory) => engine.initialize_in_memory(DEFAULT_SHADOW_DIR),
+            (None, StorageBackend::RocksDb) => {}
+        }
+
+        Ok(engine)
+    }
+
+    // Session state lives in a map instead of RocksDB; nothing is written to disk
+    pub fn new_in_memory() -> Self {
+        Self::with_store(Arc::new(Store::in_memory()))
+    }
+
+    // Several engines can share one store, like editors sharing a workspace
+    pub fn with_store(db: Arc<Store>) -> Self {
+        let mut engine = Self::new();
+        engine.db = Some(db);
+        engine
+    }
+
+    pub fn initialize(&mut self, shadow_dir: &str) -> Result<(), ShadowError> {
+        let db = Store::open(&Path::new(shadow_dir).join("session.db"))?;
+        self.attach_store(db, shadow_dir);
+        Ok(())
+    }
+
+    pub fn initialize_in_memory(&mut self, shadow_dir: &str) {
+        self.attach_store(Store::in_memory(), shadow_dir);
+    }
+
+    fn attach_store(&mut self, db: Store, shadow_dir: &str) {
         self.db = Some(Arc::new(db));
         self.shadow_dir = Some(shadow_dir.to_string());
-        self.diff_tracker = Some(DiffTracker::new(shadow_dir));
-        
-        Ok(())
+        let mut diff_tracker = DiffTracker::new(shadow_dir);
+        diff_tracker.set_respect_gitignore(self.respect_gitignore);
+        diff_tracker.set_follow_symlinks(self.follow_symlinks);
+        diff_tracker.set_max_scan_depth(self.max_scan_depth);
+        if let Some(exts) = &self.config.tracked_extensions {
+            // Already checked by with_config
+            let _ = diff_tracker.set_tracked_extensions(exts.clone());
+        }
+        if let Some(max_file_bytes) = self.config.max_file_bytes {
+            diff_tracker.set_max_file_bytes(max_file_bytes);
+        }
+        if let Some(threshold) = self.config.rename_threshold {
+            diff_tracker.set_rename_threshold(threshold);
+        }
+        diff_tracker.set_ignore_globs(self.config.ignore_globs.clone());
+        self.diff_tracker = Some(diff_tracker);
+    }
+
+    pub fn start_session(&mut self) -> Result<String, ShadowError> {
+        self.start_session_with_force(false)
     }
 
-    pub fn start_session(&mut self) -> Result<String, Box<dyn std::error::Error>> {
-        if self.session.is_some() {
-            return Err("Session already active".into());
+    // Without `force`, a session another instance left in the database blocks a new one
+    pub fn start_session_with_force(&mut self, force: bool) -> Result<String, ShadowError> {
+        self.start_new_session(Session::new(), force)
+    }
+
+    // Split out so tests can start a session in a workspace other than the current directory
+    pub(crate) fn start_new_session(&mut self, mut session: Session, force: bool) -> Result<String, ShadowError> {
+        if let Some(session) = &self.session {
+            return Err(ShadowError::SessionAlreadyActive { session_id: session.id.clone(), owner_pid: None });
         }
 
-        let session = Session::new();
+        if let Some(db) = &self.db {
+            if let Some(existing) = stored_session(db)? {
+                if !force {
+                    return Err(ShadowError::SessionAlreadyActive {
+                        session_id: existing.id,
+                        owner_pid: Some(existing.owner_pid),
+                    });
+                }
+            }
+        }
+
+        session.instance_id = self.instance_id.clone();
         let session_id = session.id.clone();
-        
-        // Store session in database
+        session.validate_workspace()?;
+
+        // Initialize diff tracking
+        if let Some(diff_tracker) = &mut self.diff_tracker {
+            diff_tracker.start_tracking(Path::new(&session.workspace_path))?;
+        }
+
+        // Only a session that actually started is stored, both as the active one and in
+        // the history; a failed start must not leave a session blocking the next one
         if let Some(db) = &self.db {
             let session_data = serde_json::to_string(&session)?;
             db.put("current_session", session_data.as_bytes())?;
+            db.put(format!("session/{}", session.id), session_data.as_bytes())?;
         }
 
+        self.notify_listeners(|listener| listener.on_start(&session));
         self.session = Some(session);
-        
-        // Initialize diff tracking
-        if let Some(diff_tracker) = &mut self.diff_tracker {
-            diff_tracker.start_tracking()?;
-        }
 
         Ok(session_id)
     }
 
-    pub fn stop_session(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn stop_session(&mut self) -> Result<(), ShadowError> {
         if self.session.is_none() {
-            return Err("No active session".into());
+            return Err(ShadowError::NoActiveSession);
+        }
+
+        if let Some(diff_tracker) = &mut self.diff_tracker {
+            diff_tracker.capture_changes()?;
+        }
+        if let Some(session) = &mut self.session {
+            session.end_time = Some(Utc::now());
         }
 
-        // Clear session from database
+        // Archive the changed files and their diffs so the session can be audited
+        // or compared later, then clear it from the database
         if let Some(db) = &self.db {
-            db.delete("current_session")?;
+            if let Some(session) = &self.session {
+                db.put(format!("session/{}", session.id), serde_json::to_string(session)?.as_bytes())?;
+            }
+            if let (Some(session), Some(diff_tracker)) = (&self.session, &self.diff_tracker) {
+                let changed_files = serde_json::to_string(&diff_tracker.changed_paths())?;
+                db.put(format!("session_changes/{}", session.id), changed_files.as_bytes())?;
+                diff_tracker.persist_diffs(db, &session.id)?;
+            }
+
+            // Leave the key alone if another instance has since taken it over
+            let owns_current = match (stored_session(db)?, &self.session) {
+                (Some(stored), Some(session)) => stored.id == session.id,
+                _ => false,
+            };
+            if owns_current {
+                db.delete("current_session")?;
+            }
         }
 
-        // Stop diff tracking and clear diffs
+        // Stop diff tracking and clear diffs, keeping them for the listeners
+        let mut diffs = Vec::new();
         if let Some(diff_tracker) = &mut self.diff_tracker {
+            diffs = diff_tracker.diffs().to_vec();
             diff_tracker.stop_tracking()?;
             diff_tracker.clear_diffs()?;
         }
 
-        self.session = None;
+        if let Some(session) = self.session.take() {
+            self.notify_listeners(|listener| listener.on_stop(&session, &diffs));
+        }
         Ok(())
     }
 
-    pub fn get_status(&self) -> Result<SessionStatus, Box<dyn std::error::Error>> {
-        if let Some(session) = &self.session {
-            Ok(SessionStatus {
+    pub fn register_listener(&mut self, listener: BoxedSessionListener) {
+        self.listeners.push(listener);
+    }
+
+    // Listeners run once the engine's own state is updated, and a panicking one is
+    // skipped, so a faulty listener can't leave a session half started or stopped
+    fn notify_listeners(&self, event: impl Fn(&dyn SessionListener)) {
+        for listener in &self.listeners {
+            let _ = std::panic::catch_unwind(AssertUnwindSafe(|| event(listener.as_ref())));
+        }
+    }
+
+    pub fn get_status(&self) -> Result<SessionStatus, ShadowError> {
+        match self.current_session()? {
+            Some(session) => Ok(SessionStatus {
                 is_active: true,
-                session_id: Some(session.id.clone()),
+                warning: (session.instance_id != self.instance_id).then(|| {
+                    format!("Session was started by another Shadow instance (process {})", session.owner_pid)
+                }),
+                session_id: Some(session.id),
                 start_time: Some(session.start_time.to_rfc3339()),
-            })
-        } else {
-            // Check database for persisted session
-            if let Some(db) = &self.db {
-                match db.get("current_session")? {
-                    Some(data) => {
-                        let session: Session = serde_json::from_slice(&data)?;
-                        Ok(SessionStatus {
-                            is_active: true,
-                            session_id: Some(session.id),
-                            start_time: Some(session.start_time.to_rfc3339()),
-                        })
-                    }
-                    None => Ok(SessionStatus {
-                        is_active: false,
-                        session_id: None,
-                        start_time: None,
-                    })
-                }
-            } else {
-                Ok(SessionStatus {
-                    is_active: false,
-                    session_id: None,
-                    start_time: None,
-                })
+                metadata: Some(session.metadata),
+            }),
+            None => Ok(SessionStatus {
+                is_active: false,
+                session_id: None,
+                start_time: None,
+                metadata: None,
+                warning: None,
+            }),
+        }
+    }
+
+    pub fn session_elapsed_seconds(&self) -> Result<Option<u64>, ShadowError> {
+        Ok(self.elapsed_session_time()?.map(|duration| duration.as_secs()))
+    }
+
+    pub fn session_duration(&self) -> Option<Duration> {
+        self.elapsed_session_time().ok().flatten()
+    }
+
+    // How long the active session has been running, at sub-second precision. A start
+    // time in the future (clock skew between processes) counts as zero.
+    pub(crate) fn elapsed_session_time(&self) -> Result<Option<Duration>, ShadowError> {
+        Ok(self.current_session()?.map(|session| {
+            (Utc::now() - session.start_time).to_std().unwrap_or(Duration::ZERO)
+        }))
+    }
+
+    pub fn set_session_metadata(&mut self, key: &str, value: &str) -> Result<(), ShadowError> {
+        let session = self.session.as_mut().ok_or(ShadowError::NoActiveSession)?;
+        session.metadata.insert(key.to_string(), value.to_string());
+
+        if let Some(db) = &self.db {
+            let session_data = serde_json::to_string(session)?;
+            // Like stop_session, leave the active key alone if another instance has taken it
+            if stored_session(db)?.is_some_and(|stored| stored.id == session.id) {
+                db.put("current_session", session_data.as_bytes())?;
             }
+            db.put(format!("session/{}", session.id), session_data.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    // Every recorded session, most recently started first
+    pub fn list_sessions(&self) -> Result<Vec<Session>, ShadowError> {
+        let db = self.db.as_ref().ok_or(ShadowError::NotInitialized)?;
+
+        let mut sessions: Vec<Session> = Vec::new();
+        for (_, value) in db.scan_prefix(b"session/")? {
+            sessions.push(serde_json::from_slice(&value)?);
+        }
+
+        sessions.sort_by(|a, b| b.start_time.cmp(&a.start_time).then_with(|| a.id.cmp(&b.id)));
+        Ok(sessions)
+    }
+
+    fn current_session(&self) -> Result<Option<Session>, ShadowError> {
+        if let Some(session) = &self.session {
+            return Ok(Some(session.clone()));
+        }
+
+        // Check database for persisted session
+        match &self.db {
+            Some(db) => stored_session(db),
+            None => Ok(None),
         }
     }
 
-    pub fn get_diff_count(&self) -> Result<u32, Box<dyn std::error::Error>> {
+    pub fn get_diff_count(&self) -> Result<u32, ShadowError> {
         if let Some(diff_tracker) = &self.diff_tracker {
             diff_tracker.get_diff_count()
         } else {
@@ -125,29 +409,672 @@ impl Engine {
         }
     }
 
-    pub fn compute_ast_diffs(&self, file_changes: &[(String, String, String)]) -> Result<Vec<AstDiff>, Box<dyn std::error::Error>> {
-        let mut diffs = Vec::new();
-        
-        for (file_path, old_content, new_content) in file_changes {
-            let diff = self.ast_engine.compute_diff(file_path, old_content, new_content)?;
-            diffs.push(diff);
+    // Refreshes the active session's diffs from disk without stopping it
+    pub fn capture_changes(&mut self) -> Result<(), ShadowError> {
+        if self.session.is_none() {
+            return Err(ShadowError::NoActiveSession);
+        }
+        match &mut self.diff_tracker {
+            Some(diff_tracker) => diff_tracker.capture_changes(),
+            None => Err(ShadowError::NotInitialized),
         }
-        
-        Ok(diffs)
     }
 
-    pub fn build_dependency_graph(&mut self, workspace_root: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let mut builder = DependencyGraphBuilder::new(workspace_root);
-        builder.build_graph()?;
+    pub fn get_changed_files(&self) -> Result<Vec<String>, ShadowError> {
+        if let Some(diff_tracker) = &self.diff_tracker {
+            Ok(diff_tracker.changed_paths())
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    // File, line and structural change counts for the captured diffs. Files no parser
+    // understands count toward the line totals only.
+    pub fn session_summary(&self) -> Result<SessionSummary, ShadowError> {
+        let diff_tracker = self.diff_tracker.as_ref().ok_or(ShadowError::NotInitialized)?;
+        let (lines_added, lines_removed) = diff_tracker.line_totals();
+        let parsed: Vec<(String, String, String)> = diff_tracker.file_changes().into_iter()
+            .filter(|(path, _, _)| self.ast_engine.has_parser(path))
+            .collect();
+        let ast_diffs = self.compute_ast_diffs(&parsed)?;
+
+        Ok(SessionSummary {
+            files_changed: diff_tracker.diffs().len(),
+            lines_added,
+            lines_removed,
+            ast_stats: DiffStats::from_ast_diffs(&ast_diffs),
+        })
+    }
+
+    // Diffs of the active session. Stopping a session clears them from memory, so with
+    // no active session the most recently stopped one is read back from the store.
+    pub fn get_all_diffs(&self) -> Result<Vec<FileDiff>, ShadowError> {
+        if let Some(diff_tracker) = &self.diff_tracker {
+            if self.session.is_some() || !diff_tracker.diffs().is_empty() {
+                return Ok(diff_tracker.diffs().to_vec());
+            }
+        }
+
+        match (&self.db, self.last_archived_session()?) {
+            (Some(db), Some(session)) => DiffTracker::load_diffs(db, &session.id),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    // The diff for a workspace-relative or absolute path, looked up like get_all_diffs
+    pub fn get_file_diff(&self, path: &str) -> Result<Option<FileDiff>, ShadowError> {
+        if let Some(diff_tracker) = &self.diff_tracker {
+            if let Some(diff) = diff_tracker.file_diff(path) {
+                return Ok(Some(diff.clone()));
+            }
+            if self.session.is_some() {
+                return Ok(None);
+            }
+        }
+
+        let (Some(db), Some(session)) = (&self.db, self.last_archived_session()?) else {
+            return Ok(None);
+        };
+        let relative_path = crate::paths::relative_path(&session.workspace_path, path);
+        match db.get(format!("diff/{}/{}", session.id, relative_path))? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn last_archived_session(&self) -> Result<Option<Session>, ShadowError> {
+        if self.db.is_none() {
+            return Ok(None);
+        }
+        Ok(self.list_sessions()?.into_iter().find(|session| session.end_time.is_some()))
+    }
+
+    pub fn checkpoint(&mut self, name: &str) -> Result<(), ShadowError> {
+        if self.session.is_none() {
+            return Err(ShadowError::NoActiveSession);
+        }
+
+        match &mut self.diff_tracker {
+            Some(diff_tracker) => diff_tracker.checkpoint(name),
+            None => Err(ShadowError::NotInitialized),
+        }
+    }
+
+    pub fn diff_directories(&self, old_root: &str, new_root: &str) -> Result<Vec<FileDiff>, ShadowError> {
+        match &self.diff_tracker {
+            Some(diff_tracker) => diff_tracker.diff_directories(Path::new(old_root), Path::new(new_root)),
+            None => Err(ShadowError::NotInitialized),
+        }
+    }
+
+    pub fn create_snapshot(&self, name: &str) -> Result<(), ShadowError> {
+        match &self.diff_tracker {
+            Some(diff_tracker) => diff_tracker.create_snapshot(name),
+            None => Err(ShadowError::NotInitialized),
+        }
+    }
+
+    pub fn restore_snapshot(&self, name: &str) -> Result<Vec<String>, ShadowError> {
+        match &self.diff_tracker {
+            Some(diff_tracker) => diff_tracker.restore_snapshot(name),
+            None => Err(ShadowError::NotInitialized),
+        }
+    }
+
+    pub fn list_snapshots(&self) -> Result<Vec<String>, ShadowError> {
+        match &self.diff_tracker {
+            Some(diff_tracker) => diff_tracker.list_snapshots(),
+            None => Err(ShadowError::NotInitialized),
+        }
+    }
+
+    pub fn diff_against(&self, name: &str) -> Result<Vec<FileDiff>, ShadowError> {
+        if self.session.is_none() {
+            return Err(ShadowError::NoActiveSession);
+        }
+
+        match &self.diff_tracker {
+            Some(diff_tracker) => diff_tracker.diff_against(name),
+            None => Err(ShadowError::NotInitialized),
+        }
+    }
+
+    pub fn activity_timeline(&self) -> Vec<(String, String)> {
+        if let Some(diff_tracker) = &self.diff_tracker {
+            diff_tracker.activity_timeline()
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub fn diffs_csv(&self) -> String {
+        if let Some(diff_tracker) = &self.diff_tracker {
+            diff_tracker.to_csv()
+        } else {
+            String::new()
+        }
+    }
+
+    pub fn session_patch(&self) -> String {
+        if let Some(diff_tracker) = &self.diff_tracker {
+            diff_tracker.export_patch()
+        } else {
+            String::new()
+        }
+    }
+
+    pub fn unified_diff(&self, path: &str, context: Option<usize>) -> Option<String> {
+        let diff_tracker = self.diff_tracker.as_ref()?;
+        match context {
+            Some(context) => diff_tracker.compute_unified_diff_with_context(path, context),
+            None => diff_tracker.compute_unified_diff(path),
+        }
+    }
+
+    pub fn set_diff_context_lines(&mut self, context_lines: usize) {
+        self.ast_engine.set_context_lines(context_lines);
+    }
+
+    pub fn set_generic_diff_fallback(&mut self, enabled: bool) {
+        self.ast_engine.set_generic_fallback(enabled);
+    }
+
+    // Native builds diff files in parallel; results keep the input order either way
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn compute_ast_diffs(&self, file_changes: &[(String, String, String)]) -> Result<Vec<AstDiff>, ShadowError> {
+        use rayon::prelude::*;
+
+        file_changes.par_iter()
+            .map(|(file_path, old_content, new_content)| self.compute_ast_diff(file_path, old_content, new_content))
+            .collect()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn compute_ast_diffs(&self, file_changes: &[(String, String, String)]) -> Result<Vec<AstDiff>, ShadowError> {
+        file_changes.iter()
+            .map(|(file_path, old_content, new_content)| self.compute_ast_diff(file_path, old_content, new_content))
+            .collect()
+    }
+
+    // The tree compute_ast_diffs compares, for tooling that wants to inspect it. There is
+    // no line-diff fallback here: an extension without a parser is a NoParser error.
+    pub fn parse_file(&self, file_path: &str, content: &str) -> Result<AstNode, ShadowError> {
+        self.ast_engine.parse(file_path, content)
+    }
+
+    fn compute_ast_diff(&self, file_path: &str, old_content: &str, new_content: &str) -> Result<AstDiff, ShadowError> {
+        if !self.ast_engine.has_parser(file_path) {
+            return self.ast_engine.compute_diff(file_path, old_content, new_content);
+        }
+
+        let old_ast = self.parse_cached(file_path, old_content)?;
+        let new_ast = self.ast_engine.parse(file_path, new_content)?;
+        self.ast_engine.diff_parsed(file_path, &old_ast, &new_ast, old_content, new_content)
+    }
+
+    pub fn set_parse_cache_capacity(&mut self, capacity: usize) {
+        self.parse_cache_capacity = capacity;
+        self.parse_cache.lock().unwrap_or_else(PoisonError::into_inner).shrink_to(capacity);
+    }
+
+    pub fn clear_parse_cache(&self) {
+        self.parse_cache.lock().unwrap_or_else(PoisonError::into_inner).shrink_to(0);
+    }
+
+    // Cached paths, least recently used first
+    #[cfg(test)]
+    pub(crate) fn parse_cache_paths(&self) -> Vec<String> {
+        self.parse_cache.lock().unwrap_or_else(PoisonError::into_inner).order.iter().cloned().collect()
+    }
+
+    fn parse_cached(&self, file_path: &str, content: &str) -> Result<AstNode, ShadowError> {
+        if self.parse_cache_capacity == 0 {
+            return self.ast_engine.parse(file_path, content);
+        }
+
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        let content_hash = hasher.finish();
+
+        if let Some(ast) = self.parse_cache.lock().unwrap_or_else(PoisonError::into_inner).get(file_path, content_hash) {
+            return Ok(ast);
+        }
+
+        let ast = self.ast_engine.parse(file_path, content)?;
+        self.parse_cache.lock().unwrap_or_else(PoisonError::into_inner)
+            .insert(file_path, content_hash, ast.clone(), self.parse_cache_capacity);
+
+        Ok(ast)
+    }
+
+    pub fn suggest_semver_bump(&self, file_changes: &[(String, String, String)]) -> SemverBump {
+        crate::semver::suggest_semver_bump(file_changes)
+    }
+
+    pub fn build_dependency_graph(&mut self, workspace_root: &str) -> Result<(), ShadowError> {
+        let mut builder = self.graph_builder(workspace_root);
+        let result = builder.build_graph();
+        self.finish_build(builder, result)
+    }
+
+    pub fn build_dependency_graph_streaming<F>(&mut self, workspace_root: &str, on_node: F) -> Result<(), ShadowError>
+    where
+        F: FnMut(&GraphNode),
+    {
+        let mut builder = self.graph_builder(workspace_root);
+        let result = builder.scan_streaming(on_node);
+        self.finish_build(builder, result)
+    }
+
+    // `on_progress` gets (files scanned, estimated total) every few files and once at the end
+    pub fn build_dependency_graph_with_progress(&mut self, workspace_root: &str, on_progress: &mut dyn FnMut(usize, usize)) -> Result<(), ShadowError> {
+        let mut builder = self.graph_builder(workspace_root);
+        let result = builder.build_graph_with_progress(on_progress);
+        self.finish_build(builder, result)
+    }
+
+    pub fn save_dependency_graph(&self) -> Result<(), ShadowError> {
+        let db = self.db.as_ref().ok_or(ShadowError::NotInitialized)?;
+        let dep_graph = self.dep_graph.as_ref().ok_or(ShadowError::GraphNotBuilt)?;
+
+        let stored = StoredDependencyGraph {
+            workspace_root: dep_graph.workspace_root().to_string(),
+            fingerprint: dep_graph.fingerprint(),
+            graph: dep_graph.get_graph().clone(),
+        };
+        db.put("dep_graph", serde_json::to_vec(&stored)?)?;
+        Ok(())
+    }
+
+    // Reuses the saved graph when it was built for this root and no scanned file has
+    // changed since; otherwise rebuilds and saves. Returns whether the saved graph was used.
+    pub fn load_dependency_graph(&mut self, workspace_root: &str) -> Result<bool, ShadowError> {
+        let db = self.db.as_ref().ok_or(ShadowError::NotInitialized)?;
+        let stored: Option<StoredDependencyGraph> = match db.get("dep_graph")? {
+            // An entry written by an older format is treated like a stale one
+            Some(data) => serde_json::from_slice(&data).ok(),
+            None => None,
+        };
+
+        let mut builder = self.graph_builder(workspace_root);
+        if let Some(stored) = stored {
+            let same_root = normalize_path(&stored.workspace_root) == normalize_path(workspace_root);
+            if same_root && stored.fingerprint == Some(builder.workspace_fingerprint()?) {
+                builder.restore_graph(stored.graph, stored.fingerprint);
+                self.dep_graph = Some(builder);
+                return Ok(true);
+            }
+        }
+
+        let result = builder.build_graph();
+        self.finish_build(builder, result)?;
+        self.save_dependency_graph()?;
+        Ok(false)
+    }
+
+    // Stops the build in progress, or the next one if none is running. A cancelled
+    // build keeps the previous graph, since it is only replaced on success.
+    pub fn cancel(&self) {
+        self.cancel_flag.store(true, Ordering::Relaxed);
+    }
+
+    // For cancelling from another thread while a build holds the engine
+    pub fn cancel_handle(&self) -> Arc<AtomicBool> {
+        self.cancel_flag.clone()
+    }
+
+    fn finish_build(&mut self, builder: DependencyGraphBuilder, result: Result<(), ShadowError>) -> Result<(), ShadowError> {
+        // The build is over either way, so a cancel aimed at it must not stop the next one
+        self.cancel_flag.store(false, Ordering::Relaxed);
+        result?;
         self.dep_graph = Some(builder);
         Ok(())
     }
 
-    pub fn analyze_impact(&self, changed_files: &[String]) -> Result<ImpactAnalysis, Box<dyn std::error::Error>> {
+    fn graph_builder(&self, workspace_root: &str) -> DependencyGraphBuilder {
+        let mut builder = DependencyGraphBuilder::new(workspace_root);
+        builder.set_cancel_flag(self.cancel_flag.clone());
+        builder.set_risk_preset(self.risk_preset);
+        if let Some(thresholds) = self.risk_thresholds {
+            builder.set_risk_thresholds(thresholds);
+        }
+        builder.set_respect_gitignore(self.respect_gitignore);
+        builder.set_follow_symlinks(self.follow_symlinks);
+        builder.set_max_scan_depth(self.max_scan_depth);
+        builder.set_ignore_globs(self.config.ignore_globs.clone());
+        if let Some(tsconfig_path) = &self.tsconfig_path {
+            builder.set_tsconfig_path(tsconfig_path);
+        }
+        builder
+    }
+
+    // Applies to the next workspace scan of both the diff tracker and the dependency graph
+    pub fn set_respect_gitignore(&mut self, enabled: bool) {
+        self.respect_gitignore = enabled;
+        if let Some(diff_tracker) = &mut self.diff_tracker {
+            diff_tracker.set_respect_gitignore(enabled);
+        }
+    }
+
+    // Like set_respect_gitignore, applies to the next scan of both
+    pub fn set_follow_symlinks(&mut self, enabled: bool) {
+        self.follow_symlinks = enabled;
+        if let Some(diff_tracker) = &mut self.diff_tracker {
+            diff_tracker.set_follow_symlinks(enabled);
+        }
+    }
+
+    // Like set_respect_gitignore, applies to the next scan of both
+    pub fn set_max_scan_depth(&mut self, depth: usize) {
+        self.max_scan_depth = depth;
+        if let Some(diff_tracker) = &mut self.diff_tracker {
+            diff_tracker.set_max_scan_depth(depth);
+        }
+    }
+
+    // Directories the diff tracker or the last graph build left out for their depth
+    pub fn too_deep_dirs(&self) -> Vec<String> {
+        let mut dirs: Vec<String> = self.diff_tracker.iter()
+            .flat_map(|diff_tracker| diff_tracker.too_deep_dirs())
+            .chain(self.dep_graph.iter().flat_map(|dep_graph| dep_graph.too_deep_dirs().iter().cloned()))
+            .collect();
+        dirs.sort();
+        dirs.dedup();
+        dirs
+    }
+
+    pub fn set_tracked_extensions(&mut self, exts: Vec<String>) -> Result<(), ShadowError> {
+        match &mut self.diff_tracker {
+            Some(diff_tracker) => diff_tracker.set_tracked_extensions(exts),
+            None => Err(ShadowError::NotInitialized),
+        }
+    }
+
+    pub fn tracked_extensions(&self) -> Vec<String> {
+        match &self.diff_tracker {
+            Some(diff_tracker) => diff_tracker.tracked_extensions().to_vec(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn set_max_file_bytes(&mut self, max_file_bytes: u64) {
+        if let Some(diff_tracker) = &mut self.diff_tracker {
+            diff_tracker.set_max_file_bytes(max_file_bytes);
+        }
+    }
+
+    pub fn set_ignore_whitespace(&mut self, ignore_whitespace: bool, ignore_indentation: bool) {
+        if let Some(diff_tracker) = &mut self.diff_tracker {
+            diff_tracker.set_ignore_whitespace(ignore_whitespace);
+            diff_tracker.set_ignore_indentation(ignore_indentation);
+        }
+    }
+
+    pub fn skipped_files(&self) -> Vec<String> {
+        match &self.diff_tracker {
+            Some(diff_tracker) => diff_tracker.skipped_files(),
+            None => Vec::new(),
+        }
+    }
+
+    // Used for path aliases on the next build_dependency_graph
+    pub fn set_tsconfig_path(&mut self, path: &str) {
+        self.tsconfig_path = Some(path.to_string());
+    }
+
+    pub fn set_risk_preset(&mut self, preset: RiskPreset) {
+        self.risk_preset = preset;
+        self.risk_thresholds = None;
+        if let Some(dep_graph) = &mut self.dep_graph {
+            dep_graph.set_risk_preset(preset);
+        }
+    }
+
+    pub fn set_risk_thresholds(&mut self, low_max: usize, medium_max: usize) -> Result<(), ShadowError> {
+        let thresholds = RiskThresholds::new(low_max, medium_max)?;
+        self.risk_thresholds = Some(thresholds);
+        if let Some(dep_graph) = &mut self.dep_graph {
+            dep_graph.set_risk_thresholds(thresholds);
+        }
+        Ok(())
+    }
+
+    pub fn analyze_impact(&self, changed_files: &[String]) -> Result<ImpactAnalysis, ShadowError> {
         if let Some(dep_graph) = &self.dep_graph {
             Ok(dep_graph.analyze_impact(changed_files))
         } else {
-            Err("Dependency graph not built. Call build_dependency_graph first.".into())
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    // Builds the graph first when there is none yet or it was built for another workspace
+    pub fn analyze_impact_or_build(&mut self, workspace_root: &str, changed_files: &[String]) -> Result<ImpactAnalysis, ShadowError> {
+        let cached = matches!(&self.dep_graph, Some(dep_graph) if normalize_path(dep_graph.workspace_root()) == normalize_path(workspace_root));
+        if !cached {
+            self.build_dependency_graph(workspace_root)?;
         }
+        self.analyze_impact(changed_files)
     }
-}
\ No newline at end of file
+
+    pub fn analyze_impact_with_depth(&self, changed_files: &[String], max_depth: usize) -> Result<ImpactAnalysis, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.analyze_impact_with_depth(changed_files, max_depth))
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn update_graph_file(&mut self, file_path: &str) -> Result<(), ShadowError> {
+        if let Some(dep_graph) = &mut self.dep_graph {
+            dep_graph.update_file(file_path)
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn remove_graph_file(&mut self, file_path: &str) -> Result<(), ShadowError> {
+        if let Some(dep_graph) = &mut self.dep_graph {
+            dep_graph.remove_file(file_path);
+            Ok(())
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn analyze_symbol_impact(&self, changed: &[(String, String)]) -> Result<ImpactAnalysis, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.analyze_symbol_impact(changed))
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn analyze_impact_globs(&self, patterns: &[String]) -> Result<GlobImpactAnalysis, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.analyze_impact_globs(patterns))
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn coupling(&self, dir_a: &str, dir_b: &str) -> Result<Coupling, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.coupling(dir_a, dir_b))
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn compare_sessions(&self, session_a: &str, session_b: &str) -> Result<SessionComparison, ShadowError> {
+        let dep_graph = self.dep_graph.as_ref()
+            .ok_or(ShadowError::GraphNotBuilt)?;
+
+        let changed_a = self.archived_changes(session_a)?;
+        let changed_b = self.archived_changes(session_b)?;
+
+        Ok(SessionComparison::new(
+            dep_graph.analyze_impact(&changed_a),
+            dep_graph.analyze_impact(&changed_b),
+        ))
+    }
+
+    fn archived_changes(&self, session_id: &str) -> Result<Vec<String>, ShadowError> {
+        let db = self.db.as_ref().ok_or(ShadowError::NotInitialized)?;
+        match db.get(format!("session_changes/{}", session_id))? {
+            Some(data) => Ok(serde_json::from_slice(&data)?),
+            None => Err(ShadowError::NotFound(format!("No archived changes for session {}", session_id))),
+        }
+    }
+
+    pub fn session_diffs(&self, session_id: &str) -> Result<Vec<FileDiff>, ShadowError> {
+        let db = self.db.as_ref().ok_or(ShadowError::NotInitialized)?;
+        DiffTracker::load_diffs(db, session_id)
+    }
+
+    pub fn find_cycles(&self) -> Result<Vec<Vec<String>>, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.find_cycles())
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn topological_order(&self) -> Result<Vec<String>, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.topological_order()?)
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn dependency_graph(&self) -> Result<&DependencyGraph, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.get_graph())
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn graph_dot(&self, roots: Option<&[String]>, depth: usize) -> Result<String, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            let graph = dep_graph.get_graph();
+            Ok(match roots {
+                Some(roots) => graph.to_dot_filtered(roots, depth),
+                None => graph.to_dot(),
+            })
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn dependents_of(&self, file: &str) -> Result<Vec<String>, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.dependents_of(file))
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn orphan_files(&self, ignore_entrypoints: &[String]) -> Result<Vec<String>, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.orphan_files(ignore_entrypoints))
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn unused_exports(&self, ignore_entrypoints: &[String]) -> Result<HashMap<String, Vec<String>>, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.unused_exports(ignore_entrypoints))
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn external_dependencies(&self) -> Result<HashMap<String, usize>, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.external_dependencies())
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn transitive_dependents_of(&self, file: &str) -> Result<Vec<String>, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.transitive_dependents_of(file))
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn import_path(&self, from: &str, to: &str) -> Result<Option<Vec<String>>, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.import_path(from, to))
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn reexport_cycles(&self) -> Result<Vec<Vec<String>>, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.reexport_cycles())
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn risk_contributors(&self, changed_files: &[String]) -> Result<Vec<RiskContribution>, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.risk_contributors(changed_files))
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn coupling_metrics(&self) -> Result<HashMap<String, (usize, usize)>, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.coupling_metrics())
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn most_depended_on(&self, n: usize) -> Result<Vec<(String, usize)>, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.most_depended_on(n))
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn graph_metrics(&self) -> Result<GraphMetrics, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.metrics())
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn has_edge(&self, from: &str, to: &str) -> Result<bool, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.has_edge(from, to))
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+
+    pub fn import_mismatches(&self) -> Result<Vec<ImportMismatch>, ShadowError> {
+        if let Some(dep_graph) = &self.dep_graph {
+            Ok(dep_graph.import_mismatches())
+        } else {
+            Err(ShadowError::GraphNotBuilt)
+        }
+    }
+}
+
+fn stored_session(db: &Store) -> Result<Option<Session>, ShadowError> {
+    match db.get("current_session")? {
+        Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,107 @@
+use crate::CycleError;
+
+#[derive(Debug)]
+pub enum ShadowError {
+    NoActiveSession,
+    // `owner_pid` is set when the session belongs to another engine instance
+    SessionAlreadyActive { session_id: String, owner_pid: Option<u32> },
+    NotInitialized,
+    GraphNotBuilt,
+    Cancelled,
+    Cycle(CycleError),
+    NotFound(String),
+    AlreadyExists(String),
+    InvalidInput(String),
+    Io(std::io::Error),
+    Db(String),
+    Serialization(serde_json::Error),
+    Parse(String),
+    NoParser(String),
+}
+
+impl ShadowError {
+    // Stable identifiers for the frontend; never change an existing one
+    pub fn code(&self) -> &'static str {
+        match self {
+            ShadowError::NoActiveSession => "NO_ACTIVE_SESSION",
+            ShadowError::SessionAlreadyActive { .. } => "SESSION_ALREADY_ACTIVE",
+            ShadowError::NotInitialized => "NOT_INITIALIZED",
+            ShadowError::GraphNotBuilt => "GRAPH_NOT_BUILT",
+            ShadowError::Cancelled => "CANCELLED",
+            ShadowError::Cycle(_) => "CYCLE",
+            ShadowError::NotFound(_) => "NOT_FOUND",
+            ShadowError::AlreadyExists(_) => "ALREADY_EXISTS",
+            ShadowError::InvalidInput(_) => "INVALID_INPUT",
+            ShadowError::Io(_) => "IO",
+            ShadowError::Db(_) => "DB",
+            ShadowError::Serialization(_) => "SERIALIZATION",
+            ShadowError::Parse(_) => "PARSE",
+            ShadowError::NoParser(_) => "NO_PARSER",
+        }
+    }
+}
+
+impl std::fmt::Display for ShadowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShadowError::NoActiveSession => write!(f, "No active session"),
+            ShadowError::SessionAlreadyActive { session_id, owner_pid: Some(owner_pid) } => write!(
+                f,
+                "Session {} is already active (started by process {}); pass force to replace it",
+                session_id, owner_pid,
+            ),
+            ShadowError::SessionAlreadyActive { session_id, owner_pid: None } => {
+                write!(f, "Session {} is already active", session_id)
+            }
+            ShadowError::NotInitialized => write!(f, "Engine not initialized"),
+            ShadowError::GraphNotBuilt => {
+                write!(f, "Dependency graph not built. Call build_dependency_graph first.")
+            }
+            ShadowError::Cancelled => write!(f, "Dependency graph build was cancelled"),
+            ShadowError::Cycle(error) => write!(f, "{}", error),
+            ShadowError::NotFound(message)
+            | ShadowError::AlreadyExists(message)
+            | ShadowError::InvalidInput(message)
+            | ShadowError::Parse(message) => write!(f, "{}", message),
+            ShadowError::Io(error) => write!(f, "{}", error),
+            ShadowError::Db(message) => write!(f, "Database error: {}", message),
+            ShadowError::Serialization(error) => write!(f, "{}", error),
+            ShadowError::NoParser(extension) => write!(f, "No parser available for extension: {}", extension),
+        }
+    }
+}
+
+impl std::error::Error for ShadowError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ShadowError::Cycle(error) => Some(error),
+            ShadowError::Io(error) => Some(error),
+            ShadowError::Serialization(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ShadowError {
+    fn from(error: std::io::Error) -> Self {
+        ShadowError::Io(error)
+    }
+}
+
+impl From<rocksdb::Error> for ShadowError {
+    fn from(error: rocksdb::Error) -> Self {
+        ShadowError::Db(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for ShadowError {
+    fn from(error: serde_json::Error) -> Self {
+        ShadowError::Serialization(error)
+    }
+}
+
+impl From<CycleError> for ShadowError {
+    fn from(error: CycleError) -> Self {
+        ShadowError::Cycle(error)
+    }
+}
@@ -0,0 +1,90 @@
+use crate::glob::glob_match;
+use crate::paths::relative_path;
+use std::fs;
+use std::path::Path;
+
+// .gitignore rules collected while walking a workspace. Rules from a nested
+// .gitignore only apply below the directory containing it, and the last
+// matching rule wins so `!pattern` can re-include a path.
+#[derive(Default)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+struct IgnoreRule {
+    // Workspace-relative directory of the .gitignore, empty for the root
+    base: String,
+    pattern: String,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl IgnoreRules {
+    pub fn load(&mut self, root: &Path, dir: &Path) {
+        let Ok(content) = fs::read_to_string(dir.join(".gitignore")) else {
+            return;
+        };
+        let base = relative_to(root, dir);
+
+        for line in content.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+
+            // Patterns with an inner slash are anchored to the .gitignore's directory,
+            // anything else matches at any depth below it
+            let pattern = if line.contains('/') {
+                line.trim_start_matches('/').to_string()
+            } else {
+                format!("**/{}", line)
+            };
+
+            self.rules.push(IgnoreRule {
+                base: base.clone(),
+                pattern,
+                negated,
+                dir_only,
+            });
+        }
+    }
+
+    pub fn is_ignored(&self, root: &Path, path: &Path, is_dir: bool) -> bool {
+        let relative = relative_to(root, path);
+        let mut ignored = false;
+
+        for rule in &self.rules {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+
+            let local = if rule.base.is_empty() {
+                relative.as_str()
+            } else {
+                match relative.strip_prefix(&rule.base).and_then(|rest| rest.strip_prefix('/')) {
+                    Some(rest) => rest,
+                    None => continue,
+                }
+            };
+
+            if glob_match(&rule.pattern, local) {
+                ignored = !rule.negated;
+            }
+        }
+
+        ignored
+    }
+}
+
+fn relative_to(root: &Path, path: &Path) -> String {
+    relative_path(&root.to_string_lossy(), &path.to_string_lossy())
+}
@@ -3,16 +3,27 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 mod engine;
+mod error;
 mod session;
 mod diff;
 mod ast_diff;
 mod dep_graph;
+mod glob;
+mod ignore;
+mod paths;
+mod semver;
+mod store;
+mod walk;
 
 pub use engine::*;
+pub use error::*;
 pub use session::*;
 pub use diff::*;
 pub use ast_diff::*;
 pub use dep_graph::*;
+pub use paths::*;
+pub use semver::*;
+pub use store::*;
 
 #[wasm_bindgen]
 extern "C" {
@@ -29,6 +40,74 @@ pub struct SessionStatus {
     pub is_active: bool,
     pub session_id: Option<String>,
     pub start_time: Option<String>,
+    #[serde(default)]
+    pub metadata: Option<HashMap<String, String>>,
+    // Set when the active session belongs to another engine instance or process
+    #[serde(default)]
+    pub warning: Option<String>,
+}
+
+// Payload passed to listeners registered from JS
+#[cfg(target_arch = "wasm32")]
+#[derive(Serialize)]
+struct SessionEvent<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    session: &'a Session,
+    diffs: &'a [FileDiff],
+}
+
+#[cfg(target_arch = "wasm32")]
+struct JsSessionListener {
+    callback: js_sys::Function,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl JsSessionListener {
+    fn emit(&self, event: &SessionEvent) {
+        if let Ok(value) = serde_wasm_bindgen::to_value(event) {
+            let _ = self.callback.call1(&JsValue::NULL, &value);
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl SessionListener for JsSessionListener {
+    fn on_start(&self, session: &Session) {
+        self.emit(&SessionEvent { kind: "start", session, diffs: &[] });
+    }
+
+    fn on_stop(&self, session: &Session, diffs: &[FileDiff]) {
+        self.emit(&SessionEvent { kind: "stop", session, diffs });
+    }
+}
+
+// Engine errors reach JS as `Error` objects carrying a stable `code` to match on
+fn js_error(error: ShadowError) -> JsValue {
+    let js_error = js_sys::Error::new(&error.to_string());
+    let _ = js_sys::Reflect::set(&js_error, &JsValue::from_str("code"), &JsValue::from_str(error.code()));
+    js_error.into()
+}
+
+// Scans stop descending at the max scan depth; say so rather than silently miss files
+fn warn_too_deep(engine: &engine::Engine) {
+    let dirs = engine.too_deep_dirs();
+    if !dirs.is_empty() {
+        console_log!("shadow: skipped {} directories deeper than the max scan depth: {}", dirs.len(), dirs.join(", "));
+    }
+}
+
+#[wasm_bindgen]
+pub struct CancelHandle {
+    flag: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+#[wasm_bindgen]
+impl CancelHandle {
+    #[wasm_bindgen]
+    pub fn cancel(&self) {
+        self.flag.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
 }
 
 #[wasm_bindgen]
@@ -38,73 +117,638 @@ pub struct ShadowEngine {
 
 #[wasm_bindgen]
 impl ShadowEngine {
+    // `config` is an EngineConfig object; omitted fields keep their defaults
     #[wasm_bindgen(constructor)]
-    pub fn new() -> ShadowEngine {
-        ShadowEngine {
-            engine: engine::Engine::new(),
-        }
+    pub fn new(config: Option<JsValue>) -> Result<ShadowEngine, JsValue> {
+        let config: EngineConfig = match config {
+            Some(config) if !config.is_null() && !config.is_undefined() => serde_wasm_bindgen::from_value(config)
+                .map_err(|e| JsValue::from_str(&e.to_string()))?,
+            _ => EngineConfig::default(),
+        };
+
+        Ok(ShadowEngine {
+            engine: engine::Engine::with_config(config).map_err(js_error)?,
+        })
     }
 
     #[wasm_bindgen]
     pub async fn initialize(&mut self, shadow_dir: &str) -> Result<(), JsValue> {
         self.engine.initialize(shadow_dir)
-            .map_err(|e| JsValue::from_str(&e.to_string()))
+            .map_err(js_error)
     }
 
     #[wasm_bindgen]
-    pub async fn start_session(&mut self) -> Result<String, JsValue> {
-        self.engine.start_session()
-            .map_err(|e| JsValue::from_str(&e.to_string()))
+    pub async fn initialize_in_memory(&mut self, shadow_dir: &str) {
+        self.engine.initialize_in_memory(shadow_dir);
+    }
+
+    #[wasm_bindgen]
+    pub async fn start_session(&mut self, force: Option<bool>) -> Result<String, JsValue> {
+        let session_id = self.engine.start_session_with_force(force.unwrap_or(false))
+            .map_err(js_error)?;
+        warn_too_deep(&self.engine);
+        Ok(session_id)
     }
 
     #[wasm_bindgen]
     pub async fn stop_session(&mut self) -> Result<(), JsValue> {
         self.engine.stop_session()
-            .map_err(|e| JsValue::from_str(&e.to_string()))
+            .map_err(js_error)
+    }
+
+    // Calls `callback` with `{ type: "start" | "stop", session, diffs }`. JS functions
+    // can't cross threads, so this only exists in wasm builds.
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen]
+    pub fn register_listener(&mut self, callback: js_sys::Function) {
+        self.engine.register_listener(Box::new(JsSessionListener { callback }));
     }
 
     #[wasm_bindgen]
     pub async fn get_status(&self) -> Result<JsValue, JsValue> {
         let status = self.engine.get_status()
-            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+            .map_err(js_error)?;
         
         serde_wasm_bindgen::to_value(&status)
             .map_err(|e| JsValue::from_str(&e.to_string()))
     }
 
+    #[wasm_bindgen]
+    pub async fn set_session_metadata(&mut self, key: &str, value: &str) -> Result<(), JsValue> {
+        self.engine.set_session_metadata(key, value)
+            .map_err(js_error)
+    }
+
+    #[wasm_bindgen]
+    pub async fn list_sessions(&self) -> Result<JsValue, JsValue> {
+        let sessions = self.engine.list_sessions()
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&sessions)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn session_elapsed_seconds(&self) -> Result<Option<f64>, JsValue> {
+        self.engine.session_elapsed_seconds()
+            .map(|elapsed| elapsed.map(|seconds| seconds as f64))
+            .map_err(js_error)
+    }
+
+    #[wasm_bindgen]
+    pub async fn get_session_duration_secs(&self) -> Result<Option<f64>, JsValue> {
+        self.engine.elapsed_session_time()
+            .map(|duration| duration.map(|duration| duration.as_secs_f64()))
+            .map_err(js_error)
+    }
+
     #[wasm_bindgen]
     pub async fn get_diff_count(&self) -> Result<u32, JsValue> {
         self.engine.get_diff_count()
+            .map_err(js_error)
+    }
+
+    #[wasm_bindgen]
+    pub async fn get_file_diff(&self, path: &str) -> Result<JsValue, JsValue> {
+        let diff = self.engine.get_file_diff(path)
+            .map_err(js_error)?;
+
+        match diff {
+            Some(diff) => serde_wasm_bindgen::to_value(&diff)
+                .map_err(|e| JsValue::from_str(&e.to_string())),
+            None => Ok(JsValue::NULL),
+        }
+    }
+
+    #[wasm_bindgen]
+    pub async fn get_all_diffs(&self) -> Result<JsValue, JsValue> {
+        let diffs = self.engine.get_all_diffs()
+            .map_err(js_error)?;
+
+        serde_wasm_bindgen::to_value(&diffs)
             .map_err(|e| JsValue::from_str(&e.to_string()))
     }
 
+    #[wasm_bindgen]
+    pub async fn capture_changes(&mut self) -> Result<(), JsValue> {
+        self.engine.capture_changes()
+            .map_err(js_error)
+    }
+
+    #[wasm_bindgen]
+    pub async fn get_changed_files(&self) -> Result<JsValue, JsValue> {
+        let files = self.engine.get_changed_files()
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&files)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn checkpoint(&mut self, name: &str) -> Result<(), JsValue> {
+        self.engine.checkpoint(name)
+            .map_err(js_error)
+    }
+
+    #[wasm_bindgen]
+    pub async fn diff_directories(&self, old_root: &str, new_root: &str) -> Result<JsValue, JsValue> {
+        let result = self.engine.diff_directories(old_root, new_root)
+            .map_err(js_error)?;
+
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn create_snapshot(&self, name: &str) -> Result<(), JsValue> {
+        self.engine.create_snapshot(name)
+            .map_err(js_error)
+    }
+
+    #[wasm_bindgen]
+    pub async fn restore_snapshot(&self, name: &str) -> Result<JsValue, JsValue> {
+        let overwritten = self.engine.restore_snapshot(name)
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&overwritten)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn list_snapshots(&self) -> Result<JsValue, JsValue> {
+        let names = self.engine.list_snapshots()
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&names)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn diff_against(&self, name: &str) -> Result<JsValue, JsValue> {
+        let diffs = self.engine.diff_against(name)
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&diffs)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn activity_timeline(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.engine.activity_timeline())
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn diffs_csv(&self) -> String {
+        self.engine.diffs_csv()
+    }
+
+    #[wasm_bindgen]
+    pub async fn session_patch(&self) -> String {
+        self.engine.session_patch()
+    }
+
+    #[wasm_bindgen]
+    pub async fn unified_diff(&self, path: &str, context: Option<u32>) -> Option<String> {
+        self.engine.unified_diff(path, context.map(|context| context as usize))
+    }
+
+    #[wasm_bindgen]
+    pub async fn set_diff_context_lines(&mut self, context_lines: u32) {
+        self.engine.set_diff_context_lines(context_lines as usize);
+    }
+
+    #[wasm_bindgen]
+    pub async fn set_generic_diff_fallback(&mut self, enabled: bool) {
+        self.engine.set_generic_diff_fallback(enabled);
+    }
+
     #[wasm_bindgen]
     pub async fn compute_ast_diffs(&self, files: JsValue) -> Result<JsValue, JsValue> {
         let file_changes: Vec<(String, String, String)> = serde_wasm_bindgen::from_value(files)
             .map_err(|e| JsValue::from_str(&e.to_string()))?;
         
         let result = self.engine.compute_ast_diffs(&file_changes)
-            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+            .map_err(js_error)?;
         
         serde_wasm_bindgen::to_value(&result)
             .map_err(|e| JsValue::from_str(&e.to_string()))
     }
 
+    #[wasm_bindgen]
+    pub fn parse_file(&self, path: &str, content: &str) -> Result<JsValue, JsValue> {
+        let ast = self.engine.parse_file(path, content)
+            .map_err(js_error)?;
+
+        serde_wasm_bindgen::to_value(&ast)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn set_parse_cache_capacity(&mut self, capacity: u32) {
+        self.engine.set_parse_cache_capacity(capacity as usize);
+    }
+
+    #[wasm_bindgen]
+    pub async fn clear_parse_cache(&self) {
+        self.engine.clear_parse_cache();
+    }
+
+    #[wasm_bindgen]
+    pub async fn suggest_semver_bump(&self, files: JsValue) -> Result<JsValue, JsValue> {
+        let file_changes: Vec<(String, String, String)> = serde_wasm_bindgen::from_value(files)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        
+        serde_wasm_bindgen::to_value(&self.engine.suggest_semver_bump(&file_changes))
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn set_respect_gitignore(&mut self, enabled: bool) {
+        self.engine.set_respect_gitignore(enabled);
+    }
+
+    #[wasm_bindgen]
+    pub async fn set_follow_symlinks(&mut self, enabled: bool) {
+        self.engine.set_follow_symlinks(enabled);
+    }
+
+    #[wasm_bindgen]
+    pub async fn set_max_scan_depth(&mut self, depth: u32) {
+        self.engine.set_max_scan_depth(depth as usize);
+    }
+
+    #[wasm_bindgen]
+    pub async fn too_deep_dirs(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.engine.too_deep_dirs())
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn set_tracked_extensions(&mut self, exts: JsValue) -> Result<(), JsValue> {
+        let exts: Vec<String> = serde_wasm_bindgen::from_value(exts)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        
+        self.engine.set_tracked_extensions(exts)
+            .map_err(js_error)
+    }
+
+    #[wasm_bindgen]
+    pub async fn tracked_extensions(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.engine.tracked_extensions())
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn set_max_file_bytes(&mut self, max_file_bytes: f64) {
+        self.engine.set_max_file_bytes(max_file_bytes as u64);
+    }
+
+    #[wasm_bindgen]
+    pub async fn set_ignore_whitespace(&mut self, ignore_whitespace: bool, ignore_indentation: bool) {
+        self.engine.set_ignore_whitespace(ignore_whitespace, ignore_indentation);
+    }
+
+    #[wasm_bindgen]
+    pub async fn skipped_files(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.engine.skipped_files())
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn set_tsconfig_path(&mut self, path: &str) {
+        self.engine.set_tsconfig_path(path);
+    }
+
+    #[wasm_bindgen]
+    pub async fn set_risk_preset(&mut self, preset: &str) -> Result<(), JsValue> {
+        let preset = match preset {
+            "default" => RiskPreset::Default,
+            "conservative" => RiskPreset::Conservative,
+            "lenient" => RiskPreset::Lenient,
+            other => return Err(JsValue::from_str(&format!("Unknown risk preset: {}", other))),
+        };
+        
+        self.engine.set_risk_preset(preset);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub async fn set_risk_thresholds(&mut self, low_max: u32, medium_max: u32) -> Result<(), JsValue> {
+        self.engine.set_risk_thresholds(low_max as usize, medium_max as usize)
+            .map_err(js_error)
+    }
+
     #[wasm_bindgen]
     pub async fn analyze_impact(&self, changed_files: JsValue) -> Result<JsValue, JsValue> {
         let files: Vec<String> = serde_wasm_bindgen::from_value(changed_files)
             .map_err(|e| JsValue::from_str(&e.to_string()))?;
         
         let result = self.engine.analyze_impact(&files)
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn analyze_impact_or_build(&mut self, workspace_root: &str, changed_files: JsValue) -> Result<JsValue, JsValue> {
+        let files: Vec<String> = serde_wasm_bindgen::from_value(changed_files)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        let result = self.engine.analyze_impact_or_build(workspace_root, &files)
+            .map_err(js_error)?;
+
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn analyze_impact_with_depth(&self, changed_files: JsValue, max_depth: u32) -> Result<JsValue, JsValue> {
+        let files: Vec<String> = serde_wasm_bindgen::from_value(changed_files)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        
+        let result = self.engine.analyze_impact_with_depth(&files, max_depth as usize)
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn update_graph_file(&mut self, file_path: &str) -> Result<(), JsValue> {
+        self.engine.update_graph_file(file_path)
+            .map_err(js_error)
+    }
+
+    #[wasm_bindgen]
+    pub async fn remove_graph_file(&mut self, file_path: &str) -> Result<(), JsValue> {
+        self.engine.remove_graph_file(file_path)
+            .map_err(js_error)
+    }
+
+    #[wasm_bindgen]
+    pub async fn analyze_symbol_impact(&self, changed: JsValue) -> Result<JsValue, JsValue> {
+        let changed: Vec<(String, String)> = serde_wasm_bindgen::from_value(changed)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        
+        let result = self.engine.analyze_symbol_impact(&changed)
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn analyze_impact_globs(&self, patterns: JsValue) -> Result<JsValue, JsValue> {
+        let patterns: Vec<String> = serde_wasm_bindgen::from_value(patterns)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        
+        let result = self.engine.analyze_impact_globs(&patterns)
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn coupling(&self, dir_a: &str, dir_b: &str) -> Result<JsValue, JsValue> {
+        let result = self.engine.coupling(dir_a, dir_b)
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn build_dependency_graph(&mut self, workspace_root: &str, on_progress: Option<js_sys::Function>) -> Result<(), JsValue> {
+        let result = match on_progress {
+            Some(on_progress) => self.engine.build_dependency_graph_with_progress(workspace_root, &mut |scanned, total| {
+                let _ = on_progress.call2(&JsValue::NULL, &JsValue::from(scanned as u32), &JsValue::from(total as u32));
+            }),
+            None => self.engine.build_dependency_graph(workspace_root),
+        };
+        result.map_err(js_error)?;
+        warn_too_deep(&self.engine);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub async fn save_dependency_graph(&self) -> Result<(), JsValue> {
+        self.engine.save_dependency_graph().map_err(js_error)
+    }
+
+    // Resolves to true when the saved graph was still current and no rescan was needed
+    #[wasm_bindgen]
+    pub async fn load_dependency_graph(&mut self, workspace_root: &str) -> Result<bool, JsValue> {
+        self.engine.load_dependency_graph(workspace_root).map_err(js_error)
+    }
+
+    #[wasm_bindgen]
+    pub fn cancel(&self) {
+        self.engine.cancel();
+    }
+
+    // Cancels without borrowing the engine, which a pending build still holds
+    #[wasm_bindgen]
+    pub fn cancel_handle(&self) -> CancelHandle {
+        CancelHandle { flag: self.engine.cancel_handle() }
+    }
+
+    #[wasm_bindgen]
+    pub async fn build_dependency_graph_streaming(&mut self, workspace_root: &str, on_node: js_sys::Function) -> Result<(), JsValue> {
+        self.engine.build_dependency_graph_streaming(workspace_root, |node| {
+            if let Ok(value) = serde_wasm_bindgen::to_value(node) {
+                let _ = on_node.call1(&JsValue::NULL, &value);
+            }
+        })
+        .map_err(js_error)
+    }
+
+    #[wasm_bindgen]
+    pub async fn session_summary(&self) -> Result<JsValue, JsValue> {
+        let result = self.engine.session_summary()
+            .map_err(js_error)?;
+
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn compare_sessions(&self, session_a: &str, session_b: &str) -> Result<JsValue, JsValue> {
+        let result = self.engine.compare_sessions(session_a, session_b)
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn session_diffs(&self, session_id: &str) -> Result<JsValue, JsValue> {
+        let result = self.engine.session_diffs(session_id)
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn find_cycles(&self) -> Result<JsValue, JsValue> {
+        let result = self.engine.find_cycles()
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn topological_order(&self) -> Result<JsValue, JsValue> {
+        let result = self.engine.topological_order()
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn get_dependency_graph(&self) -> Result<JsValue, JsValue> {
+        let graph = self.engine.dependency_graph()
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(graph)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn graph_dot(&self, roots: JsValue, depth: u32) -> Result<String, JsValue> {
+        let roots: Option<Vec<String>> = serde_wasm_bindgen::from_value(roots)
             .map_err(|e| JsValue::from_str(&e.to_string()))?;
         
+        self.engine.graph_dot(roots.as_deref(), depth as usize)
+            .map_err(js_error)
+    }
+
+    #[wasm_bindgen]
+    pub async fn dependents_of(&self, file: &str) -> Result<JsValue, JsValue> {
+        let result = self.engine.dependents_of(file)
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn orphan_files(&self, ignore_entrypoints: JsValue) -> Result<JsValue, JsValue> {
+        let entrypoints: Vec<String> = serde_wasm_bindgen::from_value(ignore_entrypoints)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        let result = self.engine.orphan_files(&entrypoints)
+            .map_err(js_error)?;
+
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn unused_exports(&self, ignore_entrypoints: JsValue) -> Result<JsValue, JsValue> {
+        let entrypoints: Vec<String> = serde_wasm_bindgen::from_value(ignore_entrypoints)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        let result = self.engine.unused_exports(&entrypoints)
+            .map_err(js_error)?;
+
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn external_dependencies(&self) -> Result<JsValue, JsValue> {
+        let result = self.engine.external_dependencies()
+            .map_err(js_error)?;
+
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn transitive_dependents_of(&self, file: &str) -> Result<JsValue, JsValue> {
+        let result = self.engine.transitive_dependents_of(file)
+            .map_err(js_error)?;
+        
         serde_wasm_bindgen::to_value(&result)
             .map_err(|e| JsValue::from_str(&e.to_string()))
     }
 
+    // The chain of files from `from` to `to`, or null when `to` isn't reachable
     #[wasm_bindgen]
-    pub async fn build_dependency_graph(&mut self, workspace_root: &str) -> Result<(), JsValue> {
-        self.engine.build_dependency_graph(workspace_root)
+    pub async fn import_path(&self, from: &str, to: &str) -> Result<JsValue, JsValue> {
+        let result = self.engine.import_path(from, to)
+            .map_err(js_error)?;
+
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn reexport_cycles(&self) -> Result<JsValue, JsValue> {
+        let result = self.engine.reexport_cycles()
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn risk_contributors(&self, changed_files: JsValue) -> Result<JsValue, JsValue> {
+        let files: Vec<String> = serde_wasm_bindgen::from_value(changed_files)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        
+        let result = self.engine.risk_contributors(&files)
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn coupling_metrics(&self) -> Result<JsValue, JsValue> {
+        let result = self.engine.coupling_metrics()
+            .map_err(js_error)?;
+
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn most_depended_on(&self, n: u32) -> Result<JsValue, JsValue> {
+        let result = self.engine.most_depended_on(n as usize)
+            .map_err(js_error)?;
+
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn graph_metrics(&self) -> Result<JsValue, JsValue> {
+        let result = self.engine.graph_metrics()
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&result)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen]
+    pub async fn has_edge(&self, from: &str, to: &str) -> Result<bool, JsValue> {
+        self.engine.has_edge(from, to)
+            .map_err(js_error)
+    }
+
+    #[wasm_bindgen]
+    pub async fn import_mismatches(&self) -> Result<JsValue, JsValue> {
+        let result = self.engine.import_mismatches()
+            .map_err(js_error)?;
+        
+        serde_wasm_bindgen::to_value(&result)
             .map_err(|e| JsValue::from_str(&e.to_string()))
     }
 }
\ No newline at end of file
@@ -0,0 +1,81 @@
+// One spelling per logical file, so the same path compares equal whichever OS or caller
+// produced it: forward slashes, a lowercase drive letter, no empty or `.` segments, and
+// `..` collapsed into its parent. A relative path climbing above its start keeps the `..`.
+pub fn normalize_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let (root, rest) = split_root(&path);
+
+    let mut segments: Vec<&str> = Vec::new();
+    for part in rest.split('/') {
+        match part {
+            "" | "." => {}
+            ".." if segments.last().is_none_or(|segment| *segment == "..") => {
+                // There is nothing above the root of an absolute path
+                if root.is_empty() {
+                    segments.push(part);
+                }
+            }
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(part),
+        }
+    }
+
+    format!("{}{}", root, segments.join("/"))
+}
+
+// `path` relative to `root`, both normalized first. A path outside `root` comes back
+// normalized but otherwise unchanged. Drive-letter roots match case-insensitively, as
+// Windows does.
+pub(crate) fn relative_path(root: &str, path: &str) -> String {
+    let root = normalize_path(root);
+    let path = normalize_path(path);
+    if root.is_empty() {
+        return path;
+    }
+
+    let under_root = if has_drive(&root) {
+        path.get(..root.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(&root))
+    } else {
+        path.starts_with(&root)
+    };
+    if !under_root {
+        return path;
+    }
+
+    let rest = &path[root.len()..];
+    if root.ends_with('/') {
+        rest.to_string()
+    } else if rest.is_empty() {
+        String::new()
+    } else if let Some(rest) = rest.strip_prefix('/') {
+        rest.to_string()
+    } else {
+        // `/work/app2` merely shares a prefix with `/work/app`
+        path
+    }
+}
+
+// Splits off `/`, `//` (UNC) or a drive like `c:/`, lowercasing the drive letter
+fn split_root(path: &str) -> (String, &str) {
+    if has_drive(path) {
+        let drive = path[..1].to_ascii_lowercase();
+        return match path[2..].strip_prefix('/') {
+            Some(rest) => (format!("{}:/", drive), rest),
+            None => (format!("{}:", drive), &path[2..]),
+        };
+    }
+    if let Some(rest) = path.strip_prefix("//") {
+        return ("//".to_string(), rest);
+    }
+    match path.strip_prefix('/') {
+        Some(rest) => ("/".to_string(), rest),
+        None => (String::new(), path),
+    }
+}
+
+fn has_drive(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
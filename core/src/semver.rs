@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SemverBump {
+    Patch,
+    Minor,
+    Major,
+}
+
+pub fn suggest_semver_bump(file_changes: &[(String, String, String)]) -> SemverBump {
+    // The most severe change in any file decides the overall bump
+    file_changes.iter()
+        .map(|(_, old_content, new_content)| file_bump(old_content, new_content))
+        .max()
+        .unwrap_or(SemverBump::Patch)
+}
+
+fn file_bump(old_content: &str, new_content: &str) -> SemverBump {
+    let old_api = export_signatures(old_content);
+    let new_api = export_signatures(new_content);
+
+    // Removed, renamed or re-declared exports break consumers
+    if old_api.iter().any(|(name, signature)| new_api.get(name) != Some(signature)) {
+        SemverBump::Major
+    } else if new_api.keys().any(|name| !old_api.contains_key(name)) {
+        SemverBump::Minor
+    } else {
+        SemverBump::Patch
+    }
+}
+
+// Maps each exported name to its whitespace-normalized declaration signature
+fn export_signatures(content: &str) -> HashMap<String, String> {
+    let mut signatures = HashMap::new();
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        let Some(declaration) = trimmed.strip_prefix("export ") else {
+            continue;
+        };
+
+        // Export lists only expose names: `export { a, b as c }`
+        if declaration.starts_with('{') {
+            let list = declaration.trim_start_matches('{').split('}').next().unwrap_or("");
+            for entry in list.split(',') {
+                if let Some(name) = entry.split_whitespace().last() {
+                    signatures.insert(name.to_string(), String::new());
+                }
+            }
+            continue;
+        }
+
+        if declaration.starts_with("default") {
+            signatures.insert("default".to_string(), normalize_signature(declaration, '{'));
+            continue;
+        }
+
+        let tokens: Vec<&str> = declaration.split_whitespace().collect();
+        let Some(keyword_pos) = tokens.iter().position(|token| {
+            matches!(*token, "function" | "function*" | "class" | "interface" | "type" | "enum" | "const" | "let" | "var")
+        }) else {
+            continue;
+        };
+
+        let Some(name) = tokens.get(keyword_pos + 1)
+            .and_then(|token| token.split(['(', '<', ':', '=', '{', ';']).next())
+            .filter(|name| !name.is_empty())
+        else {
+            continue;
+        };
+
+        // Variables are compared by name and type annotation only, not by value
+        let terminator = match tokens[keyword_pos] {
+            "const" | "let" | "var" => '=',
+            "type" => ';',
+            _ => '{',
+        };
+        signatures.insert(name.to_string(), normalize_signature(declaration, terminator));
+    }
+
+    signatures
+}
+
+fn normalize_signature(declaration: &str, terminator: char) -> String {
+    declaration.split(terminator).next()
+        .unwrap_or("")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -1,12 +1,56 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use uuid::Uuid;
 
+use crate::{DiffStats, FileDiff, ImpactAnalysis, ShadowError};
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Session {
     pub id: String,
     pub start_time: DateTime<Utc>,
     pub workspace_path: String,
+    // Set when the session is stopped
+    #[serde(default)]
+    pub end_time: Option<DateTime<Utc>>,
+    // Free-form annotations such as branch, PR number or commit SHA
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+    // Which process and engine instance started the session
+    #[serde(default)]
+    pub owner_pid: u32,
+    #[serde(default)]
+    pub instance_id: String,
+}
+
+// Observer for session lifecycle events, e.g. to kick off a build when a session stops
+pub trait SessionListener {
+    fn on_start(&self, session: &Session);
+    fn on_stop(&self, session: &Session, diffs: &[FileDiff]);
+}
+
+// Native builds share the engine across threads when diffing, so its listeners must be too
+#[cfg(not(target_arch = "wasm32"))]
+pub type BoxedSessionListener = Box<dyn SessionListener + Send + Sync>;
+#[cfg(target_arch = "wasm32")]
+pub type BoxedSessionListener = Box<dyn SessionListener>;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SessionSummary {
+    pub files_changed: usize,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    pub ast_stats: DiffStats,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SessionComparison {
+    pub session_a: ImpactAnalysis,
+    pub session_b: ImpactAnalysis,
+    pub changed_only_in_a: Vec<String>,
+    pub changed_only_in_b: Vec<String>,
+    pub impacted_only_in_a: Vec<String>,
+    pub impacted_only_in_b: Vec<String>,
 }
 
 impl Session {
@@ -18,6 +62,47 @@ impl Session {
                 .unwrap_or_default()
                 .to_string_lossy()
                 .to_string(),
+            end_time: None,
+            metadata: HashMap::new(),
+            owner_pid: std::process::id(),
+            instance_id: String::new(),
         }
     }
-}
\ No newline at end of file
+
+    pub fn validate_workspace(&self) -> Result<(), ShadowError> {
+        let workspace = std::path::Path::new(&self.workspace_path);
+        if !workspace.exists() {
+            return Err(ShadowError::InvalidInput(format!("Workspace path does not exist: {}", self.workspace_path)));
+        }
+        if !workspace.is_dir() {
+            return Err(ShadowError::InvalidInput(format!("Workspace path is not a directory: {}", self.workspace_path)));
+        }
+        Ok(())
+    }
+
+    pub fn duration_seconds(&self) -> Option<i64> {
+        self.end_time.map(|end_time| (end_time - self.start_time).num_seconds())
+    }
+}
+
+impl SessionComparison {
+    pub fn new(session_a: ImpactAnalysis, session_b: ImpactAnalysis) -> Self {
+        SessionComparison {
+            changed_only_in_a: difference(&session_a.changed_files, &session_b.changed_files),
+            changed_only_in_b: difference(&session_b.changed_files, &session_a.changed_files),
+            impacted_only_in_a: difference(&session_a.impacted_files, &session_b.impacted_files),
+            impacted_only_in_b: difference(&session_b.impacted_files, &session_a.impacted_files),
+            session_a,
+            session_b,
+        }
+    }
+}
+
+fn difference(left: &[String], right: &[String]) -> Vec<String> {
+    let mut only_left: Vec<String> = left.iter()
+        .filter(|file| !right.contains(file))
+        .cloned()
+        .collect();
+    only_left.sort();
+    only_left
+}
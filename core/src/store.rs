@@ -0,0 +1,84 @@
+use rocksdb::{DB, Options};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+
+use crate::ShadowError;
+
+pub type StoreEntry = (Vec<u8>, Vec<u8>);
+
+// Key-value backend for session state. The in-memory map is ordered like RocksDB
+// so prefix scans return keys in the same order on both backends.
+pub enum Store {
+    RocksDb(DB),
+    Memory(Mutex<BTreeMap<Vec<u8>, Vec<u8>>>),
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Self, ShadowError> {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+
+        Ok(Store::RocksDb(DB::open(&opts, path)?))
+    }
+
+    pub fn in_memory() -> Self {
+        Store::Memory(Mutex::new(BTreeMap::new()))
+    }
+
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<Vec<u8>>, ShadowError> {
+        match self {
+            Store::RocksDb(db) => Ok(db.get(key)?),
+            Store::Memory(map) => Ok(map.lock().unwrap_or_else(PoisonError::into_inner).get(key.as_ref()).cloned()),
+        }
+    }
+
+    pub fn put<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V) -> Result<(), ShadowError> {
+        match self {
+            Store::RocksDb(db) => db.put(key, value)?,
+            Store::Memory(map) => {
+                map.lock().unwrap_or_else(PoisonError::into_inner)
+                    .insert(key.as_ref().to_vec(), value.as_ref().to_vec());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn delete<K: AsRef<[u8]>>(&self, key: K) -> Result<(), ShadowError> {
+        match self {
+            Store::RocksDb(db) => db.delete(key)?,
+            Store::Memory(map) => {
+                map.lock().unwrap_or_else(PoisonError::into_inner).remove(key.as_ref());
+            }
+        }
+        Ok(())
+    }
+
+    // All (key, value) pairs whose key starts with `prefix`, in key order
+    pub fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<StoreEntry>, ShadowError> {
+        let mut entries = Vec::new();
+
+        match self {
+            Store::RocksDb(db) => {
+                for item in db.prefix_iterator(prefix) {
+                    let (key, value) = item?;
+                    if !key.starts_with(prefix) {
+                        break;
+                    }
+                    entries.push((key.to_vec(), value.to_vec()));
+                }
+            }
+            Store::Memory(map) => {
+                let map = map.lock().unwrap_or_else(PoisonError::into_inner);
+                for (key, value) in map.range(prefix.to_vec()..) {
+                    if !key.starts_with(prefix) {
+                        break;
+                    }
+                    entries.push((key.clone(), value.clone()));
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Scans are recursive, so this also bounds their stack use
+pub(crate) const DEFAULT_MAX_SCAN_DEPTH: usize = 64;
+
+// Decides which directories a workspace scan descends into. Symlinked directories are
+// skipped unless following is enabled; then each real directory is entered only once,
+// so a link pointing back up the tree can't recurse forever. Directories more than
+// `max_depth` levels below the root are skipped and remembered.
+pub(crate) struct DirWalk {
+    root: PathBuf,
+    follow_symlinks: bool,
+    max_depth: usize,
+    visited: HashSet<PathBuf>,
+    too_deep: Vec<PathBuf>,
+}
+
+impl DirWalk {
+    pub(crate) fn new(root: &Path, follow_symlinks: bool, max_depth: usize) -> Self {
+        let mut visited = HashSet::new();
+        if follow_symlinks {
+            if let Ok(real) = fs::canonicalize(root) {
+                visited.insert(real);
+            }
+        }
+        DirWalk { root: root.to_path_buf(), follow_symlinks, max_depth, visited, too_deep: Vec::new() }
+    }
+
+    pub(crate) fn enter(&mut self, dir: &Path) -> bool {
+        if !self.follow_symlinks && fs::symlink_metadata(dir).is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+            return false;
+        }
+
+        let depth = dir.strip_prefix(&self.root).map_or(0, |relative| relative.components().count());
+        if depth > self.max_depth {
+            self.too_deep.push(dir.to_path_buf());
+            return false;
+        }
+
+        if !self.follow_symlinks {
+            return true;
+        }
+        match fs::canonicalize(dir) {
+            Ok(real) => self.visited.insert(real),
+            Err(_) => false,
+        }
+    }
+
+    pub(crate) fn too_deep(&self) -> &[PathBuf] {
+        &self.too_deep
+    }
+}
+
+// The entries of `dir` in path order, so which of two routes to a linked directory
+// gets entered first doesn't depend on the order the filesystem lists them in
+pub(crate) fn sorted_entries(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<PathBuf>>>()?;
+    entries.sort();
+    Ok(entries)
+}